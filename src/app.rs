@@ -1,30 +1,70 @@
 //! Implements the `paper` application logic for converting an [`Input`] into [`Output`]s.
+mod command;
+mod finder;
+mod highlight;
+mod layout;
+mod message;
+mod prompt;
+mod tiling;
 mod translate;
+mod view;
 
 use {
     crate::io::{
-        config::Setting,
+        config::{Charset, Configuration, Setting},
         fs::{File, Purl},
-        ui::{Dimensions, Unit},
-        DocEdit, Input, LanguageId, Output,
+        history::History,
+        lsp::{ClientMessage, DocConfiguration, DocMessage, InlayHint, TokenSpan, ToolMessage},
+        ui::{Dimensions, Row, Scope},
+        DocEdit, Input, LanguageId, Output, SaveOutcome,
     },
+    command::LineCommand,
+    core::convert::TryFrom,
+    fehler::{throw, throws},
+    finder::Finder,
+    highlight::Highlights,
     log::trace,
     lsp_types::{MessageType, ShowMessageParams, ShowMessageRequestParams},
-    std::{cell::RefCell, mem, rc::Rc},
-    translate::{Command, DocOp, Interpreter, Operation},
+    message::MessageLog,
+    parse_display::Display as ParseDisplay,
+    prompt::Prompt,
+    std::{cell::RefCell, collections::HashMap, env, mem, path::Path, rc::Rc, time::Instant},
+    tiling::{Area, Tiling},
+    translate::{Command, ConfirmAction, DocOp, Interpreter, Operation, PaneOp, Switch},
+    unicode_width::UnicodeWidthStr,
+    url::Url,
+    view::{Appearance, Cursor, View},
 };
 
 /// The processor of the application.
 #[derive(Debug, Default)]
 pub(crate) struct Processor {
-    /// The currently visible pane.
-    pane: Pane,
+    /// The panes that divide the page.
+    panes: Tiling<Pane>,
+    /// The open documents, in the order in which they were opened.
+    buffers: Vec<Document>,
+    /// The [`Dimensions`] of the page.
+    size: Dimensions,
+    /// The configuration of the application.
+    config: Configuration,
     /// The input of a command.
-    input: String,
+    prompt: Prompt,
+    /// The input previously given to each command.
+    history: History,
+    /// Finds the files of the project that match the input of an open command.
+    finder: Finder,
     /// The current command to be implemented.
     command: Option<Command>,
     /// Translates input into operations.
     interpreter: Interpreter,
+    /// The state of the language server of each language.
+    servers: HashMap<LanguageId, ServerState>,
+    /// The history of messages.
+    messages: MessageLog,
+    /// If the application quits once every document with unsaved changes has been saved.
+    is_quitting: bool,
+    /// If the index of the files of the project omits files because the project has too many.
+    is_index_truncated: bool,
 }
 
 impl Processor {
@@ -34,12 +74,16 @@ impl Processor {
     }
 
     /// Processes `input` and generates [`Output`].
+    ///
+    /// Each [`Operation`] that `input` translates into is performed in order.
     pub(crate) fn process(&mut self, input: Input) -> Vec<Output> {
-        if let Some(operation) = self.interpreter.translate(input) {
-            self.operate(operation)
-        } else {
-            Vec::new()
+        let mut outputs = Vec::new();
+
+        for operation in self.interpreter.translate(input) {
+            outputs.extend(self.operate(operation));
         }
+
+        outputs
     }
 
     /// Performs `operation` and returns the appropriate [`Output`]s.
@@ -48,148 +92,1024 @@ impl Processor {
 
         match operation {
             Operation::Resize { dimensions } => {
-                self.pane.update_size(dimensions, &mut outputs);
+                self.update_size(dimensions, &mut outputs);
+            }
+            Operation::UpdateSetting(setting) => {
+                self.update_setting(setting, &mut outputs);
             }
-            Operation::UpdateSetting(setting) => match setting {
-                Setting::Wrap(is_wrapping) => {
-                    self.pane.update_is_wrapping(is_wrapping, &mut outputs);
-                }
-            },
             Operation::Confirm(action) => {
+                self.messages.acknowledge();
                 outputs.push(Output::Question {
-                    request: ShowMessageRequestParams::from(action),
+                    request: self.confirmation(action),
                 });
             }
             Operation::Reset => {
-                self.input.clear();
-                self.pane
-                    .update_is_wrapping(self.pane.is_wrapping, &mut outputs);
+                let _ = self.prompt.take();
+                self.refresh(&mut outputs);
+                self.messages.acknowledge();
+                outputs.push(Output::ClearMessage);
             }
             Operation::Alert(message) => {
-                outputs.push(Output::Notify { message });
+                self.alert(message, &mut outputs);
+            }
+            Operation::Timeout => {
+                let now = Instant::now();
+
+                if self.messages.expire(now) {
+                    outputs.push(Output::ClearMessage);
+                }
+
+                for operation in self.interpreter.expire(now) {
+                    outputs.extend(self.operate(operation));
+                }
+
+                // Only the earliest delay is scheduled, so any later delays are scheduled again.
+                if let Some(alarm) = self
+                    .messages
+                    .expiry()
+                    .into_iter()
+                    .chain(self.interpreter.deadline())
+                    .min()
+                {
+                    outputs.push(Output::Schedule {
+                        delay: alarm.saturating_duration_since(now),
+                    });
+                }
+            }
+            Operation::UpdatePending(timeout) => {
+                if let Some(delay) = timeout {
+                    outputs.push(Output::Schedule { delay });
+                }
+            }
+            Operation::ShowMessages => {
+                let text = self.messages.text();
+
+                match Purl::try_from(env::temp_dir().join("paper-messages")) {
+                    Ok(url) => {
+                        self.create_doc(File::new(url, text), true, &mut outputs);
+                    }
+                    Err(error) => self.alert(
+                        ShowMessageParams {
+                            typ: MessageType::Error,
+                            message: format!("Unable to display messages: {}", error),
+                        },
+                        &mut outputs,
+                    ),
+                }
             }
             Operation::StartCommand(command) => {
-                let prompt = command.to_string();
+                let prompt = match command {
+                    Command::Open => command.to_string().into(),
+                    Command::Buffer => format!("{}: {}", command, self.buffer_list()).into(),
+                    Command::Line => Prompt::default().row(&command.to_string()),
+                };
+
+                if command == Command::Open {
+                    outputs.push(Output::IndexFiles);
+                }
 
+                self.messages.acknowledge();
+                self.prompt = Prompt::default();
+                self.finder.search("");
                 self.command = Some(command);
                 outputs.push(Output::Command { command: prompt });
             }
             Operation::Collect(ch) => {
-                self.input.push(ch);
-                outputs.push(Output::Command {
-                    command: self.input.clone(),
-                });
+                self.prompt.insert(ch);
+                self.update_prompt(&mut outputs);
+            }
+            Operation::EditInput(edit) => {
+                let history = &self.history;
+                let entries = self
+                    .command
+                    .as_ref()
+                    .map_or(&[][..], |command| history.entries(command.name()));
+
+                self.prompt.edit(edit, entries);
+                self.update_prompt(&mut outputs);
+            }
+            Operation::LoadHistory(history) => {
+                self.history = history;
+            }
+            Operation::LoadIndex(index) => {
+                if index.is_truncated && !self.is_index_truncated {
+                    self.alert(
+                        ShowMessageParams {
+                            typ: MessageType::Warning,
+                            message: format!("The project has too many files to index. Only the first {} are completed and found when opening a file.", index.files.len()),
+                        },
+                        &mut outputs,
+                    );
+                }
+
+                self.is_index_truncated = index.is_truncated;
+                self.finder.set_files(index.files);
+
+                if self.command == Some(Command::Open) {
+                    outputs.push(Output::Command {
+                        command: self.command_line(),
+                    });
+                }
             }
+            Operation::SelectMatch(switch) => {
+                if self.command == Some(Command::Open) {
+                    self.finder.select(switch);
+                    outputs.push(Output::Command {
+                        command: self.command_line(),
+                    });
+                }
+            }
+            Operation::Complete => match self.command {
+                Some(Command::Line) => {
+                    let names: Vec<String> = self.buffers.iter().map(Document::name).collect();
+                    let completion =
+                        command::complete(self.prompt.before_cursor(), &names, self.finder.files());
+
+                    self.prompt.replace_before_cursor(&completion.line);
+                    let mut line = self.command_line();
+
+                    if completion.candidates.len() > 1 {
+                        line.push_str(&format!("  ({})", completion.candidates.join(" | ")), None);
+                    }
+
+                    outputs.push(Output::Command { command: line });
+                }
+                Some(Command::Open) => {
+                    let completion =
+                        command::complete_path(self.prompt.before_cursor(), self.finder.files());
+
+                    self.prompt.replace_before_cursor(&completion.line);
+                    self.update_prompt(&mut outputs);
+                }
+                Some(Command::Buffer) | None => {}
+            },
             Operation::Execute => {
-                if self.command.is_some() {
-                    let mut path = String::new();
-                    mem::swap(&mut path, &mut self.input);
+                let mut input = self.prompt.take();
 
-                    outputs.push(Output::OpenFile { path });
+                if self.command == Some(Command::Open) {
+                    input = self.finder.target(input);
+                }
+
+                self.messages.acknowledge();
+                outputs.push(Output::ClearMessage);
+
+                if let Some(command) = &self.command {
+                    self.history.record(command.name(), &input);
+                    outputs.push(Output::WriteHistory {
+                        history: self.history.clone(),
+                    });
+                }
+
+                match self.command.take() {
+                    Some(Command::Open) => {
+                        outputs.push(Output::OpenFile { path: input });
+                    }
+                    Some(Command::Buffer) => match self.find_buffer(&input) {
+                        Ok(index) => self.switch_buffer(index, &mut outputs),
+                        Err(message) => self.alert(message, &mut outputs),
+                    },
+                    Some(Command::Line) => self.execute(&input, &mut outputs),
+                    None => {}
                 }
             }
             Operation::Document(doc_op) => {
-                outputs.push(self.pane.operate(&doc_op));
+                if let Err(message) = self.operate_doc(&doc_op, &mut outputs) {
+                    self.alert(message, &mut outputs);
+                }
             }
-            Operation::Quit => {
-                if let Some(output) = self.pane.close_doc() {
-                    outputs.push(output);
+            Operation::Pane(pane_op) => {
+                self.operate_pane(pane_op, &mut outputs);
+            }
+            Operation::SwitchBuffer(switch) => {
+                if self.buffers.is_empty() {
+                    self.alert(
+                        ShowMessageParams {
+                            typ: MessageType::Info,
+                            message: "There are no open documents".to_string(),
+                        },
+                        &mut outputs,
+                    );
+                } else {
+                    let count = self.buffers.len();
+                    let current = self.focused_buffer().unwrap_or(0);
+                    let index = match switch {
+                        Switch::Next => current.saturating_add(1) % count,
+                        Switch::Previous => current.checked_sub(1).unwrap_or(count - 1),
+                    };
+
+                    self.switch_buffer(index, &mut outputs);
                 }
+            }
+            Operation::Quit => {
+                self.quit(&mut outputs);
+            }
+            Operation::SaveAndQuit => {
+                let unsaved: Vec<usize> = self
+                    .buffers
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, doc)| doc.is_dirty() && !doc.is_read_only)
+                    .map(|(index, _)| index)
+                    .collect();
 
-                outputs.push(Output::Quit);
+                if let Some(message) = unsaved.iter().find_map(|index| {
+                    self.buffers.get(*index).and_then(|doc| {
+                        doc.check_save(false, self.config.doc_settings(&doc.file).charset)
+                            .err()
+                    })
+                }) {
+                    self.alert(message, &mut outputs);
+                } else if unsaved.is_empty() {
+                    self.quit(&mut outputs);
+                } else {
+                    // The application quits once every save is confirmed by an Operation::Saved.
+                    self.is_quitting = true;
+
+                    for index in unsaved {
+                        self.save(index, false, &mut outputs);
+                    }
+                }
+            }
+            Operation::Saved(outcome) => {
+                self.handle_saved(outcome, &mut outputs);
             }
             Operation::CreateDoc(file) => {
-                outputs.append(&mut self.pane.create_doc(file));
+                self.create_doc(file, false, &mut outputs);
+            }
+            Operation::ExternalChange(file) => {
+                self.handle_external_change(file, &mut outputs);
             }
             Operation::SendLsp(message) => {
                 outputs.push(Output::SendLsp(message));
             }
+            Operation::Initialized(language_id) => {
+                let _ = self.servers.insert(language_id, ServerState::Ready);
+                outputs.push(Output::SendLsp(ToolMessage {
+                    language_id,
+                    message: ClientMessage::Initialized,
+                }));
+            }
+            Operation::Tokenize { url, tokens } => {
+                if let Some(index) = self.buffers.iter().position(|doc| doc.is_at(&url)) {
+                    if let Some(doc) = self.buffers.get_mut(index) {
+                        doc.tokens = tokens;
+                    }
+
+                    self.refresh_buffer(index, &mut outputs);
+                }
+            }
+            Operation::Hint { url, hints } => {
+                if let Some(index) = self.buffers.iter().position(|doc| doc.is_at(&url)) {
+                    if let Some(doc) = self.buffers.get_mut(index) {
+                        doc.hints = hints;
+                    }
+
+                    self.refresh_buffer(index, &mut outputs);
+                }
+            }
         };
 
         outputs.push(Output::UpdateHeader);
+        outputs.push(Output::UpdateStatus {
+            status: self.status(),
+        });
         trace!("outputs: {:?}", outputs);
 
         outputs
     }
-}
 
-/// A view of the document.
-#[derive(Debug, Default)]
-struct Pane {
-    /// The document in the pane.
-    doc: Option<Document>,
-    /// The number of lines by which a scroll moves.
-    scroll_amount: Rc<RefCell<Amount>>,
-    /// The length of a row.
-    row_length: Unit,
-    /// The [`Dimensions`] of the pane.
-    size: Dimensions,
-    /// If the pane is wrapping text.
-    is_wrapping: bool,
-}
+    /// Returns the index of the buffer displayed by the focused pane.
+    fn focused_buffer(&self) -> Option<usize> {
+        self.panes.focused().and_then(|pane| pane.buffer)
+    }
+
+    /// Returns the [`Document`] displayed by the focused pane.
+    fn doc(&self) -> Option<&Document> {
+        self.focused_buffer()
+            .and_then(|index| self.buffers.get(index))
+    }
 
-impl Pane {
-    /// Updates if `self` is wrapping text.
-    fn update_is_wrapping(&mut self, is_wrapping: bool, outputs: &mut Vec<Output>) {
-        if is_wrapping != self.is_wrapping {
-            self.is_wrapping = is_wrapping;
+    /// Displays every pane again.
+    ///
+    /// Each pane is sized to its area of the page, displays its document as configured for the language of the document and is scrolled so that its cursor is displayed.
+    fn refresh(&mut self, outputs: &mut Vec<Output>) {
+        let page = Area::from(self.size);
+        let areas = self.panes.areas(page);
+        let focus = self.panes.focus();
+        let buffers = &self.buffers;
+        let config = &self.config;
 
-            if let Some(doc) = &mut self.doc {
-                outputs.push(doc.change_output(is_wrapping));
+        for (pane, area) in self.panes.panes_mut().into_iter().zip(areas) {
+            pane.view.dimensions = area.dimensions();
+            pane.scroll_amount
+                .borrow_mut()
+                .set(usize::from(area.height.wrapping_div(3)));
+
+            if let Some(doc) = pane.buffer.and_then(|index| buffers.get(index)) {
+                pane.view.appearance = Appearance::new(config, &doc.file);
+                pane.view.scroll_to_cursor(doc);
             }
         }
+
+        outputs.push(Output::UpdatePage {
+            rows: self.panes.compose(page, |index, pane| {
+                pane.buffer
+                    .and_then(|buffer| buffers.get(buffer))
+                    .map_or_else(Vec::new, |doc| pane.view.rows(doc, index == focus))
+            }),
+        });
     }
 
-    /// Updates the size of `self` to match `dimensions`;
-    fn update_size(&mut self, dimensions: Dimensions, outputs: &mut Vec<Output>) {
-        self.size = dimensions;
-        self.scroll_amount
-            .borrow_mut()
-            .set(usize::from(dimensions.height.wrapping_div(3)));
+    /// Displays every pane again if the buffer at `index` is displayed by a pane.
+    fn refresh_buffer(&mut self, index: usize, outputs: &mut Vec<Output>) {
+        if self
+            .panes
+            .panes()
+            .iter()
+            .any(|pane| pane.buffer == Some(index))
+        {
+            self.refresh(outputs);
+        }
+    }
 
-        if let Some(doc) = &mut self.doc {
-            doc.dimensions = dimensions;
-            outputs.push(doc.change_output(self.is_wrapping));
+    /// Updates the configuration of `self` according to `setting`.
+    ///
+    /// The theme file is loaded whenever the theme is given, even if it is unchanged, so that the theme of the initial configuration is loaded and setting the theme again reloads its file.
+    fn update_setting(&mut self, setting: Setting, outputs: &mut Vec<Output>) {
+        let is_theme = matches!(setting, Setting::Theme(_));
+
+        if self.config.apply(setting) || is_theme {
+            if is_theme {
+                outputs.push(Output::SetTheme {
+                    theme: self.config.theme.clone(),
+                });
+            }
+
+            self.refresh(outputs);
         }
     }
 
-    /// Performs `operation` on `self`.
-    fn operate(&mut self, operation: &DocOp) -> Output {
-        if let Some(doc) = &mut self.doc {
+    /// Updates the size of the page to match `dimensions`, redistributing the space among the panes.
+    fn update_size(&mut self, dimensions: Dimensions, outputs: &mut Vec<Output>) {
+        self.size = dimensions;
+        self.refresh(outputs);
+    }
+
+    /// Performs `operation` on the document of the focused pane.
+    ///
+    /// If `operation` is unable to be performed, the message that explains why is thrown.
+    #[throws(ShowMessageParams)]
+    fn operate_doc(&mut self, operation: &DocOp, outputs: &mut Vec<Output>) {
+        let buffers = &mut self.buffers;
+        let target = self.panes.focused_mut().and_then(|pane| {
+            let doc = buffers.get_mut(pane.buffer?)?;
+
+            Some((pane, doc))
+        });
+
+        if let Some((pane, doc)) = target {
             match operation {
-                DocOp::Save => doc.save(),
+                DocOp::Save | DocOp::Overwrite => {
+                    let overwrite = *operation == DocOp::Overwrite;
+
+                    doc.check_save(overwrite, self.config.doc_settings(&doc.file).charset)?;
+
+                    if overwrite {
+                        doc.external_change = None;
+                    }
+
+                    if let Some(index) = pane.buffer {
+                        self.save(index, overwrite, outputs);
+                    }
+                }
+                DocOp::Reload => {
+                    let index = pane.buffer;
+
+                    if let Some((index, file)) = index.zip(doc.external_change.take()) {
+                        self.reload(index, *file, true, outputs);
+                    } else {
+                        throw!(ShowMessageParams {
+                            typ: MessageType::Info,
+                            message: format!(
+                                "{} has not been changed by another program",
+                                doc.name()
+                            ),
+                        });
+                    }
+                }
+                DocOp::Move(movement, count) => {
+                    for _ in 0..*count {
+                        let cursor = pane.view.cursor;
+
+                        pane.view.move_cursor(doc, *movement);
+
+                        if pane.view.cursor == cursor {
+                            break;
+                        }
+                    }
+
+                    self.refresh(outputs);
+                }
             }
         } else {
-            Output::Notify {
-                message: ShowMessageParams {
-                    typ: MessageType::Info,
-                    message: format!(
-                        "There is no open document on which to perform {}",
-                        operation
-                    ),
+            throw!(ShowMessageParams {
+                typ: MessageType::Info,
+                message: format!(
+                    "There is no open document on which to perform {}",
+                    operation
+                ),
+            });
+        }
+    }
+
+    /// Saves the buffer at `index`, overwriting any change made to its file by another program if `overwrite`.
+    ///
+    /// The text of the buffer is first formatted as configured for its language, which reloads the buffer if its text changes.
+    fn save(&mut self, index: usize, overwrite: bool, outputs: &mut Vec<Output>) {
+        if let Some(doc) = self.buffers.get(index) {
+            let text = self.config.doc_settings(&doc.file).format(doc.file.text());
+
+            if text != *doc.file.text() {
+                let file = doc.file.with_text(text);
+
+                self.reload(index, file, false, outputs);
+            }
+        }
+
+        let backup = self.config.backup;
+
+        if let Some(doc) = self.buffers.get(index) {
+            outputs.push(doc.save(backup, overwrite));
+        }
+    }
+
+    /// Handles the outcome of saving a document.
+    ///
+    /// A document is only considered saved once its file has been written. When quitting waits on saves, the application quits once no document has unsaved changes; a failed save cancels quitting.
+    fn handle_saved(&mut self, outcome: SaveOutcome, outputs: &mut Vec<Output>) {
+        let doc = self.buffers.iter_mut().find(|doc| doc.is_at(&outcome.url));
+
+        if let Some(error) = &outcome.error {
+            let name = doc.map_or_else(|| outcome.url.to_string(), |doc| doc.name());
+            let message = if mem::take(&mut self.is_quitting) {
+                format!(
+                    "Unable to save {}: {}. Quitting was cancelled.",
+                    name, error
+                )
+            } else {
+                format!("Unable to save {}: {}", name, error)
+            };
+
+            self.alert(
+                ShowMessageParams {
+                    typ: MessageType::Error,
+                    message,
                 },
+                outputs,
+            );
+        } else {
+            if let Some(doc) = doc {
+                doc.saved_version = doc.saved_version.max(outcome.version);
+            }
+
+            if self.is_quitting
+                && !self
+                    .buffers
+                    .iter()
+                    .any(|doc| doc.is_dirty() && !doc.is_read_only)
+            {
+                self.quit(outputs);
+            } else {
+                self.refresh(outputs);
             }
         }
     }
 
-    /// Opens a document at `path`.
-    fn create_doc(&mut self, file: File) -> Vec<Output> {
-        let mut outputs = Vec::new();
-        let doc = Document::new(file, self.size, self.is_wrapping);
-        let output = doc.open_output();
+    /// Handles `file` having been changed by another process.
+    ///
+    /// A document of `file` without unsaved changes is reloaded. Otherwise the user is warned so that they may choose whether to reload it.
+    fn handle_external_change(&mut self, file: File, outputs: &mut Vec<Output>) {
+        if let Some(index) = self
+            .buffers
+            .iter()
+            .position(|doc| doc.is_at(file.url().as_ref()))
+        {
+            if let Some(doc) = self.buffers.get_mut(index) {
+                let name = doc.name();
 
-        if let Some(old_doc) = self.doc.replace(doc) {
-            outputs.push(old_doc.close());
+                if doc.file.text() == file.text() {
+                    doc.external_change = None;
+                } else if doc.is_dirty() {
+                    doc.external_change = Some(Box::new(file));
+                    self.alert(
+                        ShowMessageParams {
+                            typ: MessageType::Warning,
+                            message: format!("{} was changed by another program. Press `r` to reload it, discarding your changes, or `S` to overwrite it.", name),
+                        },
+                        outputs,
+                    );
+                } else {
+                    self.reload(index, file, true, outputs);
+                    self.alert(
+                        ShowMessageParams {
+                            typ: MessageType::Info,
+                            message: format!(
+                                "Reloaded {}, which was changed by another program",
+                                name
+                            ),
+                        },
+                        outputs,
+                    );
+                }
+            }
         }
+    }
 
-        outputs.push(output);
-        outputs
+    /// Replaces the buffer at `index` with a document of `file`, keeping the cursors of the panes that display it as close to their positions as possible.
+    ///
+    /// The text of the new document is considered saved if `is_saved`, i.e. if it is the text of the file on disk.
+    fn reload(&mut self, index: usize, file: File, is_saved: bool, outputs: &mut Vec<Output>) {
+        if let Some(doc) = self.buffers.get_mut(index) {
+            // Only the lines from the first line that changed are highlighted again.
+            let first_line = doc
+                .file
+                .lines()
+                .zip(file.lines())
+                .take_while(|(old, new)| old == new)
+                .count();
+            let mut highlights = mem::take(&mut doc.highlights);
+
+            highlights.update(file.text(), first_line);
+
+            let mut new_doc = Document::with_highlights(file, highlights);
+
+            new_doc.is_read_only = doc.is_read_only;
+            new_doc.version = doc.version.saturating_add(1);
+            new_doc.saved_version = if is_saved {
+                new_doc.version
+            } else {
+                doc.saved_version
+            };
+            new_doc.cursor = doc.cursor;
+            outputs.push(mem::replace(doc, new_doc).close());
+
+            if let Some(doc) = self.buffers.get(index) {
+                outputs.push(doc.open_output());
+                outputs.append(&mut doc.request_decorations());
+
+                for pane in self.panes.panes_mut() {
+                    if pane.buffer == Some(index) {
+                        pane.view.clamp_cursor(doc);
+                    }
+                }
+            }
+
+            self.refresh(outputs);
+        }
     }
 
-    /// Returns the [`Output`] to close the [`Document`] of `self`.
-    fn close_doc(&mut self) -> Option<Output> {
-        self.doc.take().map(Document::close)
+    /// Performs `operation` on the panes of the page.
+    fn operate_pane(&mut self, operation: PaneOp, outputs: &mut Vec<Output>) {
+        let is_changed = match operation {
+            PaneOp::Split(split) => {
+                let pane = self
+                    .panes
+                    .focused()
+                    .map_or_else(Pane::default, |focused| Pane {
+                        buffer: focused.buffer,
+                        view: focused.view.clone(),
+                        scroll_amount: Rc::default(),
+                    });
+
+                self.panes.split(split, pane);
+                true
+            }
+            PaneOp::Close => {
+                if let Some(pane) = self.panes.close() {
+                    if let Some(doc) = pane.buffer.and_then(|index| self.buffers.get_mut(index)) {
+                        doc.cursor = pane.view.cursor;
+                    }
+
+                    true
+                } else {
+                    self.alert(
+                        ShowMessageParams {
+                            typ: MessageType::Info,
+                            message: "The last pane is not able to be closed".to_string(),
+                        },
+                        outputs,
+                    );
+                    false
+                }
+            }
+            PaneOp::Focus(direction) => self.panes.move_focus(Area::from(self.size), direction),
+            PaneOp::Resize(split, delta) => self.panes.resize(split, delta),
+        };
+
+        if is_changed {
+            self.refresh(outputs);
+        }
+    }
+
+    /// Opens a document of `file`, which is not treated as a file if `is_read_only`, and displays it in the focused pane.
+    ///
+    /// If the file is already open, its buffer is displayed instead. A read-only buffer with the same URL is replaced so that it displays the text of `file`.
+    fn create_doc(&mut self, file: File, is_read_only: bool, outputs: &mut Vec<Output>) {
+        let existing = self.buffers.iter().position(|doc| {
+            doc.is_read_only == is_read_only
+                && AsRef::<Url>::as_ref(doc.url()) == file.url().as_ref()
+        });
+
+        if let Some(index) = existing.filter(|_| !is_read_only) {
+            self.switch_buffer(index, outputs);
+        } else {
+            let mut doc = Document::new(file);
+
+            doc.is_read_only = is_read_only;
+            outputs.push(doc.open_output());
+            outputs.append(&mut doc.request_decorations());
+
+            let index = if let Some(index) = existing {
+                if let Some(old_doc) = self.buffers.get_mut(index) {
+                    outputs.push(mem::replace(old_doc, doc).close());
+                }
+
+                for pane in self.panes.panes_mut() {
+                    if pane.buffer == Some(index) {
+                        pane.view.show(Cursor::default());
+                    }
+                }
+
+                index
+            } else {
+                self.buffers.push(doc);
+                self.buffers.len().saturating_sub(1)
+            };
+
+            self.switch_buffer(index, outputs);
+        }
     }
+
+    /// Displays the buffer at `index` in the focused pane.
+    ///
+    /// The position of the cursor in the buffer that was displayed is remembered so that it is restored when that buffer is displayed again. If auto-save is enabled, that buffer is saved if it has unsaved changes.
+    fn switch_buffer(&mut self, index: usize, outputs: &mut Vec<Output>) {
+        let buffers = &mut self.buffers;
+        let mut previous = None;
+
+        if index < buffers.len() {
+            if let Some(pane) = self.panes.focused_mut() {
+                if pane.buffer != Some(index) {
+                    previous = pane.buffer;
+
+                    if let Some(old_doc) = pane.buffer.and_then(|old| buffers.get_mut(old)) {
+                        old_doc.cursor = pane.view.cursor;
+                    }
+
+                    pane.view.show(
+                        buffers
+                            .get(index)
+                            .map_or_else(Cursor::default, |doc| doc.cursor),
+                    );
+                    pane.buffer = Some(index);
+                }
+            }
+
+            if let Some(previous) = previous.filter(|previous| {
+                self.config.auto_save
+                    && self.buffers.get(*previous).is_some_and(|doc| {
+                        doc.is_dirty()
+                            && doc
+                                .check_save(false, self.config.doc_settings(&doc.file).charset)
+                                .is_ok()
+                    })
+            }) {
+                self.save(previous, false, outputs);
+            }
+
+            self.refresh(outputs);
+        }
+    }
+
+    /// Returns the index of the buffer identified by `name`.
+    ///
+    /// `name` is either the number of the buffer in the buffer list or a part of its name. If `name` does not identify exactly one buffer, the message that explains why is thrown.
+    #[throws(ShowMessageParams)]
+    fn find_buffer(&self, name: &str) -> usize {
+        let name = name.trim();
+
+        if let Some(index) = name
+            .parse::<usize>()
+            .ok()
+            .and_then(|number| number.checked_sub(1))
+            .filter(|index| *index < self.buffers.len())
+        {
+            index
+        } else if let Some(index) = self.buffers.iter().position(|doc| doc.name() == name) {
+            index
+        } else {
+            let matches: Vec<usize> = self
+                .buffers
+                .iter()
+                .enumerate()
+                .filter(|(_, doc)| !name.is_empty() && doc.name().contains(name))
+                .map(|(index, _)| index)
+                .collect();
+
+            match matches.as_slice() {
+                [index] => *index,
+                [] => throw!(ShowMessageParams {
+                    typ: MessageType::Warning,
+                    message: format!("No open document matches `{}`", name),
+                }),
+                _ => throw!(ShowMessageParams {
+                    typ: MessageType::Warning,
+                    message: format!("Multiple open documents match `{}`", name),
+                }),
+            }
+        }
+    }
+
+    /// Returns the list of open buffers, each preceded by its number.
+    ///
+    /// The buffer displayed by the focused pane is marked with `%` and buffers with unsaved changes are marked with `+`.
+    fn buffer_list(&self) -> String {
+        self.buffers
+            .iter()
+            .enumerate()
+            .map(|(index, doc)| {
+                format!(
+                    "{}{}{} {}",
+                    index.saturating_add(1),
+                    if self.focused_buffer() == Some(index) {
+                        "%"
+                    } else {
+                        ""
+                    },
+                    if doc.is_dirty() { "+" } else { "" },
+                    doc.name()
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(" | ")
+    }
+
+    /// Returns the [`Row`] that displays the input of the current command.
+    ///
+    /// The input of the command line follows `:`; the input of other commands replaces their prompt. The input of an open command is followed by the files that it matches.
+    fn command_line(&self) -> Row {
+        match self.command {
+            Some(Command::Line) => self.prompt.row(&Command::Line.to_string()),
+            Some(Command::Open) => {
+                let mut row = self.prompt.row("");
+
+                row.append(self.finder.row());
+                row
+            }
+            Some(Command::Buffer) | None => self.prompt.row(""),
+        }
+    }
+
+    /// Displays the input of the current command after it has changed, searching for the files that the input of an open command matches.
+    ///
+    /// The files of the project are only indexed once the input is a path.
+    fn update_prompt(&mut self, outputs: &mut Vec<Output>) {
+        match self.command {
+            Some(Command::Open) => self.finder.search(self.prompt.text()),
+            Some(Command::Line) if command::completes_path(self.prompt.before_cursor()) => {
+                outputs.push(Output::IndexFiles);
+            }
+            Some(Command::Line | Command::Buffer) | None => {}
+        }
+
+        outputs.push(Output::Command {
+            command: self.command_line(),
+        });
+    }
+
+    /// Performs the command of the command line given by `line`.
+    fn execute(&mut self, line: &str, outputs: &mut Vec<Output>) {
+        match line.parse::<LineCommand>() {
+            Ok(LineCommand::Open(path)) => {
+                outputs.push(Output::OpenFile { path });
+            }
+            Ok(LineCommand::Write {
+                path: None,
+                is_forced,
+            }) => {
+                let operation = if is_forced {
+                    DocOp::Overwrite
+                } else {
+                    DocOp::Save
+                };
+
+                if let Err(message) = self.operate_doc(&operation, outputs) {
+                    self.alert(message, outputs);
+                }
+            }
+            Ok(LineCommand::Write {
+                path: Some(path),
+                is_forced,
+            }) => {
+                if let Some(doc) = self.doc() {
+                    let settings = self.config.doc_settings(&doc.file);
+
+                    match doc.check_charset(settings.charset) {
+                        Ok(()) => outputs.push(Output::WriteFile {
+                            path,
+                            text: settings.format(doc.file.text()),
+                            overwrite: is_forced,
+                        }),
+                        Err(message) => self.alert(message, outputs),
+                    }
+                } else {
+                    self.alert(
+                        ShowMessageParams {
+                            typ: MessageType::Info,
+                            message: "There is no open document to write".to_string(),
+                        },
+                        outputs,
+                    );
+                }
+            }
+            Ok(LineCommand::Quit { is_forced }) => {
+                let unsaved = self.unsaved_names();
+
+                if is_forced || unsaved.is_empty() {
+                    self.quit(outputs);
+                } else {
+                    self.alert(
+                        ShowMessageParams {
+                            typ: MessageType::Warning,
+                            message: format!(
+                                "{} Use `write` to save or `quit!` to quit without saving.",
+                                unsaved_warning(&unsaved)
+                            ),
+                        },
+                        outputs,
+                    );
+                }
+            }
+            Ok(LineCommand::Set(setting)) => {
+                // The setting is translated so that the interpreter also receives it.
+                for operation in self.interpreter.translate(Input::Setting(setting)) {
+                    outputs.extend(self.operate(operation));
+                }
+            }
+            Ok(LineCommand::Buffer(name)) => match self.find_buffer(&name) {
+                Ok(index) => self.switch_buffer(index, outputs),
+                Err(message) => self.alert(message, outputs),
+            },
+            Ok(LineCommand::Split(split)) => {
+                self.operate_pane(PaneOp::Split(split), outputs);
+            }
+            Err(error) => self.alert(
+                ShowMessageParams {
+                    typ: MessageType::Error,
+                    message: error.to_string(),
+                },
+                outputs,
+            ),
+        }
+    }
+
+    /// Returns the question that confirms `action`.
+    ///
+    /// If any documents have unsaved changes, the question lists them and offers to save them.
+    fn confirmation(&self, action: ConfirmAction) -> ShowMessageRequestParams {
+        let unsaved = self.unsaved_names();
+
+        if unsaved.is_empty() {
+            ShowMessageRequestParams::from(action)
+        } else {
+            ShowMessageRequestParams {
+                typ: MessageType::Warning,
+                message: format!(
+                    "{}\nPress `s` to save {} and quit, `y` to quit without saving or any other key to cancel.",
+                    unsaved_warning(&unsaved),
+                    if unsaved.len() == 1 { "it" } else { "them" },
+                ),
+                actions: None,
+            }
+        }
+    }
+
+    /// Returns the names of the documents that have unsaved changes.
+    fn unsaved_names(&self) -> Vec<String> {
+        self.buffers
+            .iter()
+            .filter(|doc| doc.is_dirty())
+            .map(Document::name)
+            .collect()
+    }
+
+    /// Closes every document and quits the application.
+    fn quit(&mut self, outputs: &mut Vec<Output>) {
+        self.panes = Tiling::default();
+        outputs.extend(self.buffers.drain(..).map(Document::close));
+        outputs.push(Output::Quit);
+    }
+
+    /// Displays `message` and records it in the message history.
+    fn alert(&mut self, message: ShowMessageParams, outputs: &mut Vec<Output>) {
+        let delay = self.messages.record(message.clone());
+
+        outputs.push(Output::Notify { message });
+
+        if let Some(delay) = delay {
+            outputs.push(Output::Schedule { delay });
+        }
+    }
+
+    /// Returns the status line.
+    ///
+    /// The mode and the name of the document are aligned to the left and the keys pressed towards a sequence, the state of the language server and the position of the cursor are aligned to the right.
+    fn status(&self) -> Row {
+        let mode_scope = Scope::new("ui.status.mode".to_string());
+        let scope = Scope::new("ui.status".to_string());
+        let mode = format!(" {} ", self.interpreter.mode().to_string().to_uppercase());
+        let cursor = self
+            .panes
+            .focused()
+            .map_or_else(Cursor::default, |pane| pane.view.cursor);
+        let (name, details) = self.doc().map_or_else(
+            || (String::new(), String::new()),
+            |doc| {
+                let server = doc.language_id().map_or_else(String::new, |language_id| {
+                    format!(
+                        "{} {}  ",
+                        language_id,
+                        self.servers
+                            .get(&language_id)
+                            .copied()
+                            .unwrap_or(ServerState::Starting)
+                    )
+                });
+
+                (
+                    format!(
+                        " {}{}",
+                        doc.name(),
+                        if doc.is_dirty() { " [+]" } else { "" }
+                    ),
+                    format!(
+                        "{}Ln {}, Col {} ",
+                        server,
+                        cursor.line.saturating_add(1),
+                        cursor.character.saturating_add(1)
+                    ),
+                )
+            },
+        );
+        let pending = self.interpreter.pending();
+        let details = if pending.is_empty() {
+            details
+        } else {
+            format!("{}  {}", pending, details)
+        };
+        let padding = usize::from(*self.size.width)
+            .saturating_sub(mode.width())
+            .saturating_sub(name.width())
+            .saturating_sub(details.width())
+            .max(1);
+        let mut status = Row::default();
+
+        status.push_str(&mode, Some(&mode_scope));
+        status.push_str(&name, Some(&scope));
+        status.push_str(&" ".repeat(padding), Some(&scope));
+        status.push_str(&details, Some(&scope));
+        status
+    }
+}
+
+/// Returns the sentence that warns that the documents named `unsaved` have unsaved changes.
+fn unsaved_warning(unsaved: &[String]) -> String {
+    format!(
+        "{} {} unsaved changes.",
+        unsaved.join(", "),
+        if unsaved.len() == 1 { "has" } else { "have" }
+    )
+}
+
+/// The state of a language server.
+#[derive(Clone, Copy, Debug, Eq, ParseDisplay, PartialEq)]
+#[display(style = "lowercase")]
+enum ServerState {
+    /// The server has been started but has not been initialized.
+    Starting,
+    /// The server is ready to receive messages.
+    Ready,
+}
+
+/// A section of the page that displays a buffer.
+#[derive(Debug, Default)]
+struct Pane {
+    /// The index of the buffer displayed by the pane.
+    buffer: Option<usize>,
+    /// How the pane displays its buffer.
+    view: View,
+    /// The number of lines by which a scroll moves.
+    scroll_amount: Rc<RefCell<Amount>>,
 }
 
 /// A file and the user's current interactions with it.
@@ -197,22 +1117,48 @@ impl Pane {
 pub(crate) struct Document {
     /// The file of the document.
     file: File,
-    /// The [`Dimensions`] of the Document.
-    dimensions: Dimensions,
-    /// The version of the document.
+    /// The version of the document, which increases whenever its text is replaced.
+    ///
+    /// Text is not yet edited directly, so it only changes when the document is reloaded or formatted while saving.
     version: i64,
-    /// If the document is wrapping text.
-    is_wrapping: bool,
+    /// The version of the document when its file was last confirmed to hold its text.
+    saved_version: i64,
+    /// If the document is not of a file, such as the message log.
+    ///
+    /// A read-only document is never saved nor sent to a language server, and is not found by the URL that it is displayed with.
+    is_read_only: bool,
+    /// The position of the cursor when the document was last displayed by a pane.
+    cursor: Cursor,
+    /// The file of the document as changed by another process, if it has been changed since the document was read or saved and the change has not been reloaded.
+    external_change: Option<Box<File>>,
+    /// The semantic tokens of the document.
+    tokens: Vec<TokenSpan>,
+    /// The inlay hints of the document.
+    hints: Vec<InlayHint>,
+    /// The syntax highlighting of the document.
+    highlights: Highlights,
 }
 
 impl Document {
     /// Creates a new [`Document`].
-    const fn new(file: File, dimensions: Dimensions, is_wrapping: bool) -> Self {
+    fn new(file: File) -> Self {
+        let highlights = Highlights::new(file.language_id(), file.text());
+
+        Self::with_highlights(file, highlights)
+    }
+
+    /// Creates a new [`Document`] of `file` that is highlighted by `highlights`.
+    fn with_highlights(file: File, highlights: Highlights) -> Self {
         Self {
+            highlights,
             file,
-            dimensions,
             version: 0,
-            is_wrapping,
+            saved_version: 0,
+            is_read_only: false,
+            cursor: Cursor::default(),
+            external_change: None,
+            tokens: Vec::new(),
+            hints: Vec::new(),
         }
     }
 
@@ -226,21 +1172,84 @@ impl Document {
         }
     }
 
-    /// Returns the [`Output`] for changing `self`.
-    fn change_output(&mut self, is_wrapping: bool) -> Output {
-        self.is_wrapping = is_wrapping;
+    /// Returns the [`Output`]s that request the semantic tokens and inlay hints of `self` from its language server.
+    fn request_decorations(&self) -> Vec<Output> {
+        let url: &Url = self.url().as_ref();
+
+        self.language_id().map_or_else(Vec::new, |language_id| {
+            vec![DocMessage::SemanticTokens, DocMessage::InlayHints]
+                .into_iter()
+                .map(|message| {
+                    Output::SendLsp(ToolMessage {
+                        language_id,
+                        message: ClientMessage::Doc(DocConfiguration::new(url.clone(), message)),
+                    })
+                })
+                .collect()
+        })
+    }
+
+    /// Returns if `self` is the document of the file at `url`.
+    fn is_at(&self, url: &Url) -> bool {
+        !self.is_read_only && AsRef::<Url>::as_ref(self.url()) == url
+    }
+
+    /// Returns the version of `self`.
+    pub(crate) const fn version(&self) -> i64 {
+        self.version
+    }
+
+    /// Returns if `self` has changes that have not been saved.
+    const fn is_dirty(&self) -> bool {
+        self.version != self.saved_version
+    }
 
+    /// Returns the [`Output`] that saves the document, keeping a backup of the previous contents of its file if `backup` and overwriting any change made to its file by another program if `overwrite`.
+    ///
+    /// The document is considered saved once the outcome of the save is handled.
+    fn save(&self, backup: bool, overwrite: bool) -> Output {
         Output::EditDoc {
             doc: self.clone(),
-            edit: DocEdit::Update,
+            edit: DocEdit::Save { backup, overwrite },
         }
     }
 
-    /// Saves the document.
-    fn save(&self) -> Output {
-        Output::EditDoc {
-            doc: self.clone(),
-            edit: DocEdit::Save,
+    /// Checks that the document is able to be saved in `charset`, ignoring any change made to its file by another program if `overwrite`.
+    ///
+    /// If the document is unable to be saved, the message that explains why is thrown.
+    #[throws(ShowMessageParams)]
+    fn check_save(&self, overwrite: bool, charset: Option<Charset>) {
+        if self.is_read_only {
+            throw!(ShowMessageParams {
+                typ: MessageType::Warning,
+                message: format!("{} is read-only", self.name()),
+            });
+        }
+
+        self.check_charset(charset)?;
+
+        if !overwrite && self.external_change.is_some() {
+            throw!(ShowMessageParams {
+                typ: MessageType::Warning,
+                message: format!("{} was changed by another program since it was read. Press `r` to reload it or `S` to overwrite it.", self.name()),
+            });
+        }
+    }
+
+    /// Checks that the text of the document is able to be written in `charset`.
+    ///
+    /// If it is not, the message that explains why is thrown.
+    #[throws(ShowMessageParams)]
+    fn check_charset(&self, charset: Option<Charset>) {
+        if let Some(charset) = charset.filter(|charset| !charset.is_writable()) {
+            throw!(ShowMessageParams {
+                typ: MessageType::Warning,
+                message: format!(
+                    "{} is configured with charset `{}`, which is unable to be written; only UTF-8 is supported",
+                    self.name(),
+                    charset
+                ),
+            });
         }
     }
 
@@ -249,9 +1258,17 @@ impl Document {
         self.file.url()
     }
 
-    /// Returns the [`LanguageId`] of `self`.
+    /// Returns the name of the file of `self`.
+    fn name(&self) -> String {
+        AsRef::<Path>::as_ref(self.url()).file_name().map_or_else(
+            || self.url().to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        )
+    }
+
+    /// Returns the [`LanguageId`] of `self`, which is [`None`] if `self` is read-only so that it is not sent to a language server.
     pub(crate) fn language_id(&self) -> Option<LanguageId> {
-        self.file.language_id()
+        self.file.language_id().filter(|_| !self.is_read_only)
     }
 
     /// Returns the text of `self`.
@@ -259,32 +1276,6 @@ impl Document {
         self.file.text().to_string()
     }
 
-    /// Returns a [`Vec`] of the rows of `self`.
-    pub(crate) fn rows(&self) -> Vec<String> {
-        let mut rows = Vec::new();
-        let row_length = (*self.dimensions.width).into();
-
-        for line in self.file.lines() {
-            if !self.is_wrapping || line.len() <= row_length {
-                rows.push(line.to_string());
-            } else {
-                let mut line_remainder = line;
-
-                while line_remainder.len() > row_length {
-                    let (row, remainder) = line_remainder.split_at(row_length);
-                    line_remainder = remainder;
-                    rows.push(row.to_string());
-                }
-
-                rows.push(line_remainder.to_string());
-            }
-        }
-
-        rows.into_iter()
-            .take((*self.dimensions.height).into())
-            .collect()
-    }
-
     /// Returns the output to close `self`.
     fn close(self) -> Output {
         Output::EditDoc {
@@ -306,3 +1297,480 @@ impl Amount {
         self.0 = amount;
     }
 }
+
+/// Testing of the app module.
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::io::{config::Overrides, fs::index::Index, ui::UserAction},
+        crossterm::event::{KeyCode, KeyModifiers},
+        translate::InputEdit,
+    };
+
+    /// Types `line` into the input box of `processor`.
+    fn type_line(processor: &mut Processor, line: &str) {
+        for c in line.chars() {
+            let _ = processor.operate(Operation::Collect(c));
+        }
+    }
+
+    /// Formats the buffer at `index` of `processor` by adding a final newline and saves it without the save being confirmed, leaving the formatted text unsaved.
+    fn format_unsaved(processor: &mut Processor, index: usize) {
+        let _ = processor.process(Input::Setting(Setting::FinalNewline(true)));
+        processor.save(index, false, &mut Vec::new());
+    }
+
+    /// Returns the outputs of `processor` handling the outcome of saving the buffer at `index`, which failed with `error` if given.
+    fn saved(processor: &mut Processor, index: usize, error: Option<&str>) -> Vec<Output> {
+        let doc = &processor.buffers[index];
+        let outcome = SaveOutcome {
+            url: AsRef::<Url>::as_ref(doc.url()).clone(),
+            version: doc.version(),
+            error: error.map(str::to_string),
+        };
+
+        processor.operate(Operation::Saved(outcome))
+    }
+
+    /// Returns if `outputs` quit the application.
+    fn quits(outputs: &[Output]) -> bool {
+        outputs.iter().any(|output| matches!(output, Output::Quit))
+    }
+
+    /// Returns if `outputs` index the files of the project.
+    fn indexes(outputs: &[Output]) -> bool {
+        outputs
+            .iter()
+            .any(|output| matches!(output, Output::IndexFiles))
+    }
+
+    /// Returns the name of the document displayed by `processor`.
+    fn visible(processor: &Processor) -> Option<String> {
+        processor.doc().map(Document::name)
+    }
+
+    /// Opening a document shall keep the other documents open and opening an open document shall display it.
+    #[test]
+    fn buffers() {
+        let mut processor = Processor::new();
+
+        let _ = processor.operate(Operation::CreateDoc(File::fixture("a.txt", "a")));
+        let _ = processor.operate(Operation::CreateDoc(File::fixture("b.txt", "b")));
+        assert_eq!(processor.buffers.len(), 2);
+        assert_eq!(visible(&processor), Some("b.txt".to_string()));
+
+        let _ = processor.operate(Operation::SwitchBuffer(Switch::Next));
+        assert_eq!(visible(&processor), Some("a.txt".to_string()));
+        let _ = processor.operate(Operation::SwitchBuffer(Switch::Previous));
+        assert_eq!(visible(&processor), Some("b.txt".to_string()));
+        assert_eq!(processor.buffer_list(), "1 a.txt | 2% b.txt");
+
+        let _ = processor.operate(Operation::CreateDoc(File::fixture("a.txt", "a")));
+        assert_eq!(processor.buffers.len(), 2);
+        assert_eq!(visible(&processor), Some("a.txt".to_string()));
+
+        assert_eq!(processor.find_buffer("2"), Ok(1));
+        assert_eq!(processor.find_buffer("b"), Ok(1));
+        assert!(processor.find_buffer("txt").is_err());
+    }
+
+    /// The message log shall be replaced when it is shown again and shall be kept apart from any file at its URL.
+    #[test]
+    fn messages() {
+        let mut processor = Processor::new();
+        let url = Purl::try_from(env::temp_dir().join("paper-messages")).unwrap();
+
+        let _ = processor.operate(Operation::ShowMessages);
+        let _ = processor.operate(Operation::ShowMessages);
+        assert_eq!(processor.buffers.len(), 1);
+        assert!(!processor.buffers[0].is_at(url.as_ref()));
+
+        let _ = processor.operate(Operation::CreateDoc(File::new(
+            url.clone(),
+            "a".to_string(),
+        )));
+        assert_eq!(processor.buffers.len(), 2);
+        assert!(processor.buffers[1].is_at(url.as_ref()));
+
+        let _ = processor.operate(Operation::ShowMessages);
+        assert_eq!(processor.buffers.len(), 2);
+        assert_eq!(processor.buffers[1].text(), "a");
+    }
+
+    /// Quitting shall list the documents with unsaved changes and shall wait until they are saved.
+    #[test]
+    fn unsaved() {
+        let mut processor = Processor::new();
+
+        let _ = processor.operate(Operation::CreateDoc(File::fixture("a.txt", "a")));
+        let _ = processor.operate(Operation::CreateDoc(File::fixture("b.txt", "b")));
+        assert_eq!(
+            processor.confirmation(ConfirmAction::Quit),
+            ShowMessageRequestParams::from(ConfirmAction::Quit)
+        );
+
+        format_unsaved(&mut processor, 0);
+        assert_eq!(processor.buffers[0].text(), "a\n");
+        assert!(processor
+            .operate(Operation::StartCommand(Command::Buffer))
+            .iter()
+            .any(|output| matches!(
+                output,
+                Output::Command { command } if command.to_string() == "Buffer <name>: 1+ a.txt | 2% b.txt"
+            )));
+        let _ = processor.operate(Operation::Reset);
+        assert!(processor
+            .confirmation(ConfirmAction::Quit)
+            .message
+            .starts_with("a.txt has unsaved changes."));
+
+        let outputs = processor.operate(Operation::SaveAndQuit);
+        let saves = outputs
+            .iter()
+            .filter(|output| {
+                matches!(
+                    output,
+                    Output::EditDoc {
+                        edit: DocEdit::Save { .. },
+                        ..
+                    }
+                )
+            })
+            .count();
+
+        assert_eq!(saves, 1);
+        assert!(!quits(&outputs));
+        assert!(!processor.buffers.is_empty());
+
+        assert!(quits(&saved(&mut processor, 0, None)));
+        assert!(processor.buffers.is_empty());
+    }
+
+    /// A failed save shall keep the document unsaved and shall cancel quitting.
+    #[test]
+    fn failed_save() {
+        let mut processor = Processor::new();
+
+        let _ = processor.operate(Operation::CreateDoc(File::fixture("a.txt", "a")));
+        format_unsaved(&mut processor, 0);
+        let _ = processor.operate(Operation::SaveAndQuit);
+
+        let outputs = saved(&mut processor, 0, Some("permission denied"));
+        assert!(!quits(&outputs));
+        assert!(outputs.iter().any(|output| matches!(
+            output,
+            Output::Notify { message } if message.typ == MessageType::Error
+                && message.message == "Unable to save a.txt: permission denied. Quitting was cancelled."
+        )));
+        assert!(processor.buffers[0].is_dirty());
+
+        assert!(!quits(&saved(&mut processor, 0, None)));
+        assert!(!processor.buffers[0].is_dirty());
+    }
+
+    /// A change by another program shall reload a clean document and shall keep a dirty document from being saved until it is reloaded or overwritten.
+    #[test]
+    fn external_change() {
+        let mut processor = Processor::new();
+
+        let _ = processor.operate(Operation::CreateDoc(File::fixture("a.txt", "a")));
+        let _ = processor.operate(Operation::ExternalChange(File::fixture("a.txt", "b")));
+        assert_eq!(processor.buffers[0].text(), "b");
+        assert!(!processor.buffers[0].is_dirty());
+
+        format_unsaved(&mut processor, 0);
+        let _ = processor.operate(Operation::ExternalChange(File::fixture("a.txt", "c")));
+        assert_eq!(processor.buffers[0].text(), "b\n");
+        assert!(processor
+            .operate_doc(&DocOp::Save, &mut Vec::new())
+            .is_err());
+
+        let outputs = processor.operate(Operation::SaveAndQuit);
+        assert!(!quits(&outputs));
+        assert!(!outputs
+            .iter()
+            .any(|output| matches!(output, Output::EditDoc { .. })));
+        assert!(processor.buffers[0].external_change.is_some());
+
+        let _ = processor.operate(Operation::Document(DocOp::Reload));
+        assert_eq!(processor.buffers[0].text(), "c");
+        assert!(processor.operate_doc(&DocOp::Save, &mut Vec::new()).is_ok());
+    }
+
+    /// Reloading a document shall highlight its new text as if the document was opened with it.
+    #[test]
+    fn reload_highlights() {
+        let mut processor = Processor::new();
+        let text = "let a;\n/* b\nc */ fn d";
+
+        let _ = processor.operate(Operation::CreateDoc(File::fixture(
+            "a.rs",
+            "let a;\nb\nfn d",
+        )));
+        let _ = processor.operate(Operation::ExternalChange(File::fixture("a.rs", text)));
+
+        let expected = Highlights::new(Some(LanguageId::Rust), text);
+
+        for line in 0..3 {
+            assert_eq!(
+                processor.buffers[0].highlights.line(line),
+                expected.line(line)
+            );
+        }
+    }
+
+    /// Overwriting a document changed by another program shall save it regardless of the change.
+    #[test]
+    fn overwrite() {
+        let mut processor = Processor::new();
+
+        let _ = processor.operate(Operation::CreateDoc(File::fixture("a.txt", "a")));
+        format_unsaved(&mut processor, 0);
+        let _ = processor.operate(Operation::ExternalChange(File::fixture("a.txt", "c")));
+
+        let outputs = processor.operate(Operation::Document(DocOp::Overwrite));
+        assert!(outputs.iter().any(|output| matches!(
+            output,
+            Output::EditDoc {
+                edit: DocEdit::Save {
+                    overwrite: true,
+                    ..
+                },
+                ..
+            }
+        )));
+        assert!(processor.buffers[0].external_change.is_none());
+    }
+
+    /// The status line shall display the mode and the name of the document on the left and the state of its language server and the position of the cursor on the right, separated by at least one space when the page is too narrow.
+    #[test]
+    fn status() {
+        let mut processor = Processor::new();
+        let resize = |width: u16| Operation::Resize {
+            dimensions: Dimensions {
+                height: 10.into(),
+                width: width.into(),
+            },
+        };
+
+        let _ = processor.operate(resize(50));
+        assert_eq!(
+            processor.status().to_string(),
+            format!(" VIEW {}", " ".repeat(44))
+        );
+
+        let _ = processor.operate(Operation::CreateDoc(File::fixture("a.rs", "fn a() {}")));
+        assert_eq!(
+            processor.status().to_string(),
+            format!(" VIEW  a.rs{}Rust starting  Ln 1, Col 1 ", " ".repeat(12))
+        );
+
+        let _ = processor.operate(Operation::Initialized(LanguageId::Rust));
+        format_unsaved(&mut processor, 0);
+        assert_eq!(
+            processor.status().to_string(),
+            format!(" VIEW  a.rs [+]{}Rust ready  Ln 1, Col 1 ", " ".repeat(11))
+        );
+
+        let _ = processor.operate(resize(20));
+        assert_eq!(
+            processor.status().to_string(),
+            " VIEW  a.rs [+] Rust ready  Ln 1, Col 1 "
+        );
+    }
+
+    /// The theme file shall be loaded whenever the theme is given, including the theme of the initial configuration.
+    #[test]
+    fn theme() {
+        let mut processor = Processor::new();
+
+        for theme in ["paper-theme.toml", "dark.toml", "dark.toml"] {
+            let outputs = processor.process(Input::Setting(Setting::Theme(theme.to_string())));
+            assert!(outputs.iter().any(|output| matches!(
+                output,
+                Output::SetTheme { theme: set } if set == theme
+            )));
+        }
+    }
+
+    /// A document whose charset is not UTF-8 shall not be saved.
+    #[test]
+    fn unwritable_charset() {
+        let mut processor = Processor::new();
+        let latin1 = File::fixture("a.txt", "a").with_overrides(Overrides {
+            charset: Some(Charset::Latin1),
+            ..Overrides::default()
+        });
+
+        let _ = processor.operate(Operation::CreateDoc(latin1));
+
+        let outputs = processor.operate(Operation::Document(DocOp::Save));
+        assert!(!outputs
+            .iter()
+            .any(|output| matches!(output, Output::EditDoc { .. })));
+        assert!(outputs.iter().any(|output| matches!(
+            output,
+            Output::Notify { message } if message.typ == MessageType::Warning
+                && message.message == "a.txt is configured with charset `latin1`, which is unable to be written; only UTF-8 is supported"
+        )));
+    }
+
+    /// Commands of the command line shall be parsed and performed, and a command that is not valid shall be reported.
+    #[test]
+    fn command_line() {
+        let mut processor = Processor::new();
+
+        let _ = processor.operate(Operation::CreateDoc(File::fixture("a.txt", "a")));
+        assert!(!indexes(
+            &processor.operate(Operation::StartCommand(Command::Line))
+        ));
+        let _ = processor.operate(Operation::Collect('s'));
+        let outputs = processor.operate(Operation::Complete);
+
+        assert!(outputs.iter().any(|output| matches!(
+            output,
+            Output::Command { command } if command.to_string() == ":s   (set | split)"
+        )));
+
+        let _ = processor.operate(Operation::StartCommand(Command::Line));
+        type_line(&mut processor, "set tab_width=2");
+        let _ = processor.operate(Operation::Execute);
+        assert_eq!(processor.config.tab_width, 2);
+
+        format_unsaved(&mut processor, 0);
+        let _ = processor.operate(Operation::StartCommand(Command::Line));
+        type_line(&mut processor, "quit");
+        let outputs = processor.operate(Operation::Execute);
+        assert!(!outputs.iter().any(|output| matches!(output, Output::Quit)));
+        assert!(outputs.iter().any(|output| matches!(
+            output,
+            Output::Notify { message } if message.typ == MessageType::Warning
+        )));
+
+        let _ = processor.operate(Operation::StartCommand(Command::Line));
+        type_line(&mut processor, "quit!");
+        let outputs = processor.operate(Operation::Execute);
+        assert!(outputs.iter().any(|output| matches!(output, Output::Quit)));
+
+        let _ = processor.operate(Operation::StartCommand(Command::Line));
+        let _ = processor.operate(Operation::EditInput(InputEdit::Previous));
+        let _ = processor.operate(Operation::EditInput(InputEdit::Previous));
+        let outputs = processor.operate(Operation::EditInput(InputEdit::Previous));
+        assert!(outputs.iter().any(|output| matches!(
+            output,
+            Output::Command { command } if command.to_string() == ":set tab_width=2 "
+        )));
+    }
+
+    /// Writing to a path shall write the formatted text and shall only overwrite an existing file when forced.
+    #[test]
+    fn write_path() {
+        let mut processor = Processor::new();
+
+        let _ = processor.operate(Operation::CreateDoc(File::fixture("a.txt", "a")));
+        let _ = processor.process(Input::Setting(Setting::FinalNewline(true)));
+
+        for (line, is_forced) in [("write b.txt", false), ("write! b.txt", true)] {
+            let _ = processor.operate(Operation::StartCommand(Command::Line));
+            type_line(&mut processor, line);
+            let outputs = processor.operate(Operation::Execute);
+            assert!(outputs.iter().any(|output| matches!(
+                output,
+                Output::WriteFile { path, text, overwrite }
+                    if path == "b.txt" && text == "a\n" && *overwrite == is_forced
+            )));
+        }
+
+        assert_eq!(processor.buffers[0].text(), "a");
+    }
+
+    /// The open command shall complete paths and open the selected match of the file finder.
+    #[test]
+    fn open() {
+        let mut processor = Processor::new();
+        let files = vec!["README.md".to_string(), "src/app/view.rs".to_string()];
+
+        let outputs = processor.operate(Operation::StartCommand(Command::Open));
+        assert!(indexes(&outputs));
+        let _ = processor.operate(Operation::LoadIndex(Index {
+            files,
+            is_truncated: false,
+        }));
+
+        type_line(&mut processor, "sr");
+        let _ = processor.operate(Operation::Complete);
+        assert_eq!(processor.prompt.text(), "src/");
+        let _ = processor.operate(Operation::Complete);
+        let _ = processor.operate(Operation::Complete);
+        assert_eq!(processor.prompt.text(), "src/app/view.rs");
+
+        let _ = processor.operate(Operation::StartCommand(Command::Open));
+        type_line(&mut processor, "e");
+        let outputs = processor.operate(Operation::SelectMatch(Switch::Next));
+        assert!(outputs.iter().any(|output| matches!(
+            output,
+            Output::Command { command } if command.to_string() == "e   README.md | src/app/view.rs  (2/2)"
+        )));
+        let outputs = processor.operate(Operation::Execute);
+        assert!(outputs.iter().any(|output| matches!(
+            output,
+            Output::OpenFile { path } if path == "src/app/view.rs"
+        )));
+    }
+
+    /// The files shall only be indexed for a command line that completes a path and a truncated index shall be warned about once.
+    #[test]
+    fn index() {
+        let mut processor = Processor::new();
+        let truncated = || {
+            Operation::LoadIndex(Index {
+                files: vec!["a.txt".to_string()],
+                is_truncated: true,
+            })
+        };
+        let warns = |outputs: &[Output]| {
+            outputs.iter().any(|output| {
+                matches!(
+                    output,
+                    Output::Notify { message } if message.typ == MessageType::Warning
+                )
+            })
+        };
+
+        let _ = processor.operate(Operation::StartCommand(Command::Line));
+        type_line(&mut processor, "writ");
+        assert!(!indexes(&processor.operate(Operation::Collect('e'))));
+        assert!(indexes(&processor.operate(Operation::Collect(' '))));
+
+        assert!(warns(&processor.operate(truncated())));
+        assert!(!warns(&processor.operate(truncated())));
+        assert_eq!(processor.finder.files(), ["a.txt"]);
+    }
+
+    /// A key bound to several actions shall perform each of them in order.
+    #[test]
+    fn composite() {
+        let mut processor = Processor::new();
+
+        let _ = processor.operate(Operation::CreateDoc(File::fixture("a.txt", "a")));
+        let _ = processor.operate(Operation::CreateDoc(File::fixture("b.txt", "b")));
+        let _ = processor.process(Input::Setting(Setting::Keymap(
+            toml::from_str("[view]\nx = [\"save\", \"next_buffer\"]").unwrap(),
+        )));
+
+        let outputs = processor.process(Input::User(UserAction::Key {
+            code: KeyCode::Char('x'),
+            modifiers: KeyModifiers::empty(),
+        }));
+
+        assert!(outputs.iter().any(|output| matches!(
+            output,
+            Output::EditDoc {
+                edit: DocEdit::Save { .. },
+                ..
+            }
+        )));
+        assert_eq!(visible(&processor), Some("a.txt".to_string()));
+    }
+}
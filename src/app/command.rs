@@ -0,0 +1,423 @@
+//! Implements the language of the command line.
+use {
+    super::translate::Split,
+    crate::io::config::{Setting, SETTING_NAMES},
+    core::str::FromStr,
+    thiserror::Error,
+};
+
+/// The names of the commands.
+const COMMAND_NAMES: [&str; 9] = [
+    "buffer", "open", "quit", "quit!", "set", "split", "vsplit", "write", "write!",
+];
+
+/// A command given on the command line.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum LineCommand {
+    /// Opens the file at the path.
+    Open(String),
+    /// Saves the document, or writes its text to the path if one is given.
+    Write {
+        /// The path to which the text is written instead of the file of the document.
+        path: Option<String>,
+        /// If a change to the file of the document by another program or an existing file at `path` is overwritten.
+        is_forced: bool,
+    },
+    /// Quits the application.
+    Quit {
+        /// If documents with unsaved changes are discarded.
+        is_forced: bool,
+    },
+    /// Updates a setting.
+    Set(Setting),
+    /// Displays the open document with the number or name.
+    Buffer(String),
+    /// Splits the focused pane.
+    Split(Split),
+}
+
+impl FromStr for LineCommand {
+    type Err = ParseCommandError;
+
+    /// Parses `s` as a command name followed by its arguments, separated by whitespace.
+    ///
+    /// An argument that contains whitespace is surrounded by quotes or has its whitespace escaped with `\`.
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (words, quote) = words(s);
+
+        if let Some(quote) = quote {
+            return Err(ParseCommandError::UnclosedQuote(quote));
+        }
+
+        let mut words = words.into_iter().map(|(_, word)| word);
+        let name = words.next().ok_or(ParseCommandError::Empty)?;
+        let arguments: Vec<String> = words.collect();
+        let argument = || match arguments.as_slice() {
+            [] => Ok(None),
+            [argument] => Ok(Some(argument.clone())),
+            _ => Err(ParseCommandError::ExtraArguments(name.clone())),
+        };
+        let required =
+            || argument()?.ok_or_else(|| ParseCommandError::MissingArgument(name.clone()));
+        let no_argument = || match arguments.as_slice() {
+            [] => Ok(()),
+            _ => Err(ParseCommandError::ExtraArguments(name.clone())),
+        };
+
+        match name.as_str() {
+            "open" => Ok(Self::Open(required()?)),
+            "write" | "write!" => Ok(Self::Write {
+                path: argument()?,
+                is_forced: name == "write!",
+            }),
+            "quit" | "quit!" => no_argument().map(|_| Self::Quit {
+                is_forced: name == "quit!",
+            }),
+            "set" => {
+                let (setting, value) = match arguments.as_slice() {
+                    [assignment] => assignment
+                        .split_once('=')
+                        .ok_or(ParseCommandError::Assignment)?,
+                    [setting, value] => (setting.as_str(), value.as_str()),
+                    _ => return Err(ParseCommandError::Assignment),
+                };
+
+                Setting::parse(setting.trim(), value.trim())
+                    .map(Self::Set)
+                    .map_err(ParseCommandError::Setting)
+            }
+            "buffer" => Ok(Self::Buffer(required()?)),
+            "split" => no_argument().map(|_| Self::Split(Split::Horizontal)),
+            "vsplit" => no_argument().map(|_| Self::Split(Split::Vertical)),
+            _ => Err(ParseCommandError::Unknown(name)),
+        }
+    }
+}
+
+/// An error parsing a [`LineCommand`].
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub(crate) enum ParseCommandError {
+    /// No command was given.
+    #[error("no command was given")]
+    Empty,
+    /// A quote was not closed.
+    #[error("missing closing {0}")]
+    UnclosedQuote(char),
+    /// The command is unknown.
+    #[error("unknown command `{0}`")]
+    Unknown(String),
+    /// The command requires an argument.
+    #[error("`{0}` requires an argument")]
+    MissingArgument(String),
+    /// The command was given more arguments than it accepts.
+    #[error("`{0}` was given too many arguments")]
+    ExtraArguments(String),
+    /// The argument of `set` was not an assignment.
+    #[error("`set` requires an argument of the form `name=value`")]
+    Assignment,
+    /// The setting is invalid.
+    #[error("{0}")]
+    Setting(String),
+}
+
+/// The completion of the last word of a command line.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Completion {
+    /// The command line with its last word completed as far as every candidate agrees.
+    pub(crate) line: String,
+    /// The candidates that begin with the last word.
+    pub(crate) candidates: Vec<String>,
+}
+
+/// Returns the [`Completion`] of the last word of `line`, where `buffers` are the names of the open documents and `files` are the paths of the files of the project.
+///
+/// The first word is completed from the names of commands. The arguments of `set` are completed from the names of settings and their values, the argument of `buffer` is completed from `buffers` and the arguments of `open`, `write` and `write!` are completed from `files`.
+pub(crate) fn complete(line: &str, buffers: &[String], files: &[String]) -> Completion {
+    let (words, (start, word)) = split_last_word(line);
+    let candidates: Vec<String> = match words.first().map(|(_, name)| name.as_str()) {
+        None => COMMAND_NAMES
+            .iter()
+            .map(|name| (*name).to_string())
+            .collect(),
+        Some("set") if words.len() == 1 => match word.split_once('=') {
+            Some((setting, _)) => values(setting)
+                .iter()
+                .map(|value| format!("{}={}", setting, value))
+                .collect(),
+            None => SETTING_NAMES
+                .iter()
+                .map(|setting| format!("{}=", setting))
+                .collect(),
+        },
+        Some("buffer") if words.len() == 1 => buffers.to_vec(),
+        Some("open" | "write" | "write!") if words.len() == 1 => paths(&word, files),
+        Some(_) => Vec::new(),
+    }
+    .into_iter()
+    .filter(|candidate| candidate.starts_with(&word))
+    .collect();
+    let completion = match candidates.as_slice() {
+        [] => None,
+        [candidate] if candidate.ends_with(['=', '/']) => Some(escape(candidate)),
+        [candidate] => Some(format!("{} ", escape(candidate))),
+        [first, rest @ ..] => Some(escape(
+            &rest.iter().fold(first.clone(), |common, candidate| {
+                common_prefix(&common, candidate)
+            }),
+        )),
+    };
+
+    Completion {
+        line: completion.map_or_else(
+            || line.to_string(),
+            |completion| format!("{}{}", line.get(..start).unwrap_or(""), completion),
+        ),
+        candidates,
+    }
+}
+
+/// Returns the [`Completion`] of `path`, which is relative to the root directory, where `files` are the paths of the files of the project.
+///
+/// Since the whole input is the path, it is neither escaped nor followed by a space.
+pub(crate) fn complete_path(path: &str, files: &[String]) -> Completion {
+    let candidates: Vec<String> = paths(path, files)
+        .into_iter()
+        .filter(|candidate| candidate.starts_with(path))
+        .collect();
+
+    Completion {
+        line: match candidates.split_first() {
+            Some((first, rest)) => rest.iter().fold(first.clone(), |common, candidate| {
+                common_prefix(&common, candidate)
+            }),
+            None => path.to_string(),
+        },
+        candidates,
+    }
+}
+
+/// Returns the paths within the directory of `path` that lead to `files`, in order.
+///
+/// A path that leads to a directory ends with `/`.
+fn paths(path: &str, files: &[String]) -> Vec<String> {
+    let dir = path
+        .rfind('/')
+        .map_or("", |index| path.get(..=index).unwrap_or_default());
+    let mut paths: Vec<String> = files
+        .iter()
+        .filter_map(|file| file.strip_prefix(dir))
+        .map(|rest| {
+            let entry = rest
+                .find('/')
+                .map_or(rest, |index| rest.get(..=index).unwrap_or(rest));
+
+            format!("{}{}", dir, entry)
+        })
+        .collect();
+
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/// Returns the values that the setting named `setting` is completed with.
+fn values(setting: &str) -> &'static [&'static str] {
+    match setting {
+        "wrap" => &["none", "char", "word"],
+        "line_numbers" => &["none", "absolute", "relative", "hybrid"],
+        "expand_tabs" | "auto_save" | "trim_trailing_whitespace" | "final_newline" | "backup" => {
+            &["true", "false"]
+        }
+        _ => &[],
+    }
+}
+
+/// Returns if the last word of `line` is the path argument of `open`, `write` or `write!`, i.e. if it is completed from the files of the project.
+pub(crate) fn completes_path(line: &str) -> bool {
+    matches!(split_last_word(line).0.as_slice(), [(_, name)] if ["open", "write", "write!"].contains(&name.as_str()))
+}
+
+/// Returns the words of `line` that precede its last word, followed by the last word, each with the byte index at which it starts.
+///
+/// If `line` ends with whitespace that is not quoted, the last word is the empty word that begins at the end of `line`.
+fn split_last_word(line: &str) -> (Vec<(usize, String)>, (usize, String)) {
+    let (mut words, quote) = words(line);
+
+    if quote.is_none() && (words.is_empty() || line.ends_with(char::is_whitespace)) {
+        words.push((line.len(), String::new()));
+    }
+
+    let last = words.pop().unwrap_or_default();
+
+    (words, last)
+}
+
+/// Returns the words of `line`, each with the byte index at which it starts, and the quote that is not closed, if any.
+///
+/// Words are separated by whitespace. Within double quotes or outside of quotes, `\` escapes the following character; within single quotes, every character is literal.
+fn words(line: &str) -> (Vec<(usize, String)>, Option<char>) {
+    let mut words = Vec::new();
+    let mut word: Option<(usize, String)> = None;
+    let mut quote = None;
+    let mut chars = line.char_indices();
+
+    while let Some((index, c)) = chars.next() {
+        match (quote, c) {
+            (None, _) if c.is_whitespace() => words.extend(word.take()),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                let _ = word.get_or_insert_with(|| (index, String::new()));
+            }
+            (Some(closing), _) if c == closing => quote = None,
+            (Some('\''), _) => word.get_or_insert_with(|| (index, String::new())).1.push(c),
+            (_, '\\') => {
+                let (_, text) = word.get_or_insert_with(|| (index, String::new()));
+
+                if let Some((_, escaped)) = chars.next() {
+                    text.push(escaped);
+                }
+            }
+            _ => word.get_or_insert_with(|| (index, String::new())).1.push(c),
+        }
+    }
+
+    words.extend(word);
+    (words, quote)
+}
+
+/// Returns `word` with each character that would otherwise separate or quote it escaped by `\`.
+fn escape(word: &str) -> String {
+    word.chars().fold(String::new(), |mut escaped, c| {
+        if c.is_whitespace() || matches!(c, '"' | '\'' | '\\') {
+            escaped.push('\\');
+        }
+
+        escaped.push(c);
+        escaped
+    })
+}
+
+/// Returns the longest prefix shared by `a` and `b`.
+fn common_prefix(a: &str, b: &str) -> String {
+    a.chars()
+        .zip(b.chars())
+        .take_while(|(a_char, b_char)| a_char == b_char)
+        .map(|(c, _)| c)
+        .collect()
+}
+
+/// Testing of the command module.
+#[cfg(test)]
+mod test {
+    use {super::*, crate::io::config::Wrap};
+
+    /// Commands shall be parsed from their name and arguments, which may be quoted.
+    #[test]
+    fn parse() {
+        assert_eq!(
+            "open 'my file.txt'".parse(),
+            Ok(LineCommand::Open("my file.txt".to_string()))
+        );
+        assert_eq!(
+            r#"write "a \"b\"" "#.parse(),
+            Ok(LineCommand::Write {
+                path: Some("a \"b\"".to_string()),
+                is_forced: false
+            })
+        );
+        assert_eq!(
+            "write".parse(),
+            Ok(LineCommand::Write {
+                path: None,
+                is_forced: false
+            })
+        );
+        assert_eq!(
+            "write! b.txt".parse(),
+            Ok(LineCommand::Write {
+                path: Some("b.txt".to_string()),
+                is_forced: true
+            })
+        );
+        assert_eq!(
+            r"open a\ b".parse(),
+            Ok(LineCommand::Open("a b".to_string()))
+        );
+        assert_eq!("quit!".parse(), Ok(LineCommand::Quit { is_forced: true }));
+        assert_eq!(
+            "set wrap=word".parse(),
+            Ok(LineCommand::Set(Setting::Wrap(Wrap::Word)))
+        );
+        assert_eq!(
+            "set tab_width 2".parse(),
+            Ok(LineCommand::Set(Setting::TabWidth(2)))
+        );
+        assert_eq!("vsplit".parse(), Ok(LineCommand::Split(Split::Vertical)));
+        assert_eq!(
+            "open".parse::<LineCommand>(),
+            Err(ParseCommandError::MissingArgument("open".to_string()))
+        );
+        assert_eq!(
+            "quit now".parse::<LineCommand>(),
+            Err(ParseCommandError::ExtraArguments("quit".to_string()))
+        );
+        assert_eq!(
+            "open 'a".parse::<LineCommand>(),
+            Err(ParseCommandError::UnclosedQuote('\''))
+        );
+        assert_eq!(
+            "edit a".parse::<LineCommand>(),
+            Err(ParseCommandError::Unknown("edit".to_string()))
+        );
+        assert_eq!("  ".parse::<LineCommand>(), Err(ParseCommandError::Empty));
+    }
+
+    /// The last word shall be completed as far as its candidates agree.
+    #[test]
+    fn completion() {
+        let buffers = vec!["main.rs".to_string(), "my notes.txt".to_string()];
+        let files = vec![
+            "README.md".to_string(),
+            "src/app.rs".to_string(),
+            "src/app/view.rs".to_string(),
+            "src/main.rs".to_string(),
+        ];
+
+        assert_eq!(
+            complete("s", &buffers, &files),
+            Completion {
+                line: "s".to_string(),
+                candidates: vec!["set".to_string(), "split".to_string()],
+            }
+        );
+        assert_eq!(complete("wr", &buffers, &files).line, "write");
+        assert_eq!(complete("set tab", &buffers, &files).line, "set tab_width=");
+        assert_eq!(
+            complete("set wrap=w", &buffers, &files).line,
+            "set wrap=word "
+        );
+        assert_eq!(
+            complete("buffer my", &buffers, &files).line,
+            r"buffer my\ notes.txt "
+        );
+        assert_eq!(complete("buffer m", &buffers, &files).line, "buffer m");
+        assert_eq!(complete("buffer ", &buffers, &files).candidates, buffers);
+        assert!(complete("quit ", &buffers, &files).candidates.is_empty());
+        assert_eq!(complete("open s", &buffers, &files).line, "open src/");
+        assert!(completes_path("open "));
+        assert!(completes_path("write \"my n"));
+        assert!(!completes_path("open"));
+        assert!(!completes_path("buffer s"));
+        assert!(!completes_path("open a b"));
+        assert_eq!(
+            complete_path("src/a", &files),
+            Completion {
+                line: "src/app".to_string(),
+                candidates: vec!["src/app.rs".to_string(), "src/app/".to_string()],
+            }
+        );
+        assert_eq!(complete_path("src/m", &files).line, "src/main.rs");
+        assert_eq!(complete_path("x", &files).line, "x");
+    }
+}
@@ -0,0 +1,212 @@
+//! Implements the fuzzy finder of the files of the project.
+use {
+    super::translate::Switch,
+    crate::io::ui::{Row, Scope},
+    core::cmp::Reverse,
+};
+
+/// The number of matches that are displayed at once.
+const MATCH_LIMIT: usize = 5;
+
+/// The score of each matched character.
+const MATCH_SCORE: i64 = 1;
+/// The bonus of a matched character that immediately precedes the next matched character.
+const CONSECUTIVE_BONUS: i64 = 5;
+/// The bonus of a matched character that begins a word.
+const WORD_BONUS: i64 = 3;
+/// The bonus of a matched character within the name of the file.
+const NAME_BONUS: i64 = 2;
+
+/// Finds the files of the project whose paths match a query.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Finder {
+    /// The paths of the files of the project, relative to the root directory.
+    files: Vec<String>,
+    /// The current query.
+    query: String,
+    /// The paths that match `query`, from best to worst.
+    matches: Vec<String>,
+    /// The index of the selected path within `matches`.
+    selected: usize,
+}
+
+impl Finder {
+    /// Returns the paths of the files of the project.
+    pub(crate) fn files(&self) -> &[String] {
+        &self.files
+    }
+
+    /// Replaces the paths of the files of the project with `files`, searching them for the current query.
+    pub(crate) fn set_files(&mut self, files: Vec<String>) {
+        self.files = files;
+        self.search(&self.query.clone());
+    }
+
+    /// Finds the paths that match `query`, selecting the best one.
+    ///
+    /// An empty query matches nothing.
+    pub(crate) fn search(&mut self, query: &str) {
+        let mut scored: Vec<(i64, &String)> = if query.is_empty() {
+            Vec::new()
+        } else {
+            self.files
+                .iter()
+                .filter_map(|file| score(query, file).map(|score| (score, file)))
+                .collect()
+        };
+
+        scored.sort_by_key(|(score, file)| (Reverse(*score), file.len(), *file));
+        self.matches = scored.into_iter().map(|(_, file)| file.clone()).collect();
+        self.query = query.to_string();
+        self.selected = 0;
+    }
+
+    /// Selects the match indicated by `switch`.
+    pub(crate) fn select(&mut self, switch: Switch) {
+        let count = self.matches.len();
+
+        if count > 0 {
+            self.selected = match switch {
+                Switch::Next => self.selected.saturating_add(1) % count,
+                Switch::Previous => self.selected.checked_sub(1).unwrap_or(count - 1),
+            };
+        }
+    }
+
+    /// Returns the path of the file that is opened when `input` is given to the finder.
+    ///
+    /// `input` is opened as given if it is the path of a file of the project or if it matches nothing; otherwise the selected match is opened.
+    pub(crate) fn target(&self, input: String) -> String {
+        if self.files.contains(&input) {
+            input
+        } else {
+            self.matches.get(self.selected).cloned().unwrap_or(input)
+        }
+    }
+
+    /// Returns the [`Row`] that displays the page of matches that holds the selected match, followed by its position among the matches.
+    pub(crate) fn row(&self) -> Row {
+        let mut row = Row::default();
+        let scope = Scope::new("ui.selection".to_string());
+        let start = self.selected.saturating_sub(self.selected % MATCH_LIMIT);
+
+        for (index, file) in self
+            .matches
+            .iter()
+            .enumerate()
+            .skip(start)
+            .take(MATCH_LIMIT)
+        {
+            row.push_str(if index == start { "  " } else { " | " }, None);
+            row.push_str(file, Some(&scope).filter(|_| index == self.selected));
+        }
+
+        if !self.matches.is_empty() {
+            row.push_str(
+                &format!(
+                    "  ({}/{})",
+                    self.selected.saturating_add(1),
+                    self.matches.len()
+                ),
+                None,
+            );
+        }
+
+        row
+    }
+}
+
+/// Returns the score of `path` as a match of `query`, or [`None`] if the characters of `query` do not appear in `path` in order.
+///
+/// Case is ignored. The characters are matched from the end of `path` so that matches within the name of the file are preferred. Each matched character scores a point, with bonuses if it is followed by the next matched character, begins a word or is within the name of the file.
+fn score(query: &str, path: &str) -> Option<i64> {
+    let chars: Vec<char> = path.chars().flat_map(char::to_lowercase).collect();
+    let name_start = chars
+        .iter()
+        .rposition(|c| *c == '/')
+        .map_or(0, |index| index.saturating_add(1));
+    let mut end = chars.len();
+    let mut next_match = None;
+    let mut score: i64 = 0;
+
+    for c in query.chars().flat_map(char::to_lowercase).rev() {
+        let index = chars
+            .get(..end)?
+            .iter()
+            .rposition(|path_char| *path_char == c)?;
+
+        score = score.saturating_add(MATCH_SCORE);
+
+        if next_match == Some(index.saturating_add(1)) {
+            score = score.saturating_add(CONSECUTIVE_BONUS);
+        }
+
+        if index
+            .checked_sub(1)
+            .and_then(|previous| chars.get(previous))
+            .is_none_or(|previous| matches!(previous, '/' | '_' | '-' | '.' | ' '))
+        {
+            score = score.saturating_add(WORD_BONUS);
+        }
+
+        if index >= name_start {
+            score = score.saturating_add(NAME_BONUS);
+        }
+
+        next_match = Some(index);
+        end = index;
+    }
+
+    Some(score)
+}
+
+/// Testing of the finder module.
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Returns a [`Finder`] of the given files.
+    fn finder(files: &[&str]) -> Finder {
+        let mut finder = Finder::default();
+
+        finder.set_files(files.iter().map(|file| (*file).to_string()).collect());
+        finder
+    }
+
+    /// Matches shall be ranked by how well their names match the query.
+    #[test]
+    fn rank() {
+        let mut finder = finder(&["src/app/view.rs", "src/io/ui.rs", "src/app.rs", "README.md"]);
+
+        finder.search("app");
+        assert_eq!(finder.matches, vec!["src/app.rs", "src/app/view.rs"]);
+        finder.search("vw");
+        assert_eq!(finder.matches, vec!["src/app/view.rs"]);
+        finder.search("REA");
+        assert_eq!(finder.matches, vec!["README.md"]);
+        finder.search("xyz");
+        assert!(finder.matches.is_empty());
+    }
+
+    /// The selected match shall be opened unless the input is a file of the project or matches nothing.
+    #[test]
+    fn target() {
+        let mut finder = finder(&["src/app/view.rs", "src/app.rs"]);
+
+        finder.search("app");
+        assert_eq!(finder.target("app".to_string()), "src/app.rs");
+        finder.select(Switch::Next);
+        assert_eq!(finder.target("app".to_string()), "src/app/view.rs");
+        finder.select(Switch::Next);
+        assert_eq!(finder.target("app".to_string()), "src/app.rs");
+
+        finder.search("src/app/view.rs");
+        finder.select(Switch::Previous);
+        assert_eq!(
+            finder.target("src/app/view.rs".to_string()),
+            "src/app/view.rs"
+        );
+        finder.search("new.txt");
+        assert_eq!(finder.target("new.txt".to_string()), "new.txt");
+    }
+}
@@ -0,0 +1,521 @@
+//! Implements syntax highlighting of documents that does not rely on a language server.
+use crate::io::{ui::Scope, LanguageId};
+
+/// The rules for highlighting the syntax of a language.
+#[derive(Debug)]
+pub(crate) struct Grammar {
+    /// The words that are keywords.
+    keywords: &'static [&'static str],
+    /// The words that are constants.
+    constants: &'static [&'static str],
+    /// The marker that begins a comment which ends at the end of the line.
+    line_comment: &'static str,
+    /// The markers that begin and end a block comment.
+    block_comment: (&'static str, &'static str),
+    /// If block comments may be nested within each other.
+    nests_block_comments: bool,
+    /// If strings may be raw, i.e. `r#"text"#`.
+    has_raw_strings: bool,
+    /// If `'` may begin a lifetime instead of a character.
+    has_lifetimes: bool,
+    /// If an identifier followed by `!` is a macro.
+    has_macros: bool,
+}
+
+/// The [`Grammar`] of rust.
+static RUST: Grammar = Grammar {
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+        "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "type",
+        "unsafe", "use", "where", "while",
+    ],
+    constants: &["true", "false"],
+    line_comment: "//",
+    block_comment: ("/*", "*/"),
+    nests_block_comments: true,
+    has_raw_strings: true,
+    has_lifetimes: true,
+    has_macros: true,
+};
+
+impl LanguageId {
+    /// Returns the [`Grammar`] of `self`.
+    #[allow(clippy::missing_const_for_fn)] // For stable rust, match is not allowed in const fn.
+    fn grammar(self) -> &'static Grammar {
+        match self {
+            Self::Rust => &RUST,
+        }
+    }
+}
+
+/// The construct that is open at the end of a line and thus continues onto the next line.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum LineState {
+    /// No construct is open.
+    Normal,
+    /// Within a block comment nested `depth` deep.
+    BlockComment {
+        /// The number of open block comments.
+        depth: usize,
+    },
+    /// Within a string.
+    String,
+    /// Within a raw string that is closed by `"` followed by `hashes` `#`s.
+    RawString {
+        /// The number of `#`s.
+        hashes: usize,
+    },
+}
+
+/// A section of a line that has a [`Scope`].
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Highlight {
+    /// The index of the first character.
+    pub(crate) start: usize,
+    /// The number of characters.
+    pub(crate) length: usize,
+    /// The [`Scope`].
+    pub(crate) scope: Scope,
+}
+
+/// The highlighting of a single line.
+#[derive(Clone, Debug)]
+struct HighlightedLine {
+    /// The text of the line.
+    text: String,
+    /// The [`LineState`] at the start of the line.
+    start_state: LineState,
+    /// The [`LineState`] at the end of the line.
+    end_state: LineState,
+    /// The [`Highlight`]s of the line.
+    highlights: Vec<Highlight>,
+}
+
+/// The syntax highlighting of a document.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Highlights {
+    /// The [`Grammar`] of the document.
+    ///
+    /// [`None`] indicates the language of the document is unknown and thus nothing is highlighted.
+    grammar: Option<&'static Grammar>,
+    /// The highlighting of each line.
+    lines: Vec<HighlightedLine>,
+}
+
+impl Highlights {
+    /// Creates a new [`Highlights`] for `text` written in the language of `language_id`.
+    pub(crate) fn new(language_id: Option<LanguageId>, text: &str) -> Self {
+        let mut highlights = Self {
+            grammar: language_id.map(LanguageId::grammar),
+            lines: Vec::new(),
+        };
+
+        highlights.update(text, 0);
+        highlights
+    }
+
+    /// Returns the [`Highlight`]s of the line at `index`.
+    pub(crate) fn line(&self, index: usize) -> &[Highlight] {
+        self.lines
+            .get(index)
+            .map_or(&[], |line| line.highlights.as_slice())
+    }
+
+    /// Updates `self` to match `text`, where no line before `first_line` has changed.
+    ///
+    /// Lines are highlighted from `first_line` until reaching a line that is unchanged and begins in the same [`LineState`] as before, after which the previous highlighting is reused.
+    pub(crate) fn update(&mut self, text: &str, first_line: usize) {
+        let grammar = if let Some(grammar) = self.grammar {
+            grammar
+        } else {
+            return;
+        };
+        let new_lines: Vec<&str> = text.lines().collect();
+        let first_line = first_line.min(self.lines.len()).min(new_lines.len());
+        let old_lines = self.lines.split_off(first_line);
+        // The number of lines from first_line in the new text.
+        let remaining = new_lines.len().saturating_sub(first_line);
+        let mut state = self
+            .lines
+            .last()
+            .map_or(LineState::Normal, |line| line.end_state);
+
+        for (index, text) in new_lines.iter().enumerate().skip(first_line) {
+            // Lines after the edit are shifted by the difference in the number of lines.
+            if let Some(old_index) = index
+                .saturating_sub(first_line)
+                .checked_add(old_lines.len())
+                .and_then(|sum| sum.checked_sub(remaining))
+            {
+                if index > first_line {
+                    if let Some(old_line) = old_lines.get(old_index) {
+                        if old_line.start_state == state && old_line.text == *text {
+                            self.lines.extend(old_lines.into_iter().skip(old_index));
+                            return;
+                        }
+                    }
+                }
+            }
+
+            let (highlights, end_state) = highlight_line(grammar, text, state);
+
+            self.lines.push(HighlightedLine {
+                text: (*text).to_string(),
+                start_state: state,
+                end_state,
+                highlights,
+            });
+            state = end_state;
+        }
+    }
+}
+
+/// Returns the [`Highlight`]s of `line`, which begins in `state`, and the [`LineState`] at its end.
+fn highlight_line(grammar: &Grammar, line: &str, state: LineState) -> (Vec<Highlight>, LineState) {
+    let mut lexer = Lexer {
+        grammar,
+        chars: line.chars().collect(),
+        index: 0,
+        highlights: Vec::new(),
+    };
+    let mut state = lexer.resume(state);
+
+    while state == LineState::Normal && lexer.index < lexer.chars.len() {
+        state = lexer.step();
+    }
+
+    (lexer.highlights, state)
+}
+
+/// Splits a single line into [`Highlight`]s.
+struct Lexer<'a> {
+    /// The [`Grammar`] of the line.
+    grammar: &'a Grammar,
+    /// The characters of the line.
+    chars: Vec<char>,
+    /// The index of the current character.
+    index: usize,
+    /// The [`Highlight`]s that have been found.
+    highlights: Vec<Highlight>,
+}
+
+impl Lexer<'_> {
+    /// Returns the character that is `offset` characters after the current one.
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.index
+            .checked_add(offset)
+            .and_then(|index| self.chars.get(index))
+            .copied()
+    }
+
+    /// Returns if the characters beginning at the current one match `pattern`.
+    fn is_at(&self, pattern: &str) -> bool {
+        pattern
+            .chars()
+            .enumerate()
+            .all(|(offset, ch)| self.peek(offset) == Some(ch))
+    }
+
+    /// Records the characters from `start` to the current one as `scope`.
+    fn highlight(&mut self, start: usize, scope: &str) {
+        if self.index > start {
+            self.highlights.push(Highlight {
+                start,
+                length: self.index.saturating_sub(start),
+                scope: Scope::new(scope.to_string()),
+            });
+        }
+    }
+
+    /// Advances by `count` characters, stopping at the end of the line.
+    fn advance(&mut self, count: usize) {
+        self.index = self.index.saturating_add(count).min(self.chars.len());
+    }
+
+    /// Continues the construct described by `state` from the start of the line.
+    fn resume(&mut self, state: LineState) -> LineState {
+        match state {
+            LineState::Normal => LineState::Normal,
+            LineState::BlockComment { depth } => self.block_comment(0, depth),
+            LineState::String => self.string(0),
+            LineState::RawString { hashes } => self.raw_string(0, hashes),
+        }
+    }
+
+    /// Highlights the next construct of a line that is in [`LineState::Normal`].
+    fn step(&mut self) -> LineState {
+        let start = self.index;
+        let (open_comment, _) = self.grammar.block_comment;
+
+        if self.is_at(self.grammar.line_comment) {
+            self.index = self.chars.len();
+            self.highlight(start, "comment");
+        } else if self.is_at(open_comment) {
+            self.advance(open_comment.chars().count());
+            return self.block_comment(start, 1);
+        } else if self.is_at("\"") || self.is_at("b\"") {
+            self.advance(if self.is_at("b") { 2 } else { 1 });
+            return self.string(start);
+        } else if self.grammar.has_raw_strings && (self.is_at("r") || self.is_at("br")) {
+            self.advance(if self.is_at("b") { 2 } else { 1 });
+            let hashes = self.count_while(|ch| ch == '#');
+
+            if self.peek(hashes) == Some('"') {
+                self.advance(hashes.saturating_add(1));
+                return self.raw_string(start, hashes);
+            }
+
+            self.index = start;
+            self.word();
+        } else if self.is_at("'") {
+            self.quote();
+        } else if self.peek(0).is_some_and(|ch| ch.is_ascii_digit()) {
+            self.number();
+        } else if self.peek(0).is_some_and(is_word_start) {
+            self.word();
+        } else {
+            self.advance(1);
+        }
+
+        LineState::Normal
+    }
+
+    /// Returns the number of consecutive characters from the current one that satisfy `predicate`.
+    fn count_while<P: Fn(char) -> bool>(&self, predicate: P) -> usize {
+        self.chars
+            .iter()
+            .skip(self.index)
+            .take_while(|ch| predicate(**ch))
+            .count()
+    }
+
+    /// Highlights a block comment that began at `start` with `depth` open comments.
+    fn block_comment(&mut self, start: usize, mut depth: usize) -> LineState {
+        let (open, close) = self.grammar.block_comment;
+
+        while self.index < self.chars.len() {
+            if self.is_at(close) {
+                self.advance(close.chars().count());
+                depth = depth.saturating_sub(1);
+
+                if depth == 0 {
+                    self.highlight(start, "comment");
+                    return LineState::Normal;
+                }
+            } else if self.grammar.nests_block_comments && self.is_at(open) {
+                self.advance(open.chars().count());
+                depth = depth.saturating_add(1);
+            } else {
+                self.advance(1);
+            }
+        }
+
+        self.highlight(start, "comment");
+        LineState::BlockComment { depth }
+    }
+
+    /// Highlights a string that began at `start`.
+    fn string(&mut self, start: usize) -> LineState {
+        while let Some(ch) = self.peek(0) {
+            self.advance(1);
+
+            match ch {
+                '\\' => self.advance(1),
+                '"' => {
+                    self.highlight(start, "string");
+                    return LineState::Normal;
+                }
+                _ => {}
+            }
+        }
+
+        self.highlight(start, "string");
+        LineState::String
+    }
+
+    /// Highlights a raw string that began at `start` and is closed by `"` followed by `hashes` `#`s.
+    fn raw_string(&mut self, start: usize, hashes: usize) -> LineState {
+        let close = format!("\"{}", "#".repeat(hashes));
+
+        while self.index < self.chars.len() {
+            if self.is_at(&close) {
+                self.advance(close.chars().count());
+                self.highlight(start, "string");
+                return LineState::Normal;
+            }
+
+            self.advance(1);
+        }
+
+        self.highlight(start, "string");
+        LineState::RawString { hashes }
+    }
+
+    /// Highlights a character or lifetime beginning with `'`.
+    fn quote(&mut self) {
+        let start = self.index;
+        let is_char = self.peek(1) == Some('\\') || self.peek(2) == Some('\'');
+
+        self.advance(1);
+
+        if is_char || !self.grammar.has_lifetimes {
+            while let Some(ch) = self.peek(0) {
+                self.advance(1);
+
+                match ch {
+                    '\\' => self.advance(1),
+                    '\'' => break,
+                    _ => {}
+                }
+            }
+
+            self.highlight(start, "string");
+        } else {
+            let length = self.count_while(is_word_char);
+
+            self.advance(length);
+            self.highlight(start, "lifetime");
+        }
+    }
+
+    /// Highlights a number.
+    fn number(&mut self) {
+        let start = self.index;
+
+        while let Some(ch) = self.peek(0) {
+            if is_word_char(ch)
+                || (ch == '.' && self.peek(1).is_some_and(|next| next.is_ascii_digit()))
+            {
+                self.advance(1);
+            } else {
+                break;
+            }
+        }
+
+        self.highlight(start, "number");
+    }
+
+    /// Highlights a word according to its meaning.
+    fn word(&mut self) {
+        let start = self.index;
+        let length = self.count_while(is_word_char);
+        let word: String = self.chars.iter().skip(start).take(length).collect();
+
+        self.advance(length);
+
+        if self.grammar.keywords.contains(&word.as_str()) {
+            self.highlight(start, "keyword");
+        } else if self.grammar.constants.contains(&word.as_str()) {
+            self.highlight(start, "constant");
+        } else if self.grammar.has_macros && self.is_at("!") && !self.is_at("!=") {
+            self.advance(1);
+            self.highlight(start, "macro");
+        } else if self.is_at("(") {
+            self.highlight(start, "function");
+        } else if word.starts_with(char::is_uppercase) {
+            self.highlight(start, "type");
+        }
+    }
+}
+
+/// Returns if `ch` may begin a word.
+fn is_word_start(ch: char) -> bool {
+    ch.is_alphabetic() || ch == '_'
+}
+
+/// Returns if `ch` may be within a word.
+fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+/// Testing of the highlight module.
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn scopes(highlights: &Highlights, index: usize) -> Vec<(usize, usize, String)> {
+        highlights
+            .line(index)
+            .iter()
+            .map(|highlight| {
+                (
+                    highlight.start,
+                    highlight.length,
+                    highlight.scope.to_string(),
+                )
+            })
+            .collect()
+    }
+
+    /// Words, strings, numbers and comments shall be highlighted.
+    #[test]
+    fn line() {
+        let highlights = Highlights::new(
+            Some(LanguageId::Rust),
+            "let x: Vec<u8> = vec![1, 2]; // \"end\"\nfoo(\"a\\\"b\", 'c', 'a);",
+        );
+
+        assert_eq!(
+            scopes(&highlights, 0),
+            vec![
+                (0, 3, "keyword".to_string()),
+                (7, 3, "type".to_string()),
+                (17, 4, "macro".to_string()),
+                (22, 1, "number".to_string()),
+                (25, 1, "number".to_string()),
+                (29, 8, "comment".to_string()),
+            ]
+        );
+        assert_eq!(
+            scopes(&highlights, 1),
+            vec![
+                (0, 3, "function".to_string()),
+                (4, 6, "string".to_string()),
+                (12, 3, "string".to_string()),
+                (17, 2, "lifetime".to_string()),
+            ]
+        );
+    }
+
+    /// Block comments and strings shall continue across lines.
+    #[test]
+    fn multiline() {
+        let highlights =
+            Highlights::new(Some(LanguageId::Rust), "a /* b /* c */\nd */ e\n\"f\ng\" h");
+
+        assert_eq!(scopes(&highlights, 0), vec![(2, 12, "comment".to_string())]);
+        assert_eq!(scopes(&highlights, 1), vec![(0, 4, "comment".to_string())]);
+        assert_eq!(scopes(&highlights, 2), vec![(0, 2, "string".to_string())]);
+        assert_eq!(scopes(&highlights, 3), vec![(0, 2, "string".to_string())]);
+    }
+
+    /// Raw strings shall only be closed by a quote followed by as many `#`s as opened them, on one line and across lines.
+    #[test]
+    fn raw_strings() {
+        let highlights = Highlights::new(
+            Some(LanguageId::Rust),
+            "r#\"a \"quoted\" b\"# x\nbr#\"c \"d\"\n\"e\"# br\"f\" rust",
+        );
+
+        assert_eq!(scopes(&highlights, 0), vec![(0, 17, "string".to_string())]);
+        assert_eq!(scopes(&highlights, 1), vec![(0, 9, "string".to_string())]);
+        assert_eq!(
+            scopes(&highlights, 2),
+            vec![(0, 4, "string".to_string()), (5, 5, "string".to_string())]
+        );
+    }
+
+    /// An edit shall re-highlight all lines whose state changes.
+    #[test]
+    fn update() {
+        let mut highlights = Highlights::new(Some(LanguageId::Rust), "a\nb\nc");
+
+        highlights.update("/* a\nb\nc", 0);
+        assert_eq!(scopes(&highlights, 2), vec![(0, 1, "comment".to_string())]);
+
+        highlights.update("let\n/* a\nb\nc", 0);
+        assert_eq!(scopes(&highlights, 0), vec![(0, 3, "keyword".to_string())]);
+        assert_eq!(scopes(&highlights, 3), vec![(0, 1, "comment".to_string())]);
+    }
+}
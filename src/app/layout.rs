@@ -0,0 +1,425 @@
+//! Implements the arrangement of the lines of a document into the rows of the page.
+use {
+    crate::io::{
+        config::Wrap,
+        ui::{Row, Scope},
+    },
+    unicode_width::UnicodeWidthStr,
+};
+
+/// A section of text that is displayed as a single unit.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Glyph {
+    /// The text that is displayed.
+    pub(crate) text: String,
+    /// The [`Scope`] of `text`.
+    pub(crate) scope: Option<Scope>,
+    /// The index of the character of the line at which the text of `self` begins.
+    ///
+    /// [`None`] indicates that `self` is only displayed; it is not a part of the line.
+    pub(crate) character: Option<usize>,
+}
+
+impl Glyph {
+    /// Creates a [`Glyph`] that is only displayed.
+    fn virtual_text(text: String, scope: Option<Scope>) -> Self {
+        Self {
+            text,
+            scope,
+            character: None,
+        }
+    }
+
+    /// Returns the number of columns that `self` occupies.
+    fn width(&self) -> usize {
+        self.text.width()
+    }
+
+    /// Returns if `self` is made up entirely of whitespace.
+    fn is_whitespace(&self) -> bool {
+        !self.text.is_empty() && self.text.chars().all(char::is_whitespace)
+    }
+}
+
+/// A row of the page and the line of the document that it displays.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct VisualRow {
+    /// The index of the line.
+    pub(crate) line: usize,
+    /// The index of the first character of the line that is displayed by `self`.
+    start: usize,
+    /// If `self` continues a line that is displayed by a previous row.
+    is_continuation: bool,
+    /// The [`Glyph`]s of `self`.
+    glyphs: Vec<Glyph>,
+}
+
+impl VisualRow {
+    /// Returns if `self` continues a line that is displayed by a previous row.
+    pub(crate) const fn is_continuation(&self) -> bool {
+        self.is_continuation
+    }
+
+    /// Returns if `self` displays the character at `character` of `line`.
+    pub(crate) fn contains(&self, line: usize, character: usize) -> bool {
+        self.line == line
+            && self
+                .glyphs
+                .iter()
+                .any(|glyph| glyph.character == Some(character))
+    }
+
+    /// Returns the column at which the character at `character` is displayed.
+    pub(crate) fn column_of(&self, character: usize) -> Option<usize> {
+        let mut column: usize = 0;
+
+        for glyph in &self.glyphs {
+            if glyph.character == Some(character) {
+                return Some(column);
+            }
+
+            column = column.saturating_add(glyph.width());
+        }
+
+        None
+    }
+
+    /// Returns the index of the character that is displayed at or closest before `column`.
+    pub(crate) fn character_at(&self, column: usize) -> usize {
+        let mut glyph_column: usize = 0;
+        let mut character = self.start;
+
+        for glyph in &self.glyphs {
+            if glyph_column > column {
+                break;
+            }
+
+            if let Some(glyph_character) = glyph.character {
+                character = glyph_character;
+            }
+
+            glyph_column = glyph_column.saturating_add(glyph.width());
+        }
+
+        character
+    }
+
+    /// Returns the [`Row`] that displays the `width` columns of `self` that begin at `offset`.
+    ///
+    /// If `self` has content that is not displayed, the first or last column is replaced by an indicator. Wide glyphs that are partially displayed are replaced by spaces.
+    pub(crate) fn window(self, offset: usize, width: usize) -> Row {
+        let total: usize = self.glyphs.iter().map(Glyph::width).sum();
+        let end = offset.saturating_add(width);
+        let has_left = offset > 0 && total > 0 && width > 0;
+        let has_right = total > end && width > 1;
+        let start = offset.saturating_add(usize::from(has_left));
+        let stop = end.saturating_sub(usize::from(has_right));
+        let indicator_scope = Scope::new("ui.overflow".to_string());
+        let mut row = Row::default();
+        let mut column: usize = 0;
+
+        if has_left {
+            row.push_str("<", Some(&indicator_scope));
+        }
+
+        for glyph in &self.glyphs {
+            let glyph_end = column.saturating_add(glyph.width());
+
+            if glyph_end > start && column < stop {
+                if column >= start && glyph_end <= stop {
+                    row.push_str(&glyph.text, glyph.scope.as_ref());
+                } else {
+                    let visible = glyph_end.min(stop).saturating_sub(column.max(start));
+
+                    row.push_str(&" ".repeat(visible), glyph.scope.as_ref());
+                }
+            }
+
+            column = glyph_end;
+        }
+
+        if has_right {
+            row.push_str(&" ".repeat(stop.saturating_sub(column.max(start))), None);
+            row.push_str(">", Some(&indicator_scope));
+        }
+
+        row
+    }
+
+    /// Returns the index of the character that follows the last character displayed by `self`.
+    fn next_start(&self) -> usize {
+        self.glyphs
+            .iter()
+            .rev()
+            .find_map(|glyph| {
+                glyph
+                    .character
+                    .map(|character| character.saturating_add(glyph.text.chars().count()))
+            })
+            .unwrap_or(self.start)
+    }
+}
+
+impl From<VisualRow> for Row {
+    #[inline]
+    fn from(value: VisualRow) -> Self {
+        let mut row = Self::default();
+
+        for glyph in value.glyphs {
+            row.push_str(&glyph.text, glyph.scope.as_ref());
+        }
+
+        row
+    }
+}
+
+/// Arranges the [`Glyph`]s of the line at `line` into [`VisualRow`]s according to `wrap`, where the page is `width` columns wide.
+///
+/// Each row that continues the line is indented to match the line and begins with `indicator`. If there is not enough room for the indentation or indicator, they are omitted.
+pub(crate) fn wrap(
+    line: usize,
+    glyphs: Vec<Glyph>,
+    width: usize,
+    wrap: Wrap,
+    indicator: char,
+) -> Vec<VisualRow> {
+    let limit = match wrap {
+        Wrap::None => 0,
+        Wrap::Char | Wrap::Word => width,
+        Wrap::Column(column) => column.min(width),
+    };
+
+    if limit == 0 || glyphs.iter().map(Glyph::width).sum::<usize>() <= limit {
+        return vec![visual_row(line, 0, glyphs, false)];
+    }
+
+    let by_word = wrap != Wrap::Char;
+    let prefix = continuation(&glyphs, limit, indicator);
+    let prefix_width: usize = prefix.iter().map(Glyph::width).sum();
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut row_width: usize = 0;
+    let mut row_start: usize = 0;
+
+    for glyph in glyphs {
+        let glyph_width = glyph.width();
+        let is_hanging = by_word && glyph.is_whitespace();
+        let content_len = row
+            .len()
+            .saturating_sub(if rows.is_empty() { 0 } else { prefix.len() });
+
+        if row_width.saturating_add(glyph_width) > limit && content_len > 0 && !is_hanging {
+            let mut carried = Vec::new();
+
+            if by_word {
+                let content_start = row.len().saturating_sub(content_len);
+
+                if let Some(index) = row
+                    .iter()
+                    .rposition(Glyph::is_whitespace)
+                    .map(|index| index.saturating_add(1))
+                    .filter(|index| *index > content_start && *index < row.len())
+                {
+                    carried = row.split_off(index);
+                }
+            }
+
+            let finished = visual_row(line, row_start, row, !rows.is_empty());
+
+            row_start = finished.next_start();
+            rows.push(finished);
+            row = prefix.clone();
+            row_width = prefix_width;
+
+            for carried_glyph in carried {
+                row_width = row_width.saturating_add(carried_glyph.width());
+                row.push(carried_glyph);
+            }
+        }
+
+        row_width = row_width.saturating_add(glyph_width);
+        row.push(glyph);
+    }
+
+    let is_continuation = !rows.is_empty();
+
+    rows.push(visual_row(line, row_start, row, is_continuation));
+    rows
+}
+
+/// Creates the [`VisualRow`] of `glyphs` at `line`, where `fallback` is the start if `glyphs` displays no characters of the line.
+fn visual_row(
+    line: usize,
+    fallback: usize,
+    glyphs: Vec<Glyph>,
+    is_continuation: bool,
+) -> VisualRow {
+    VisualRow {
+        line,
+        is_continuation,
+        start: glyphs
+            .iter()
+            .find_map(|glyph| glyph.character)
+            .unwrap_or(fallback),
+        glyphs,
+    }
+}
+
+/// Returns the [`Glyph`]s that begin each row that continues the line of `glyphs`.
+fn continuation(glyphs: &[Glyph], limit: usize, indicator: char) -> Vec<Glyph> {
+    let indent: usize = glyphs
+        .iter()
+        .take_while(|glyph| glyph.character.is_some() && glyph.is_whitespace())
+        .map(Glyph::width)
+        .sum();
+    let marker = Glyph::virtual_text(
+        format!("{} ", indicator),
+        Some(Scope::new("ui.wrap".to_string())),
+    );
+    // Leave at least half of each row for text.
+    let room = limit / 2;
+
+    if indent > 0 && indent.saturating_add(marker.width()) <= room {
+        vec![Glyph::virtual_text(" ".repeat(indent), None), marker]
+    } else if marker.width() <= room {
+        vec![marker]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Testing of the layout module.
+#[cfg(test)]
+mod test {
+    use {super::*, unicode_segmentation::UnicodeSegmentation};
+
+    /// Returns a [`Glyph`] for each character of `text`.
+    fn glyphs(text: &str) -> Vec<Glyph> {
+        text.chars()
+            .enumerate()
+            .map(|(index, ch)| Glyph {
+                text: ch.to_string(),
+                scope: None,
+                character: Some(index),
+            })
+            .collect()
+    }
+
+    /// Returns a [`Glyph`] for each grapheme cluster of `text`, where each tab is expanded to the next of the tab stops that are `tab_width` columns apart.
+    fn graphemes(text: &str, tab_width: usize) -> Vec<Glyph> {
+        let mut character: usize = 0;
+        let mut column: usize = 0;
+
+        text.graphemes(true)
+            .map(|grapheme| {
+                let glyph = Glyph {
+                    text: if grapheme == "\t" {
+                        " ".repeat(tab_width - column % tab_width)
+                    } else {
+                        grapheme.to_string()
+                    },
+                    scope: None,
+                    character: Some(character),
+                };
+
+                character += grapheme.chars().count();
+                column += glyph.width();
+                glyph
+            })
+            .collect()
+    }
+
+    /// Returns the number of columns of each row of `rows`.
+    fn widths(rows: &[VisualRow]) -> Vec<usize> {
+        rows.iter()
+            .map(|row| row.glyphs.iter().map(Glyph::width).sum())
+            .collect()
+    }
+
+    /// Returns the text of each row of `rows`.
+    fn texts(rows: Vec<VisualRow>) -> Vec<String> {
+        rows.into_iter()
+            .map(|row| Row::from(row).to_string())
+            .collect()
+    }
+
+    /// Lines shall wrap at characters, between words or at a column.
+    #[test]
+    fn modes() {
+        let text = "  one two three";
+
+        assert_eq!(
+            texts(wrap(0, glyphs(text), 10, Wrap::None, '>')),
+            vec!["  one two three"]
+        );
+        assert_eq!(
+            texts(wrap(0, glyphs(text), 10, Wrap::Char, '>')),
+            vec!["  one two ", "  > three"]
+        );
+        assert_eq!(
+            texts(wrap(0, glyphs(text), 12, Wrap::Word, '>')),
+            vec!["  one two ", "  > three"]
+        );
+        assert_eq!(
+            texts(wrap(0, glyphs(text), 20, Wrap::Column(7), '>')),
+            vec!["  one ", "> two ", "> three"]
+        );
+    }
+
+    /// Lines shall wrap by display width without dividing wide characters or grapheme clusters.
+    #[test]
+    fn graphemes_wrap() {
+        let rows = wrap(0, graphemes("a世界e\u{301}x", 4), 4, Wrap::Char, '>');
+
+        assert_eq!(widths(&rows), vec![3, 4, 4]);
+        assert_eq!(texts(rows), vec!["a世", "> 界", "> e\u{301}x"]);
+
+        let rows = wrap(0, graphemes("a世界e\u{301}x", 4), 4, Wrap::Column(3), '>');
+
+        assert_eq!(texts(rows), vec!["a世", "界e\u{301}", "x"]);
+    }
+
+    /// A tab shall wrap as a single glyph that is as wide as the distance to the next tab stop.
+    #[test]
+    fn tabs() {
+        let rows = wrap(0, graphemes("ab\tcd\te", 4), 8, Wrap::Char, '>');
+
+        assert_eq!(widths(&rows), vec![8, 3]);
+        assert_eq!(texts(rows), vec!["ab  cd  ", "> e"]);
+
+        let rows = wrap(0, graphemes("abcde\tf", 4), 6, Wrap::Char, '>');
+
+        assert_eq!(texts(rows), vec!["abcde", ">    f"]);
+        assert_eq!(
+            texts(wrap(0, graphemes("abcde\tf", 4), 6, Wrap::Word, '>')),
+            vec!["abcde   ", "> f"]
+        );
+    }
+
+    /// Rows shall be clipped to the page with indicators of the content that is not displayed.
+    #[test]
+    fn window() {
+        let row = || wrap(0, glyphs("abcdefgh"), 4, Wrap::None, '>').remove(0);
+
+        assert_eq!(row().window(0, 10).to_string(), "abcdefgh");
+        assert_eq!(row().window(0, 4).to_string(), "abc>");
+        assert_eq!(row().window(2, 4).to_string(), "<de>");
+        assert_eq!(row().window(6, 4).to_string(), "<h");
+        assert_eq!(row().window(10, 4).to_string(), "<");
+    }
+
+    /// Continuation rows shall map columns to the characters they display.
+    #[test]
+    fn positions() {
+        let rows = wrap(3, glyphs("abcdef"), 4, Wrap::Char, '>');
+
+        assert_eq!(rows.len(), 2);
+        assert!(!rows[0].is_continuation());
+        assert!(rows[1].is_continuation());
+        assert!(rows[1].contains(3, 4));
+        assert_eq!(rows[1].column_of(4), Some(2));
+        assert_eq!(rows[1].character_at(0), 4);
+        assert_eq!(rows[1].character_at(3), 5);
+        assert_eq!(rows[1].character_at(9), 5);
+    }
+}
@@ -0,0 +1,131 @@
+//! Implements the history of messages that have been displayed to the user.
+use {
+    core::time::Duration,
+    lsp_types::{MessageType, ShowMessageParams},
+    std::time::Instant,
+    time::OffsetDateTime,
+};
+
+/// How long an informational message is displayed before it is dismissed.
+pub(crate) const DISMISS_DELAY: Duration = Duration::from_secs(5);
+
+/// A message and when it was displayed.
+#[derive(Clone, Debug)]
+struct Entry {
+    /// The message.
+    message: ShowMessageParams,
+    /// When `message` was displayed.
+    time: OffsetDateTime,
+}
+
+/// Records every message that is displayed and tracks when the displayed message shall be dismissed.
+#[derive(Debug, Default)]
+pub(crate) struct MessageLog {
+    /// The recorded messages, from oldest to newest.
+    entries: Vec<Entry>,
+    /// When the displayed message shall be dismissed.
+    ///
+    /// [`None`] indicates that the message line does not display a message that is dismissed automatically.
+    expiry: Option<Instant>,
+}
+
+impl MessageLog {
+    /// Records `message` as displayed.
+    ///
+    /// Returns the delay after which `message` shall be dismissed, if any. Errors and warnings are displayed until they are acknowledged.
+    pub(crate) fn record(&mut self, message: ShowMessageParams) -> Option<Duration> {
+        let delay = match message.typ {
+            MessageType::Info | MessageType::Log => Some(DISMISS_DELAY),
+            MessageType::Error | MessageType::Warning => None,
+        };
+
+        self.expiry = delay.map(|delay| Instant::now() + delay);
+        self.entries.push(Entry {
+            message,
+            time: OffsetDateTime::try_now_local().unwrap_or_else(|_| OffsetDateTime::now_utc()),
+        });
+        delay
+    }
+
+    /// Records that the message line no longer displays a recorded message.
+    pub(crate) fn acknowledge(&mut self) {
+        self.expiry = None;
+    }
+
+    /// Returns when the displayed message shall be dismissed, if ever.
+    pub(crate) const fn expiry(&self) -> Option<Instant> {
+        self.expiry
+    }
+
+    /// Returns if the displayed message has expired at `now`, in which case it is acknowledged.
+    pub(crate) fn expire(&mut self, now: Instant) -> bool {
+        if self.expiry.is_some_and(|expiry| expiry <= now) {
+            self.acknowledge();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the text that lists every recorded message, one per line.
+    pub(crate) fn text(&self) -> String {
+        self.entries
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{} {:<7} {}\n",
+                    entry.time.format("%H:%M:%S"),
+                    match entry.message.typ {
+                        MessageType::Error => "ERROR",
+                        MessageType::Warning => "WARNING",
+                        MessageType::Info => "INFO",
+                        MessageType::Log => "LOG",
+                    },
+                    entry.message.message.replace('\n', " ")
+                )
+            })
+            .collect()
+    }
+}
+
+/// Testing of the message module.
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Informational messages shall expire while errors shall persist until acknowledged.
+    #[test]
+    fn expiry() {
+        let mut log = MessageLog::default();
+        let later = Instant::now() + DISMISS_DELAY + DISMISS_DELAY;
+
+        assert_eq!(
+            log.record(ShowMessageParams {
+                typ: MessageType::Info,
+                message: "saved".to_string(),
+            }),
+            Some(DISMISS_DELAY)
+        );
+        assert!(!log.expire(Instant::now()));
+        assert!(log.expiry().is_some());
+        assert!(log.expire(later));
+        assert!(log.expiry().is_none());
+        assert!(!log.expire(later));
+
+        assert_eq!(
+            log.record(ShowMessageParams {
+                typ: MessageType::Error,
+                message: "failed\nto save".to_string(),
+            }),
+            None
+        );
+        assert!(!log.expire(later));
+
+        let text = log.text();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with(" INFO    saved"));
+        assert!(lines[1].ends_with(" ERROR   failed to save"));
+    }
+}
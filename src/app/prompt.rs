@@ -0,0 +1,220 @@
+//! Implements the editing of the single line of input given to a command.
+use {
+    super::translate::InputEdit,
+    crate::io::ui::{Row, Scope},
+    core::mem,
+    unicode_segmentation::UnicodeSegmentation,
+};
+
+/// A single line of input that is edited at a cursor.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct Prompt {
+    /// The text of the input.
+    text: String,
+    /// The byte index of the cursor within `text`, which is always at a grapheme boundary.
+    cursor: usize,
+    /// The index of the entry of the history that has been recalled, if any.
+    recalled: Option<usize>,
+    /// The input that was being edited before the history was recalled.
+    draft: String,
+}
+
+impl Prompt {
+    /// Returns the text of `self`.
+    pub(crate) fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the text of `self` that precedes the cursor.
+    pub(crate) fn before_cursor(&self) -> &str {
+        self.text.get(..self.cursor).unwrap_or_default()
+    }
+
+    /// Returns the text of `self` that follows the cursor.
+    fn after_cursor(&self) -> &str {
+        self.text.get(self.cursor..).unwrap_or_default()
+    }
+
+    /// Returns the text of `self`, resetting `self`.
+    pub(crate) fn take(&mut self) -> String {
+        mem::take(self).text
+    }
+
+    /// Inserts `c` at the cursor.
+    pub(crate) fn insert(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor = self.cursor.saturating_add(c.len_utf8());
+    }
+
+    /// Replaces the text that precedes the cursor with `text`, placing the cursor after it.
+    pub(crate) fn replace_before_cursor(&mut self, text: &str) {
+        self.text = format!("{}{}", text, self.after_cursor());
+        self.cursor = text.len();
+    }
+
+    /// Performs `edit`, where `history` is the input previously given to the command from oldest to newest.
+    pub(crate) fn edit(&mut self, edit: InputEdit, history: &[String]) {
+        match edit {
+            InputEdit::DeleteBack => {
+                let start = self.previous_boundary();
+
+                let _ = self.text.drain(start..self.cursor);
+                self.cursor = start;
+            }
+            InputEdit::DeleteForward => {
+                let end = self.next_boundary();
+
+                let _ = self.text.drain(self.cursor..end);
+            }
+            InputEdit::DeleteWord => {
+                let start = self
+                    .before_cursor()
+                    .trim_end()
+                    .trim_end_matches(|c: char| !c.is_whitespace())
+                    .len();
+
+                let _ = self.text.drain(start..self.cursor);
+                self.cursor = start;
+            }
+            InputEdit::Left => {
+                self.cursor = self.previous_boundary();
+            }
+            InputEdit::Right => {
+                self.cursor = self.next_boundary();
+            }
+            InputEdit::Start => {
+                self.cursor = 0;
+            }
+            InputEdit::End => {
+                self.cursor = self.text.len();
+            }
+            InputEdit::Previous => {
+                if let Some(index) = self
+                    .recalled
+                    .unwrap_or(history.len())
+                    .checked_sub(1)
+                    .filter(|index| *index < history.len())
+                {
+                    if self.recalled.is_none() {
+                        self.draft = self.text.clone();
+                    }
+
+                    self.recalled = Some(index);
+                    self.recall(history.get(index).cloned().unwrap_or_default());
+                }
+            }
+            InputEdit::Next => {
+                if let Some(recalled) = self.recalled {
+                    let index = recalled.saturating_add(1);
+
+                    if let Some(entry) = history.get(index) {
+                        self.recalled = Some(index);
+                        self.recall(entry.clone());
+                    } else {
+                        self.recalled = None;
+                        let draft = mem::take(&mut self.draft);
+                        self.recall(draft);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the [`Row`] that displays `prefix` followed by `self`, where the cursor is styled.
+    pub(crate) fn row(&self, prefix: &str) -> Row {
+        let mut row = Row::from(format!("{}{}", prefix, self.before_cursor()));
+        let mut after = self.after_cursor().graphemes(true);
+
+        row.push_str(
+            after.next().unwrap_or(" "),
+            Some(&Scope::new("ui.cursor".to_string())),
+        );
+        row.push_str(after.as_str(), None);
+        row
+    }
+
+    /// Replaces the text of `self` with `text`, placing the cursor at its end.
+    fn recall(&mut self, text: String) {
+        self.cursor = text.len();
+        self.text = text;
+    }
+
+    /// Returns the byte index of the grapheme before the cursor.
+    fn previous_boundary(&self) -> usize {
+        self.before_cursor()
+            .grapheme_indices(true)
+            .next_back()
+            .map_or(0, |(index, _)| index)
+    }
+
+    /// Returns the byte index that follows the grapheme after the cursor.
+    fn next_boundary(&self) -> usize {
+        self.cursor.saturating_add(
+            self.after_cursor()
+                .graphemes(true)
+                .next()
+                .map_or(0, str::len),
+        )
+    }
+}
+
+/// Testing of the prompt module.
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Returns a [`Prompt`] with `text` typed into it.
+    fn typed(text: &str) -> Prompt {
+        let mut prompt = Prompt::default();
+
+        for c in text.chars() {
+            prompt.insert(c);
+        }
+
+        prompt
+    }
+
+    /// Edits shall move the cursor and delete text by grapheme and by word.
+    #[test]
+    fn edit() {
+        let mut prompt = typed("open e\u{301}a.txt");
+
+        prompt.edit(InputEdit::DeleteWord, &[]);
+        assert_eq!(prompt.before_cursor(), "open ");
+
+        prompt = typed("ae\u{301}");
+        prompt.edit(InputEdit::Left, &[]);
+        prompt.edit(InputEdit::DeleteBack, &[]);
+        prompt.insert('b');
+        assert_eq!(prompt.take(), "be\u{301}");
+
+        prompt = typed("abc");
+        prompt.edit(InputEdit::Start, &[]);
+        prompt.edit(InputEdit::DeleteForward, &[]);
+        prompt.edit(InputEdit::End, &[]);
+        prompt.edit(InputEdit::Left, &[]);
+        assert_eq!(prompt.row(":").to_string(), ":bc");
+        prompt.replace_before_cursor("x");
+        assert_eq!(prompt.before_cursor(), "x");
+        assert_eq!(prompt.take(), "xc");
+    }
+
+    /// The history shall be recalled from newest to oldest and the draft shall be restored after the newest entry.
+    #[test]
+    fn history() {
+        let history = vec!["a".to_string(), "b".to_string()];
+        let mut prompt = typed("dr");
+
+        prompt.edit(InputEdit::Previous, &history);
+        assert_eq!(prompt.before_cursor(), "b");
+        prompt.edit(InputEdit::Previous, &history);
+        prompt.edit(InputEdit::Previous, &history);
+        assert_eq!(prompt.before_cursor(), "a");
+        prompt.edit(InputEdit::Next, &history);
+        assert_eq!(prompt.before_cursor(), "b");
+        prompt.edit(InputEdit::Next, &history);
+        assert_eq!(prompt.before_cursor(), "dr");
+        prompt.edit(InputEdit::Next, &history);
+        assert_eq!(prompt.take(), "dr");
+    }
+}
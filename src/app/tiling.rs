@@ -0,0 +1,580 @@
+//! Implements the division of the page into panes.
+use {
+    super::translate::{Direction, Split},
+    crate::io::ui::{Dimensions, Row, Scope},
+    core::{cmp::Reverse, convert::TryFrom, iter, mem},
+};
+
+/// The smallest percentage of a split that either side may occupy.
+const MIN_RATIO: i16 = 10;
+/// The largest percentage of a split that either side may occupy.
+const MAX_RATIO: i16 = 90;
+
+/// A rectangular section of the page.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) struct Area {
+    /// The index of the first row.
+    pub(crate) row: u16,
+    /// The index of the first column.
+    pub(crate) column: u16,
+    /// The number of rows.
+    pub(crate) height: u16,
+    /// The number of columns.
+    pub(crate) width: u16,
+}
+
+impl Area {
+    /// Returns the [`Dimensions`] of `self`.
+    pub(crate) fn dimensions(self) -> Dimensions {
+        Dimensions {
+            height: self.height.into(),
+            width: self.width.into(),
+        }
+    }
+
+    /// Divides `self` according to `split`, where the first part receives `ratio` percent of the space that is not occupied by the border between the parts.
+    fn divide(self, split: Split, ratio: u16) -> (Self, Self) {
+        let total = match split {
+            Split::Horizontal => self.height,
+            Split::Vertical => self.width,
+        };
+        let available = total.saturating_sub(1);
+        let first = u16::try_from(u32::from(available).saturating_mul(u32::from(ratio)) / 100)
+            .unwrap_or(available);
+        let second = available.saturating_sub(first);
+
+        match split {
+            Split::Horizontal => (
+                Self {
+                    height: first,
+                    ..self
+                },
+                Self {
+                    row: self.row.saturating_add(first).saturating_add(1),
+                    height: second,
+                    ..self
+                },
+            ),
+            Split::Vertical => (
+                Self {
+                    width: first,
+                    ..self
+                },
+                Self {
+                    column: self.column.saturating_add(first).saturating_add(1),
+                    width: second,
+                    ..self
+                },
+            ),
+        }
+    }
+
+    /// Returns the number of rows or columns that `self` shares with `other` if `other` is across a border from `self` in `direction`.
+    fn adjacency(self, other: Self, direction: Direction) -> u16 {
+        let is_adjacent = match direction {
+            Direction::Left => {
+                other.column.saturating_add(other.width).saturating_add(1) == self.column
+            }
+            Direction::Right => {
+                self.column.saturating_add(self.width).saturating_add(1) == other.column
+            }
+            Direction::Up => other.row.saturating_add(other.height).saturating_add(1) == self.row,
+            Direction::Down => self.row.saturating_add(self.height).saturating_add(1) == other.row,
+        };
+        let ((start, length), (other_start, other_length)) = match direction {
+            Direction::Left | Direction::Right => {
+                ((self.row, self.height), (other.row, other.height))
+            }
+            Direction::Up | Direction::Down => {
+                ((self.column, self.width), (other.column, other.width))
+            }
+        };
+
+        if is_adjacent {
+            start
+                .saturating_add(length)
+                .min(other_start.saturating_add(other_length))
+                .saturating_sub(start.max(other_start))
+        } else {
+            0
+        }
+    }
+}
+
+impl From<Dimensions> for Area {
+    #[inline]
+    fn from(value: Dimensions) -> Self {
+        Self {
+            row: 0,
+            column: 0,
+            height: *value.height,
+            width: *value.width,
+        }
+    }
+}
+
+/// A node of a [`Tiling`].
+#[derive(Debug)]
+enum Node<T> {
+    /// A single pane.
+    Leaf(T),
+    /// Two nodes that divide a space.
+    Branch {
+        /// How the space is divided.
+        split: Split,
+        /// The percentage of the space that is occupied by `first`.
+        ratio: u16,
+        /// The node above or to the left of `second`.
+        first: Box<Self>,
+        /// The node below or to the right of `first`.
+        second: Box<Self>,
+    },
+}
+
+impl<T> Node<T> {
+    /// Returns if `self` is a single pane.
+    const fn is_leaf(&self) -> bool {
+        matches!(self, Self::Leaf(..))
+    }
+
+    /// Returns the number of panes in `self`.
+    fn count(&self) -> usize {
+        match self {
+            Self::Leaf(..) => 1,
+            Self::Branch { first, second, .. } => first.count().saturating_add(second.count()),
+        }
+    }
+
+    /// Appends the panes of `self` to `leaves`, in order from top left to bottom right.
+    fn leaves<'a>(&'a self, leaves: &mut Vec<&'a T>) {
+        match self {
+            Self::Leaf(item) => leaves.push(item),
+            Self::Branch { first, second, .. } => {
+                first.leaves(leaves);
+                second.leaves(leaves);
+            }
+        }
+    }
+
+    /// Appends mutable references to the panes of `self` to `leaves`, in order from top left to bottom right.
+    fn leaves_mut<'a>(&'a mut self, leaves: &mut Vec<&'a mut T>) {
+        match self {
+            Self::Leaf(item) => leaves.push(item),
+            Self::Branch { first, second, .. } => {
+                first.leaves_mut(leaves);
+                second.leaves_mut(leaves);
+            }
+        }
+    }
+
+    /// Returns the node of the pane at `index`.
+    fn nth_mut(&mut self, index: usize) -> Option<&mut Self> {
+        match self {
+            Self::Leaf(..) => Some(self).filter(|_| index == 0),
+            Self::Branch { first, second, .. } => {
+                let count = first.count();
+
+                if index < count {
+                    first.nth_mut(index)
+                } else {
+                    second.nth_mut(index.saturating_sub(count))
+                }
+            }
+        }
+    }
+
+    /// Appends the [`Area`] of each pane of `self` to `areas`, where `self` occupies `area`.
+    fn areas(&self, area: Area, areas: &mut Vec<Area>) {
+        match self {
+            Self::Leaf(..) => areas.push(area),
+            Self::Branch {
+                split,
+                ratio,
+                first,
+                second,
+            } => {
+                let (first_area, second_area) = area.divide(*split, *ratio);
+
+                first.areas(first_area, areas);
+                second.areas(second_area, areas);
+            }
+        }
+    }
+
+    /// Removes the pane at `index` and returns it; its sibling takes the space of their parent.
+    fn remove(&mut self, index: usize) -> Option<T>
+    where
+        T: Default,
+    {
+        let (is_first, child_index, is_leaf) = match self {
+            Self::Leaf(..) => return None,
+            Self::Branch { first, second, .. } => {
+                let count = first.count();
+
+                if index < count {
+                    (true, index, first.is_leaf())
+                } else {
+                    (false, index.saturating_sub(count), second.is_leaf())
+                }
+            }
+        };
+
+        if is_leaf {
+            if let Self::Branch { first, second, .. } = mem::replace(self, Self::Leaf(T::default()))
+            {
+                let (removed, kept) = if is_first {
+                    (first, second)
+                } else {
+                    (second, first)
+                };
+
+                *self = *kept;
+
+                if let Self::Leaf(item) = *removed {
+                    return Some(item);
+                }
+            }
+
+            None
+        } else if let Self::Branch { first, second, .. } = self {
+            if is_first {
+                first.remove(child_index)
+            } else {
+                second.remove(child_index)
+            }
+        } else {
+            None
+        }
+    }
+
+    /// Changes the ratio of the innermost branch divided by `split` that contains the pane at `index` so that the pane grows by `delta` percent.
+    ///
+    /// Returns if a ratio was changed.
+    fn resize(&mut self, index: usize, split: Split, delta: i8) -> bool {
+        if let Self::Branch {
+            split: branch_split,
+            ratio,
+            first,
+            second,
+        } = self
+        {
+            let count = first.count();
+            let is_first = index < count;
+            let is_resized = if is_first {
+                first.resize(index, split, delta)
+            } else {
+                second.resize(index.saturating_sub(count), split, delta)
+            };
+
+            if is_resized {
+                true
+            } else if *branch_split == split {
+                let delta = if is_first {
+                    i16::from(delta)
+                } else {
+                    i16::from(delta).saturating_neg()
+                };
+                let new_ratio = i16::try_from(*ratio)
+                    .unwrap_or(MAX_RATIO)
+                    .saturating_add(delta)
+                    .clamp(MIN_RATIO, MAX_RATIO);
+
+                *ratio = u16::try_from(new_ratio).unwrap_or(*ratio);
+                true
+            } else {
+                false
+            }
+        } else {
+            false
+        }
+    }
+
+    /// Returns the rows that display `self` within `area`, where `render` returns the rows of the pane at an index.
+    ///
+    /// `index` is the index of the first pane of `self`. Panes are separated by borders.
+    fn compose(
+        &self,
+        area: Area,
+        index: &mut usize,
+        render: &mut dyn FnMut(usize, &T) -> Vec<Row>,
+    ) -> Vec<Row> {
+        match self {
+            Self::Leaf(item) => {
+                let rows = render(*index, item);
+
+                *index = index.saturating_add(1);
+                rows.into_iter()
+                    .chain(iter::repeat_with(Row::default))
+                    .take(usize::from(area.height))
+                    .map(|row| row.fit(usize::from(area.width)))
+                    .collect()
+            }
+            Self::Branch {
+                split,
+                ratio,
+                first,
+                second,
+            } => {
+                let (first_area, second_area) = area.divide(*split, *ratio);
+                let first_rows = first.compose(first_area, index, render);
+                let second_rows = second.compose(second_area, index, render);
+                let scope = Scope::new("ui.border".to_string());
+
+                match split {
+                    Split::Horizontal => {
+                        let mut border = Row::default();
+
+                        border.push_str(&"─".repeat(usize::from(area.width)), Some(&scope));
+                        first_rows
+                            .into_iter()
+                            .chain(iter::once(border).filter(|_| area.height > 0))
+                            .chain(second_rows)
+                            .collect()
+                    }
+                    Split::Vertical => first_rows
+                        .into_iter()
+                        .zip(second_rows)
+                        .map(|(mut row, second_row)| {
+                            if area.width > 0 {
+                                row.push_str("│", Some(&scope));
+                            }
+
+                            row.append(second_row);
+                            row
+                        })
+                        .collect(),
+                }
+            }
+        }
+    }
+}
+
+/// Divides the page into panes and tracks which pane is focused.
+///
+/// Panes are identified by their index in order from top left to bottom right.
+#[derive(Debug)]
+pub(crate) struct Tiling<T> {
+    /// The root of the tree of panes.
+    root: Node<T>,
+    /// The index of the focused pane.
+    focus: usize,
+}
+
+impl<T> Tiling<T> {
+    /// Returns the index of the focused pane.
+    pub(crate) const fn focus(&self) -> usize {
+        self.focus
+    }
+
+    /// Returns the focused pane.
+    pub(crate) fn focused(&self) -> Option<&T> {
+        self.panes().into_iter().nth(self.focus)
+    }
+
+    /// Returns a mutable reference to the focused pane.
+    pub(crate) fn focused_mut(&mut self) -> Option<&mut T> {
+        match self.root.nth_mut(self.focus) {
+            Some(Node::Leaf(item)) => Some(item),
+            Some(Node::Branch { .. }) | None => None,
+        }
+    }
+
+    /// Returns the panes, in order from top left to bottom right.
+    pub(crate) fn panes(&self) -> Vec<&T> {
+        let mut leaves = Vec::new();
+
+        self.root.leaves(&mut leaves);
+        leaves
+    }
+
+    /// Returns mutable references to the panes, in order from top left to bottom right.
+    pub(crate) fn panes_mut(&mut self) -> Vec<&mut T> {
+        let mut leaves = Vec::new();
+
+        self.root.leaves_mut(&mut leaves);
+        leaves
+    }
+
+    /// Returns the [`Area`] of each pane when `self` occupies `area`.
+    pub(crate) fn areas(&self, area: Area) -> Vec<Area> {
+        let mut areas = Vec::new();
+
+        self.root.areas(area, &mut areas);
+        areas
+    }
+
+    /// Divides the focused pane according to `split`, where `item` is placed below or to the right of the focused pane and becomes focused.
+    pub(crate) fn split(&mut self, split: Split, item: T)
+    where
+        T: Default,
+    {
+        if let Some(node) = self.root.nth_mut(self.focus) {
+            let old = mem::replace(node, Node::Leaf(T::default()));
+
+            *node = Node::Branch {
+                split,
+                ratio: 50,
+                first: Box::new(old),
+                second: Box::new(Node::Leaf(item)),
+            };
+            self.focus = self.focus.saturating_add(1);
+        }
+    }
+
+    /// Removes the focused pane and returns it.
+    ///
+    /// The last pane is not able to be removed.
+    pub(crate) fn close(&mut self) -> Option<T>
+    where
+        T: Default,
+    {
+        let removed = self.root.remove(self.focus);
+
+        self.focus = self.focus.min(self.root.count().saturating_sub(1));
+        removed
+    }
+
+    /// Grows the focused pane by `delta` percent of the innermost split of kind `split` that contains it; a negative `delta` shrinks it.
+    ///
+    /// Returns if the size of a pane changed.
+    pub(crate) fn resize(&mut self, split: Split, delta: i8) -> bool {
+        self.root.resize(self.focus, split, delta)
+    }
+
+    /// Focuses the pane across the border in `direction` from the focused pane, where `self` occupies `area`.
+    ///
+    /// If multiple panes are adjacent, the one that shares the most rows or columns is focused. Returns if the focus changed.
+    pub(crate) fn move_focus(&mut self, area: Area, direction: Direction) -> bool {
+        let areas = self.areas(area);
+        let target = areas.get(self.focus).and_then(|focused| {
+            areas
+                .iter()
+                .enumerate()
+                .map(|(index, other)| (index, focused.adjacency(*other, direction)))
+                .filter(|(_, shared)| *shared > 0)
+                .max_by_key(|(index, shared)| (*shared, Reverse(*index)))
+                .map(|(index, _)| index)
+        });
+
+        if let Some(index) = target {
+            self.focus = index;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the rows that display every pane when `self` occupies `area`, where `render` returns the rows of the pane at an index.
+    ///
+    /// Each pane is clipped or padded to fit its [`Area`].
+    pub(crate) fn compose(
+        &self,
+        area: Area,
+        mut render: impl FnMut(usize, &T) -> Vec<Row>,
+    ) -> Vec<Row> {
+        self.root.compose(area, &mut 0, &mut render)
+    }
+}
+
+impl<T: Default> Default for Tiling<T> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            root: Node::Leaf(T::default()),
+            focus: 0,
+        }
+    }
+}
+
+/// Testing of the tiling module.
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// The [`Area`] of a page with 11 rows and 21 columns.
+    const PAGE: Area = Area {
+        row: 0,
+        column: 0,
+        height: 11,
+        width: 21,
+    };
+
+    /// Splits shall divide the space of the focused pane and focus shall move to adjacent panes.
+    #[test]
+    fn split() {
+        let mut tiling = Tiling::<u8>::default();
+
+        tiling.split(Split::Vertical, 1);
+        tiling.split(Split::Horizontal, 2);
+        assert_eq!(tiling.panes(), vec![&0, &1, &2]);
+        assert_eq!(tiling.focus(), 2);
+        assert_eq!(
+            tiling.areas(PAGE),
+            vec![
+                Area {
+                    row: 0,
+                    column: 0,
+                    height: 11,
+                    width: 10
+                },
+                Area {
+                    row: 0,
+                    column: 11,
+                    height: 5,
+                    width: 10
+                },
+                Area {
+                    row: 6,
+                    column: 11,
+                    height: 5,
+                    width: 10
+                },
+            ]
+        );
+
+        assert!(tiling.move_focus(PAGE, Direction::Up));
+        assert_eq!(tiling.focused(), Some(&1));
+        assert!(!tiling.move_focus(PAGE, Direction::Right));
+        assert!(tiling.move_focus(PAGE, Direction::Left));
+        assert_eq!(tiling.focused(), Some(&0));
+        assert!(tiling.move_focus(PAGE, Direction::Right));
+        assert_eq!(tiling.focused(), Some(&1));
+
+        assert!(tiling.resize(Split::Horizontal, 20));
+        assert_eq!(tiling.areas(PAGE)[1].height, 7);
+        assert!(tiling.resize(Split::Vertical, -20));
+        assert_eq!(tiling.areas(PAGE)[1].width, 6);
+
+        assert_eq!(tiling.close(), Some(1));
+        assert_eq!(tiling.panes(), vec![&0, &2]);
+        assert_eq!(tiling.areas(PAGE)[1].height, 11);
+        assert_eq!(tiling.close(), Some(2));
+        assert_eq!(tiling.close(), None);
+        assert_eq!(tiling.focused(), Some(&0));
+    }
+
+    /// Panes shall be separated by borders and fill their areas.
+    #[test]
+    fn compose() {
+        let mut tiling = Tiling::<u8>::default();
+
+        tiling.split(Split::Vertical, 1);
+        tiling.split(Split::Horizontal, 2);
+
+        let rows: Vec<String> = tiling
+            .compose(
+                Area {
+                    row: 0,
+                    column: 0,
+                    height: 3,
+                    width: 5,
+                },
+                |index, item| vec![Row::from(format!("{}{}", index, item).repeat(3))],
+            )
+            .into_iter()
+            .map(|row| row.to_string())
+            .collect();
+
+        assert_eq!(rows, vec!["00│11", "  │──", "  │22"]);
+    }
+}
@@ -1,19 +1,32 @@
 //! Implements the functionality of interpreting an [`Input`] into [`Operation`]s.
 use {
     crate::io::{
-        config::Setting,
-        fs::File,
-        lsp::{ClientMessage, ServerMessage, ToolMessage},
+        config::{
+            keymap::{Action, Binding, Chord, Keymap, Sequence},
+            Setting,
+        },
+        fs::{index::Index, File},
+        history::History,
+        lsp::{ClientMessage, InlayHint, ServerMessage, TokenSpan, ToolMessage},
         ui::{Dimensions, UserAction},
-        Input,
+        Input, LanguageId, SaveOutcome,
     },
-    core::fmt::{self, Debug},
-    crossterm::event::KeyCode,
+    core::{
+        convert::TryFrom,
+        fmt::{self, Debug},
+        time::Duration,
+    },
+    crossterm::event::{KeyCode, KeyModifiers},
     enum_map::{enum_map, Enum, EnumMap},
     lsp_types::{MessageType, ShowMessageParams, ShowMessageRequestParams},
     parse_display::Display as ParseDisplay,
+    std::{collections::HashMap, time::Instant},
+    url::Url,
 };
 
+/// The percentage by which a resize changes the size of a pane.
+const RESIZE_STEP: i8 = 5;
+
 /// Signifies actions that can be performed by the application.
 #[derive(Debug, PartialEq)]
 pub(crate) enum Operation {
@@ -24,26 +37,68 @@ pub(crate) enum Operation {
     },
     /// Sends message to language server.
     SendLsp(ToolMessage<ClientMessage>),
+    /// Records that the language server of a language has been initialized.
+    Initialized(LanguageId),
     /// Resets the application.
     Reset,
     /// Confirms that the action is desired.
     Confirm(ConfirmAction),
     /// Quits the application.
     Quit,
+    /// Saves every document that has unsaved changes and quits the application once they are saved.
+    SaveAndQuit,
+    /// Handles the outcome of saving a document.
+    Saved(SaveOutcome),
     /// Updates a setting.
     UpdateSetting(Setting),
     /// Alerts the user with a message.
     Alert(ShowMessageParams),
+    /// Handles the expiration of a scheduled delay.
+    Timeout,
+    /// Displays the keys that have been pressed towards a sequence, scheduling a timeout after the given delay if any.
+    UpdatePending(Option<Duration>),
+    /// Displays the history of messages.
+    ShowMessages,
     /// Open input box for a command.
     StartCommand(Command),
     /// Input to input box.
     Collect(char),
+    /// Completes the last word of the input box.
+    Complete,
+    /// Edits the input box.
+    EditInput(InputEdit),
+    /// Replaces the history of the input given to each command.
+    LoadHistory(History),
+    /// Replaces the index of the files of the project that are searched by the file finder.
+    LoadIndex(Index),
+    /// Selects another match of the file finder.
+    SelectMatch(Switch),
     /// Executes the current command.
     Execute,
     /// An operation to edit the text or selection of the document.
     Document(DocOp),
+    /// Displays another open document.
+    SwitchBuffer(Switch),
+    /// An operation to arrange the panes of the page.
+    Pane(PaneOp),
     /// Creates a document from the file.
     CreateDoc(File),
+    /// Handles a file that has been changed by another process.
+    ExternalChange(File),
+    /// Updates the semantic tokens of the document at `url`.
+    Tokenize {
+        /// The URL of the document.
+        url: Url,
+        /// The semantic tokens.
+        tokens: Vec<TokenSpan>,
+    },
+    /// Updates the inlay hints of the document at `url`.
+    Hint {
+        /// The URL of the document.
+        url: Url,
+        /// The inlay hints.
+        hints: Vec<InlayHint>,
+    },
 }
 
 /// Signifies actions that require a confirmation prior to their execution.
@@ -61,7 +116,6 @@ impl fmt::Display for ConfirmAction {
 
 impl From<ConfirmAction> for ShowMessageRequestParams {
     #[inline]
-    #[must_use]
     fn from(value: ConfirmAction) -> Self {
         Self {
             typ: MessageType::Info,
@@ -77,6 +131,55 @@ pub(crate) enum Command {
     /// Opens a given file.
     #[display("Open <file>")]
     Open,
+    /// Displays the open document with a given number or name.
+    #[display("Buffer <name>")]
+    Buffer,
+    /// Performs a command of the command line.
+    #[display(":")]
+    Line,
+}
+
+impl Command {
+    /// Returns the name under which the history of the input of `self` is recorded.
+    pub(crate) const fn name(&self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Buffer => "buffer",
+            Self::Line => "line",
+        }
+    }
+}
+
+/// An edit of the input box.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum InputEdit {
+    /// Deletes the grapheme before the cursor.
+    DeleteBack,
+    /// Deletes the grapheme after the cursor.
+    DeleteForward,
+    /// Deletes the word before the cursor, along with any whitespace that follows it.
+    DeleteWord,
+    /// Moves the cursor to the previous grapheme.
+    Left,
+    /// Moves the cursor to the next grapheme.
+    Right,
+    /// Moves the cursor to the start of the input.
+    Start,
+    /// Moves the cursor to the end of the input.
+    End,
+    /// Recalls the previous input given to the command.
+    Previous,
+    /// Recalls the next input given to the command, or the input that was being edited before the history was recalled.
+    Next,
+}
+
+/// Signifies which item of a list, such as the open documents or the matches of the file finder, to move to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Switch {
+    /// The item after the current one, wrapping to the first.
+    Next,
+    /// The item before the current one, wrapping to the last.
+    Previous,
 }
 
 /// An operation performed on a document.
@@ -84,6 +187,66 @@ pub(crate) enum Command {
 pub(crate) enum DocOp {
     /// Saves the document.
     Save,
+    /// Saves the document even if its file has been changed by another process.
+    Overwrite,
+    /// Replaces the text of the document with the text of its file that was changed by another process.
+    Reload,
+    /// Moves the cursor by a [`Movement`] the given number of times.
+    Move(Movement, usize),
+}
+
+/// An operation that arranges the panes of the page.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum PaneOp {
+    /// Splits the focused pane into two panes that display the same document.
+    Split(Split),
+    /// Closes the focused pane.
+    Close,
+    /// Focuses the pane adjacent to the focused pane.
+    Focus(Direction),
+    /// Changes the size of the focused pane within the nearest split of the given kind by a percentage; a negative percentage shrinks it.
+    Resize(Split, i8),
+}
+
+/// How a space is divided between two panes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Split {
+    /// The panes are stacked, separated by a horizontal border.
+    Horizontal,
+    /// The panes are side by side, separated by a vertical border.
+    Vertical,
+}
+
+/// A direction on the page.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Direction {
+    /// Towards the left side.
+    Left,
+    /// Towards the bottom.
+    Down,
+    /// Towards the top.
+    Up,
+    /// Towards the right side.
+    Right,
+}
+
+/// A movement of the cursor.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Movement {
+    /// To the previous grapheme.
+    Left,
+    /// To the next grapheme.
+    Right,
+    /// To the previous row of the page, which may be within the same line if it is wrapped.
+    RowUp,
+    /// To the next row of the page, which may be within the same line if it is wrapped.
+    RowDown,
+    /// To the previous line.
+    LineUp,
+    /// To the next line.
+    LineDown,
+    /// To the line at the given index, or the last line if the index is past the end of the document.
+    Line(usize),
 }
 
 impl fmt::Display for DocOp {
@@ -93,6 +256,9 @@ impl fmt::Display for DocOp {
             "{}",
             match self {
                 Self::Save => "save",
+                Self::Overwrite => "overwrite",
+                Self::Reload => "reload",
+                Self::Move(..) => "move",
             }
         )
     }
@@ -107,17 +273,192 @@ pub(crate) struct Interpreter {
     mode: Mode,
     /// Map of [`ModeInterpreter`]s.
     map: EnumMap<Mode, &'static dyn ModeInterpreter>,
+    /// The key bindings of every [`Mode`], including the defaults.
+    keymap: Keymap,
+    /// How long to wait for the next key of a sequence.
+    key_timeout: Duration,
+    /// The chords that have been pressed towards a sequence.
+    pending: Vec<Chord>,
+    /// The count typed before a sequence.
+    count: Option<usize>,
+    /// When the chords of `pending` shall be interpreted without waiting for more.
+    deadline: Option<Instant>,
 }
 
 impl Interpreter {
-    /// Returns the [`Operation`] that maps to `input` given the current [`Mode`].
-    pub(crate) fn translate(&mut self, input: Input) -> Option<Operation> {
+    /// Returns the current [`Mode`].
+    pub(crate) const fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Returns the count and chords that have been typed towards a sequence, such as `5g`.
+    pub(crate) fn pending(&self) -> String {
+        format!(
+            "{}{}",
+            self.count
+                .map_or_else(String::new, |count| count.to_string()),
+            self.pending
+                .iter()
+                .map(Chord::to_string)
+                .collect::<Vec<String>>()
+                .join(" ")
+        )
+    }
+
+    /// Returns when the pending chords shall be interpreted without waiting for more, if ever.
+    pub(crate) const fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Returns the [`Operation`]s of the pending chords if the deadline to complete their sequence has passed at `now`.
+    ///
+    /// The pending chords perform the actions to which they are bound, if any; otherwise they are discarded.
+    pub(crate) fn expire(&mut self, now: Instant) -> Vec<Operation> {
+        if self.deadline.is_none_or(|deadline| deadline > now) {
+            return Vec::new();
+        }
+
+        let output = self.flush();
+
+        self.finish(output)
+    }
+
+    /// Returns the [`Output`] of the pending chords performing the binding to which they are bound, if any, after which they are discarded.
+    fn flush(&mut self) -> Output {
+        let mut output = Output::new();
+
+        match self.lookup(&self.pending) {
+            Match::Binding(binding) | Match::Prefix(Some(binding)) => {
+                output.perform_binding(&binding, self.count);
+            }
+            Match::Prefix(None) | Match::None => {
+                output.add_op(Operation::UpdatePending(None));
+            }
+        }
+
+        self.clear_pending();
+        output
+    }
+
+    /// Returns the key bindings of the current [`Mode`].
+    fn bindings(&self) -> &HashMap<Sequence, Binding> {
+        match self.mode {
+            Mode::View => &self.keymap.view,
+            Mode::Confirm => &self.keymap.confirm,
+            Mode::Collect => &self.keymap.collect,
+        }
+    }
+
+    /// Returns how `chords` match the bindings of the current [`Mode`].
+    fn lookup(&self, chords: &[Chord]) -> Match {
+        let bindings = self.bindings();
+        let binding = bindings.get(&Sequence::new(chords.to_vec())).cloned();
+
+        if bindings.keys().any(|sequence| sequence.continues(chords)) {
+            Match::Prefix(binding)
+        } else {
+            binding.map_or(Match::None, Match::Binding)
+        }
+    }
+
+    /// Discards the pending chords and count.
+    fn clear_pending(&mut self) {
+        self.pending.clear();
+        self.count = None;
+        self.deadline = None;
+    }
+
+    /// Returns the [`Output`] of pressing `code` with `modifiers`.
+    ///
+    /// A chord that may be followed by more chords of a sequence waits for them until the key timeout. If the chord does not continue the sequence but the chords before it are bound, they perform their binding as they would at the key timeout and the chord is then pressed on its own. In [`Mode::View`], a digit that is not bound begins or continues a count.
+    fn press(&mut self, code: KeyCode, modifiers: KeyModifiers) -> Output {
+        let chord = Chord::new(code, modifiers);
+        let mut output = Output::new();
+        let is_bound_prefix = !self.pending.is_empty()
+            && matches!(self.lookup(&self.pending), Match::Prefix(Some(_)));
+
+        self.pending.push(chord);
+
+        match self.lookup(&self.pending) {
+            Match::None if is_bound_prefix => {
+                let _ = self.pending.pop();
+                output = self.flush();
+
+                if let Some(mode) = output.new_mode {
+                    self.mode = mode;
+                }
+
+                let next = self.press(code, modifiers);
+
+                output.operations.extend(next.operations);
+                output.new_mode = next.new_mode.or(output.new_mode);
+            }
+            Match::Binding(binding) => {
+                output.perform_binding(&binding, self.count);
+                self.clear_pending();
+            }
+            Match::Prefix(..) => {
+                self.deadline = Some(Instant::now() + self.key_timeout);
+                output.add_op(Operation::UpdatePending(Some(self.key_timeout)));
+            }
+            Match::None => {
+                let is_sequence = self.pending.len() > 1;
+                let count = self.count;
+                let digit = chord
+                    .character()
+                    .and_then(|c| c.to_digit(10))
+                    .and_then(|digit| usize::try_from(digit).ok())
+                    .filter(|digit| *digit != 0 || count.is_some());
+
+                self.clear_pending();
+
+                match digit {
+                    Some(digit) if !is_sequence && self.mode == Mode::View => {
+                        self.count =
+                            Some(count.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+                        output.add_op(Operation::UpdatePending(None));
+                    }
+                    _ if is_sequence => {
+                        output.add_op(Operation::UpdatePending(None));
+                    }
+                    _ => {
+                        #[allow(clippy::indexing_slicing)]
+                        // EnumMap guarantees that index is in bounds.
+                        let mode_interpreter = self.map[self.mode];
+
+                        output = mode_interpreter.decode(UserAction::Key { code, modifiers });
+
+                        if count.is_some() && output.operations.is_empty() {
+                            output.add_op(Operation::UpdatePending(None));
+                        }
+                    }
+                }
+            }
+        }
+
+        output
+    }
+
+    /// Switches to the mode of `output` and returns its [`Operation`]s.
+    fn finish(&mut self, output: Output) -> Vec<Operation> {
+        if let Some(mode) = output.new_mode {
+            self.mode = mode;
+        }
+
+        output.operations
+    }
+
+    /// Returns the [`Operation`]s that map to `input` given the current [`Mode`], in the order in which they shall be performed.
+    pub(crate) fn translate(&mut self, input: Input) -> Vec<Operation> {
         let mut output = Output::new();
 
         match input {
             Input::File(file) => {
                 output.add_op(Operation::CreateDoc(file));
             }
+            Input::Changed(file) => {
+                output.add_op(Operation::ExternalChange(file));
+            }
             Input::Glitch(glitch) => {
                 output.add_op(Operation::Alert(ShowMessageParams {
                     typ: MessageType::Error,
@@ -125,22 +466,66 @@ impl Interpreter {
                 }));
             }
             Input::Setting(setting) => {
+                match &setting {
+                    Setting::Keymap(keymap) => {
+                        self.keymap = keymap.with_defaults();
+                        self.clear_pending();
+                    }
+                    Setting::KeyTimeout(key_timeout) => {
+                        self.key_timeout = Duration::from_millis(*key_timeout);
+                    }
+                    Setting::Wrap(..)
+                    | Setting::WrapGlyph(..)
+                    | Setting::TabWidth(..)
+                    | Setting::ExpandTabs(..)
+                    | Setting::LineNumbers(..)
+                    | Setting::SignColumns(..)
+                    | Setting::ScrollOff(..)
+                    | Setting::Theme(..)
+                    | Setting::AutoSave(..)
+                    | Setting::TrimTrailingWhitespace(..)
+                    | Setting::FinalNewline(..)
+                    | Setting::Backup(..)
+                    | Setting::Language(..) => {}
+                }
+
                 output.add_op(Operation::UpdateSetting(setting));
             }
+            Input::History(history) => {
+                output.add_op(Operation::LoadHistory(history));
+            }
+            Input::Index(index) => {
+                output.add_op(Operation::LoadIndex(index));
+            }
+            Input::Saved(outcome) => {
+                output.add_op(Operation::Saved(outcome));
+            }
+            Input::Timeout => {
+                output.add_op(Operation::Timeout);
+            }
             Input::Lsp(ToolMessage {
                 language_id,
                 message,
-            }) => {
-                if let Some(return_message) = match message {
-                    ServerMessage::Initialize => Some(ClientMessage::Initialized),
-                    ServerMessage::Request { id } => Some(ClientMessage::RegisterCapability(id)),
-                    ServerMessage::Shutdown => None,
-                } {
+            }) => match message {
+                ServerMessage::Initialize => {
+                    output.add_op(Operation::Initialized(language_id));
+                }
+                ServerMessage::Request { id } => {
                     output.add_op(Operation::SendLsp(ToolMessage {
                         language_id,
-                        message: return_message,
+                        message: ClientMessage::RegisterCapability(id),
                     }));
                 }
+                ServerMessage::SemanticTokens { url, tokens } => {
+                    output.add_op(Operation::Tokenize { url, tokens });
+                }
+                ServerMessage::InlayHints { url, hints } => {
+                    output.add_op(Operation::Hint { url, hints });
+                }
+                ServerMessage::Shutdown => {}
+            },
+            Input::User(UserAction::Key { code, modifiers }) => {
+                output = self.press(code, modifiers);
             }
             Input::User(user_input) => {
                 #[allow(clippy::indexing_slicing)] // EnumMap guarantees that index is in bounds.
@@ -150,11 +535,7 @@ impl Interpreter {
             }
         }
 
-        if let Some(mode) = output.new_mode {
-            self.mode = mode;
-        }
-
-        output.operation
+        self.finish(output)
     }
 }
 
@@ -177,14 +558,30 @@ impl Default for Interpreter {
                 Mode::Collect => &COLLECT_INTERPRETER,
             },
             mode: Mode::default(),
+            keymap: Keymap::default().with_defaults(),
+            key_timeout: Duration::from_millis(1000),
+            pending: Vec::new(),
+            count: None,
+            deadline: None,
         }
     }
 }
 
+/// How the pending chords match the bindings of a [`Mode`].
+#[derive(Clone, Debug)]
+enum Match {
+    /// No binding begins with the chords.
+    None,
+    /// The chords are bound to the binding and no longer sequence begins with them.
+    Binding(Binding),
+    /// Longer sequences begin with the chords, which may also be bound to a binding.
+    Prefix(Option<Binding>),
+}
+
 /// Signifies the mode of the application.
 #[derive(Copy, Clone, Debug, Enum, Eq, ParseDisplay, PartialEq, Hash)]
 #[display(style = "CamelCase")]
-enum Mode {
+pub(crate) enum Mode {
     /// Displays the current file.
     View,
     /// Confirms the user's action
@@ -203,8 +600,8 @@ impl Default for Mode {
 /// Signifies the data gleaned from user input.
 #[derive(Debug, Default, PartialEq)]
 struct Output {
-    /// The operation to be run.
-    operation: Option<Operation>,
+    /// The operations to be run, in order.
+    operations: Vec<Operation>,
     /// The mode to switch to.
     ///
     /// If None, interpreter should not switch modes.
@@ -217,9 +614,9 @@ impl Output {
         Self::default()
     }
 
-    /// Adds `operation` to `self`.
+    /// Adds `operation` after the operations of `self`.
     fn add_op(&mut self, operation: Operation) {
-        let _ = self.operation.replace(operation);
+        self.operations.push(operation);
     }
 
     /// Sets the mode of `self` to `mode`.
@@ -232,11 +629,176 @@ impl Output {
         self.add_op(Operation::Reset);
         self.set_mode(Mode::View);
     }
+
+    /// Modifies `self` to perform each action of `binding` in order.
+    fn perform_binding(&mut self, binding: &Binding, count: Option<usize>) {
+        for action in binding.actions() {
+            self.perform(*action, count);
+        }
+    }
+
+    /// Modifies `self` to perform `action`, which movements and resizes repeat `count` times.
+    fn perform(&mut self, action: Action, count: Option<usize>) {
+        let repeat = count.unwrap_or(1);
+        let step = i8::try_from(usize::from(RESIZE_STEP.unsigned_abs()).saturating_mul(repeat))
+            .unwrap_or(i8::MAX);
+        let movement = |movement| Operation::Document(DocOp::Move(movement, repeat));
+        let line = |default| {
+            Operation::Document(DocOp::Move(
+                Movement::Line(count.map_or(default, |count| count.saturating_sub(1))),
+                1,
+            ))
+        };
+
+        match action {
+            Action::Ignore => {}
+            Action::Cancel => {
+                self.reset();
+            }
+            Action::ConfirmQuit => {
+                self.add_op(Operation::Confirm(ConfirmAction::Quit));
+                self.set_mode(Mode::Confirm);
+            }
+            Action::Quit => {
+                self.add_op(Operation::Quit);
+            }
+            Action::SaveAndQuit => {
+                self.add_op(Operation::SaveAndQuit);
+            }
+            Action::Save => {
+                self.add_op(Operation::Document(DocOp::Save));
+            }
+            Action::Overwrite => {
+                self.add_op(Operation::Document(DocOp::Overwrite));
+            }
+            Action::Reload => {
+                self.add_op(Operation::Document(DocOp::Reload));
+            }
+            Action::ShowMessages => {
+                self.add_op(Operation::ShowMessages);
+            }
+            Action::Open => {
+                self.add_op(Operation::StartCommand(Command::Open));
+                self.set_mode(Mode::Collect);
+            }
+            Action::Buffer => {
+                self.add_op(Operation::StartCommand(Command::Buffer));
+                self.set_mode(Mode::Collect);
+            }
+            Action::CommandLine => {
+                self.add_op(Operation::StartCommand(Command::Line));
+                self.set_mode(Mode::Collect);
+            }
+            Action::NextBuffer => {
+                self.add_op(Operation::SwitchBuffer(Switch::Next));
+            }
+            Action::PreviousBuffer => {
+                self.add_op(Operation::SwitchBuffer(Switch::Previous));
+            }
+            Action::SplitHorizontal => {
+                self.add_op(Operation::Pane(PaneOp::Split(Split::Horizontal)));
+            }
+            Action::SplitVertical => {
+                self.add_op(Operation::Pane(PaneOp::Split(Split::Vertical)));
+            }
+            Action::ClosePane => {
+                self.add_op(Operation::Pane(PaneOp::Close));
+            }
+            Action::GrowHeight => {
+                self.add_op(Operation::Pane(PaneOp::Resize(Split::Horizontal, step)));
+            }
+            Action::ShrinkHeight => {
+                self.add_op(Operation::Pane(PaneOp::Resize(Split::Horizontal, -step)));
+            }
+            Action::GrowWidth => {
+                self.add_op(Operation::Pane(PaneOp::Resize(Split::Vertical, step)));
+            }
+            Action::ShrinkWidth => {
+                self.add_op(Operation::Pane(PaneOp::Resize(Split::Vertical, -step)));
+            }
+            Action::FocusLeft => {
+                self.add_op(Operation::Pane(PaneOp::Focus(Direction::Left)));
+            }
+            Action::FocusDown => {
+                self.add_op(Operation::Pane(PaneOp::Focus(Direction::Down)));
+            }
+            Action::FocusUp => {
+                self.add_op(Operation::Pane(PaneOp::Focus(Direction::Up)));
+            }
+            Action::FocusRight => {
+                self.add_op(Operation::Pane(PaneOp::Focus(Direction::Right)));
+            }
+            Action::MoveLeft => {
+                self.add_op(movement(Movement::Left));
+            }
+            Action::MoveRight => {
+                self.add_op(movement(Movement::Right));
+            }
+            Action::RowUp => {
+                self.add_op(movement(Movement::RowUp));
+            }
+            Action::RowDown => {
+                self.add_op(movement(Movement::RowDown));
+            }
+            Action::LineUp => {
+                self.add_op(movement(Movement::LineUp));
+            }
+            Action::LineDown => {
+                self.add_op(movement(Movement::LineDown));
+            }
+            Action::FirstLine => {
+                self.add_op(line(0));
+            }
+            Action::LastLine => {
+                self.add_op(line(usize::MAX));
+            }
+            Action::Execute => {
+                self.add_op(Operation::Execute);
+                self.set_mode(Mode::View);
+            }
+            Action::Complete => {
+                self.add_op(Operation::Complete);
+            }
+            Action::DeleteBack => {
+                self.add_op(Operation::EditInput(InputEdit::DeleteBack));
+            }
+            Action::DeleteForward => {
+                self.add_op(Operation::EditInput(InputEdit::DeleteForward));
+            }
+            Action::DeleteWord => {
+                self.add_op(Operation::EditInput(InputEdit::DeleteWord));
+            }
+            Action::CursorLeft => {
+                self.add_op(Operation::EditInput(InputEdit::Left));
+            }
+            Action::CursorRight => {
+                self.add_op(Operation::EditInput(InputEdit::Right));
+            }
+            Action::CursorStart => {
+                self.add_op(Operation::EditInput(InputEdit::Start));
+            }
+            Action::CursorEnd => {
+                self.add_op(Operation::EditInput(InputEdit::End));
+            }
+            Action::HistoryPrevious => {
+                self.add_op(Operation::EditInput(InputEdit::Previous));
+            }
+            Action::HistoryNext => {
+                self.add_op(Operation::EditInput(InputEdit::Next));
+            }
+            Action::NextMatch => {
+                self.add_op(Operation::SelectMatch(Switch::Next));
+            }
+            Action::PreviousMatch => {
+                self.add_op(Operation::SelectMatch(Switch::Previous));
+            }
+        }
+    }
 }
 
 /// Defines the functionality to convert [`Input`] to [`Output`].
 trait ModeInterpreter: Debug {
-    /// Converts `input` to [`Operation`]s.
+    /// Converts `input`, which is not a key bound to an action, to [`Operation`]s.
     fn decode(&self, input: UserAction) -> Output;
 }
 
@@ -249,43 +811,6 @@ impl ViewInterpreter {
     const fn new() -> Self {
         Self {}
     }
-
-    /// Converts `output` appropriate to `key`.
-    fn decode_key(key: KeyCode, output: &mut Output) {
-        match key {
-            KeyCode::Esc => {
-                output.add_op(Operation::Reset);
-            }
-            KeyCode::Char('w') => {
-                output.add_op(Operation::Confirm(ConfirmAction::Quit));
-                output.set_mode(Mode::Confirm);
-            }
-            KeyCode::Char('s') => {
-                output.add_op(Operation::Document(DocOp::Save));
-            }
-            KeyCode::Char('o') => {
-                output.add_op(Operation::StartCommand(Command::Open));
-                output.set_mode(Mode::Collect);
-            }
-            KeyCode::Backspace
-            | KeyCode::Enter
-            | KeyCode::Left
-            | KeyCode::Right
-            | KeyCode::Up
-            | KeyCode::Down
-            | KeyCode::Home
-            | KeyCode::End
-            | KeyCode::PageUp
-            | KeyCode::PageDown
-            | KeyCode::Tab
-            | KeyCode::BackTab
-            | KeyCode::Delete
-            | KeyCode::Insert
-            | KeyCode::F(..)
-            | KeyCode::Null
-            | KeyCode::Char(..) => {}
-        }
-    }
 }
 
 impl ModeInterpreter for ViewInterpreter {
@@ -293,13 +818,10 @@ impl ModeInterpreter for ViewInterpreter {
         let mut output = Output::new();
 
         match input {
-            UserAction::Key { code, .. } => {
-                Self::decode_key(code, &mut output);
-            }
             UserAction::Resize { dimensions } => {
                 output.add_op(Operation::Resize { dimensions });
             }
-            UserAction::Mouse => {}
+            UserAction::Key { .. } | UserAction::Mouse => {}
         }
 
         output
@@ -318,21 +840,10 @@ impl ConfirmInterpreter {
 }
 
 impl ModeInterpreter for ConfirmInterpreter {
-    fn decode(&self, input: UserAction) -> Output {
+    fn decode(&self, _: UserAction) -> Output {
         let mut output = Output::new();
 
-        match input {
-            UserAction::Key {
-                code: KeyCode::Char('y'),
-                ..
-            } => {
-                output.add_op(Operation::Quit);
-            }
-            UserAction::Key { .. } | UserAction::Mouse | UserAction::Resize { .. } => {
-                output.reset();
-            }
-        }
-
+        output.reset();
         output
     }
 }
@@ -353,25 +864,12 @@ impl ModeInterpreter for CollectInterpreter {
         let mut output = Output::new();
 
         match input {
-            UserAction::Key {
-                code: KeyCode::Esc, ..
-            } => {
-                output.reset();
-            }
-            UserAction::Key {
-                code: KeyCode::Enter,
-                ..
-            } => {
-                output.add_op(Operation::Execute);
-                output.set_mode(Mode::View);
-            }
-            UserAction::Key {
-                code: KeyCode::Char(c),
-                ..
-            } => {
-                output.add_op(Operation::Collect(c));
+            UserAction::Key { code, modifiers } => {
+                if let Some(c) = Chord::new(code, modifiers).character() {
+                    output.add_op(Operation::Collect(c));
+                }
             }
-            UserAction::Key { .. } | UserAction::Mouse | UserAction::Resize { .. } => {}
+            UserAction::Mouse | UserAction::Resize { .. } => {}
         }
 
         output
@@ -383,8 +881,10 @@ impl ModeInterpreter for CollectInterpreter {
 mod test {
     use {
         super::*,
-        crate::io::{config::Setting, Glitch},
-        crossterm::event::KeyModifiers,
+        crate::io::{
+            config::{Setting, Wrap},
+            Glitch,
+        },
     };
 
     /// Tests decoding user input while the [`Interpreter`] is in [`Mode::View`].
@@ -402,10 +902,10 @@ mod test {
 
             assert_eq!(
                 int.translate(Input::Glitch(Glitch::WatcherConnection)),
-                Some(Operation::Alert(ShowMessageParams {
+                vec![Operation::Alert(ShowMessageParams {
                     typ: MessageType::Error,
                     message: "config file watcher disconnected".to_string(),
-                }))
+                })]
             );
             assert_eq!(int.mode, Mode::View);
         }
@@ -416,8 +916,30 @@ mod test {
             let mut int = view_mode();
 
             assert_eq!(
-                int.translate(Input::Setting(Setting::Wrap(true))),
-                Some(Operation::UpdateSetting(Setting::Wrap(true)))
+                int.translate(Input::Setting(Setting::Wrap(Wrap::Char))),
+                vec![Operation::UpdateSetting(Setting::Wrap(Wrap::Char))]
+            );
+            assert_eq!(int.mode, Mode::View);
+        }
+
+        /// Semantic tokens from the language server shall be applied to the document.
+        #[test]
+        fn semantic_tokens() {
+            let mut int = view_mode();
+            let url = Url::parse("file:///main.rs").unwrap();
+
+            assert_eq!(
+                int.translate(Input::Lsp(ToolMessage {
+                    language_id: LanguageId::Rust,
+                    message: ServerMessage::SemanticTokens {
+                        url: url.clone(),
+                        tokens: Vec::new(),
+                    },
+                })),
+                vec![Operation::Tokenize {
+                    url,
+                    tokens: Vec::new()
+                }]
             );
             assert_eq!(int.mode, Mode::View);
         }
@@ -432,11 +954,20 @@ mod test {
                     code: KeyCode::Char('w'),
                     modifiers: KeyModifiers::CONTROL,
                 })),
-                Some(Operation::Confirm(ConfirmAction::Quit))
+                vec![Operation::Confirm(ConfirmAction::Quit)]
             );
             assert_eq!(int.mode, Mode::Confirm);
         }
 
+        /// The `w` key without `Ctrl` shall not quit.
+        #[test]
+        fn plain_quit() {
+            let mut int = view_mode();
+
+            assert_eq!(int.translate(key('w')), Vec::new());
+            assert_eq!(int.mode, Mode::View);
+        }
+
         /// The `Ctrl-o` key shall request the name of the document to be opened.
         #[test]
         fn open() {
@@ -447,7 +978,7 @@ mod test {
                     code: KeyCode::Char('o'),
                     modifiers: KeyModifiers::CONTROL,
                 })),
-                Some(Operation::StartCommand(Command::Open))
+                vec![Operation::StartCommand(Command::Open)]
             );
             assert_eq!(int.mode, Mode::Collect);
         }
@@ -462,10 +993,207 @@ mod test {
                     code: KeyCode::Char('s'),
                     modifiers: KeyModifiers::CONTROL,
                 })),
-                Some(Operation::Document(DocOp::Save))
+                vec![Operation::Document(DocOp::Save)]
+            );
+            assert_eq!(int.mode, Mode::View);
+        }
+
+        /// Home-row keys held with `Alt` shall move the focus between panes.
+        #[test]
+        fn focus() {
+            let mut int = view_mode();
+
+            assert_eq!(
+                int.translate(Input::User(UserAction::Key {
+                    code: KeyCode::Char('l'),
+                    modifiers: KeyModifiers::ALT,
+                })),
+                vec![Operation::Pane(PaneOp::Focus(Direction::Right))]
+            );
+            assert_eq!(
+                int.translate(Input::User(UserAction::Key {
+                    code: KeyCode::Char('j'),
+                    modifiers: KeyModifiers::empty(),
+                })),
+                vec![Operation::Document(DocOp::Move(Movement::LineDown, 1))]
             );
             assert_eq!(int.mode, Mode::View);
         }
+        /// A key held with modifiers that its binding does not include shall do nothing.
+        #[test]
+        fn modifiers() {
+            let mut int = view_mode();
+
+            assert_eq!(
+                int.translate(Input::User(UserAction::Key {
+                    code: KeyCode::Char('j'),
+                    modifiers: KeyModifiers::CONTROL,
+                })),
+                Vec::new()
+            );
+            assert_eq!(
+                int.translate(Input::User(UserAction::Key {
+                    code: KeyCode::Char('S'),
+                    modifiers: KeyModifiers::SHIFT,
+                })),
+                vec![Operation::Document(DocOp::Overwrite)]
+            );
+        }
+
+        /// A keymap setting shall change the bindings while keeping the defaults it does not replace.
+        #[test]
+        fn keymap() {
+            let mut int = view_mode();
+            let keymap: Keymap =
+                toml::from_str("[view]\nj = \"ignore\"\n\"ctrl-q\" = \"quit\"").unwrap();
+
+            let _ = int.translate(Input::Setting(Setting::Keymap(keymap)));
+
+            assert_eq!(
+                int.translate(Input::User(UserAction::Key {
+                    code: KeyCode::Char('j'),
+                    modifiers: KeyModifiers::empty(),
+                })),
+                Vec::new()
+            );
+            assert_eq!(
+                int.translate(Input::User(UserAction::Key {
+                    code: KeyCode::Char('q'),
+                    modifiers: KeyModifiers::CONTROL,
+                })),
+                vec![Operation::Quit]
+            );
+            assert_eq!(
+                int.translate(Input::User(UserAction::Key {
+                    code: KeyCode::Char('k'),
+                    modifiers: KeyModifiers::empty(),
+                })),
+                vec![Operation::Document(DocOp::Move(Movement::LineUp, 1))]
+            );
+        }
+        /// Returns the input of pressing `c` without modifiers.
+        fn key(c: char) -> Input {
+            Input::User(UserAction::Key {
+                code: KeyCode::Char(c),
+                modifiers: KeyModifiers::empty(),
+            })
+        }
+
+        /// A sequence shall wait for its remaining keys and a count shall repeat the movement that follows it.
+        #[test]
+        fn sequence() {
+            let mut int = view_mode();
+
+            assert_eq!(
+                int.translate(key('g')),
+                vec![Operation::UpdatePending(Some(Duration::from_millis(1000)))]
+            );
+            assert_eq!(int.pending(), "g");
+            assert_eq!(
+                int.translate(key('g')),
+                vec![Operation::Document(DocOp::Move(Movement::Line(0), 1))]
+            );
+            assert_eq!(
+                int.translate(key('1')),
+                vec![Operation::UpdatePending(None)]
+            );
+            assert_eq!(
+                int.translate(key('0')),
+                vec![Operation::UpdatePending(None)]
+            );
+            assert_eq!(int.pending(), "10");
+            assert_eq!(
+                int.translate(key('j')),
+                vec![Operation::Document(DocOp::Move(Movement::LineDown, 10))]
+            );
+            assert_eq!(int.pending(), "");
+            assert_eq!(int.translate(key('0')), Vec::new());
+            assert_eq!(
+                int.translate(key('4')),
+                vec![Operation::UpdatePending(None)]
+            );
+            assert_eq!(
+                int.translate(key('g')),
+                vec![Operation::UpdatePending(Some(Duration::from_millis(1000)))]
+            );
+            assert_eq!(int.pending(), "4g");
+            assert_eq!(
+                int.translate(key('g')),
+                vec![Operation::Document(DocOp::Move(Movement::Line(3), 1))]
+            );
+            assert_eq!(
+                int.translate(key('g')),
+                vec![Operation::UpdatePending(Some(Duration::from_millis(1000)))]
+            );
+            assert_eq!(
+                int.translate(key('x')),
+                vec![Operation::UpdatePending(None)]
+            );
+            assert_eq!(int.pending(), "");
+        }
+
+        /// A sequence bound to several actions shall translate into each of their operations in order.
+        #[test]
+        fn composite() {
+            let mut int = view_mode();
+            let keymap: Keymap =
+                toml::from_str("[view]\nx = [\"split_vertical\", \"focus_right\"]").unwrap();
+
+            let _ = int.translate(Input::Setting(Setting::Keymap(keymap)));
+
+            assert_eq!(
+                int.translate(key('x')),
+                vec![
+                    Operation::Pane(PaneOp::Split(Split::Vertical)),
+                    Operation::Pane(PaneOp::Focus(Direction::Right))
+                ]
+            );
+        }
+
+        /// When the key timeout passes, an ambiguous sequence shall perform the action of the keys pressed so far.
+        #[test]
+        fn timeout() {
+            let mut int = view_mode();
+            let keymap: Keymap = toml::from_str("[view]\ng = \"last_line\"").unwrap();
+
+            let _ = int.translate(Input::Setting(Setting::Keymap(keymap)));
+            let _ = int.translate(Input::Setting(Setting::KeyTimeout(500)));
+
+            assert_eq!(
+                int.translate(key('g')),
+                vec![Operation::UpdatePending(Some(Duration::from_millis(500)))]
+            );
+            assert_eq!(int.expire(Instant::now()), Vec::new());
+            assert_eq!(
+                int.expire(Instant::now() + Duration::from_secs(1)),
+                vec![Operation::Document(DocOp::Move(
+                    Movement::Line(usize::MAX),
+                    1
+                ))]
+            );
+            assert_eq!(int.deadline(), None);
+            assert_eq!(int.pending(), "");
+        }
+
+        /// A key that does not continue an ambiguous sequence shall perform the action of the keys pressed before it and then its own action.
+        #[test]
+        fn interrupted() {
+            let mut int = view_mode();
+            let keymap: Keymap = toml::from_str("[view]\ng = \"last_line\"").unwrap();
+
+            let _ = int.translate(Input::Setting(Setting::Keymap(keymap)));
+            let _ = int.translate(key('g'));
+
+            assert_eq!(
+                int.translate(key('j')),
+                vec![
+                    Operation::Document(DocOp::Move(Movement::Line(usize::MAX), 1)),
+                    Operation::Document(DocOp::Move(Movement::LineDown, 1))
+                ]
+            );
+            assert_eq!(int.deadline(), None);
+            assert_eq!(int.pending(), "");
+        }
     }
 
     /// Tests decoding user input while in the Confirm mode.
@@ -473,9 +1201,10 @@ mod test {
         use super::*;
 
         fn confirm_mode() -> Interpreter {
-            let mut int = Interpreter::default();
-            int.mode = Mode::Confirm;
-            int
+            Interpreter {
+                mode: Mode::Confirm,
+                ..Interpreter::default()
+            }
         }
 
         /// The `y` key shall confirm the action.
@@ -488,7 +1217,21 @@ mod test {
                     code: KeyCode::Char('y'),
                     modifiers: KeyModifiers::empty(),
                 })),
-                Some(Operation::Quit)
+                vec![Operation::Quit]
+            );
+        }
+
+        /// The `s` key shall save unsaved changes before quitting.
+        #[test]
+        fn save() {
+            let mut int = confirm_mode();
+
+            assert_eq!(
+                int.translate(Input::User(UserAction::Key {
+                    code: KeyCode::Char('s'),
+                    modifiers: KeyModifiers::empty(),
+                })),
+                vec![Operation::SaveAndQuit]
             );
         }
 
@@ -502,7 +1245,7 @@ mod test {
                     code: KeyCode::Char('n'),
                     modifiers: KeyModifiers::empty(),
                 })),
-                Some(Operation::Reset)
+                vec![Operation::Reset]
             );
             assert_eq!(int.mode, Mode::View);
 
@@ -513,7 +1256,7 @@ mod test {
                     code: KeyCode::Char('1'),
                     modifiers: KeyModifiers::empty(),
                 })),
-                Some(Operation::Reset)
+                vec![Operation::Reset]
             );
             assert_eq!(int.mode, Mode::View);
         }
@@ -525,9 +1268,10 @@ mod test {
         use super::*;
 
         fn collect_mode() -> Interpreter {
-            let mut int = Interpreter::default();
-            int.mode = Mode::Collect;
-            int
+            Interpreter {
+                mode: Mode::Collect,
+                ..Interpreter::default()
+            }
         }
 
         /// The `Esc` key shall return to [`Mode::View`].
@@ -540,7 +1284,7 @@ mod test {
                     code: KeyCode::Esc,
                     modifiers: KeyModifiers::empty(),
                 })),
-                Some(Operation::Reset)
+                vec![Operation::Reset]
             );
             assert_eq!(int.mode, Mode::View);
         }
@@ -555,7 +1299,7 @@ mod test {
                     code: KeyCode::Char('a'),
                     modifiers: KeyModifiers::empty(),
                 })),
-                Some(Operation::Collect('a'))
+                vec![Operation::Collect('a')]
             );
             assert_eq!(int.mode, Mode::Collect);
 
@@ -566,7 +1310,7 @@ mod test {
                     code: KeyCode::Char('.'),
                     modifiers: KeyModifiers::empty(),
                 })),
-                Some(Operation::Collect('.'))
+                vec![Operation::Collect('.')]
             );
             assert_eq!(int.mode, Mode::Collect);
 
@@ -577,7 +1321,7 @@ mod test {
                     code: KeyCode::Char('1'),
                     modifiers: KeyModifiers::empty(),
                 })),
-                Some(Operation::Collect('1'))
+                vec![Operation::Collect('1')]
             );
             assert_eq!(int.mode, Mode::Collect);
         }
@@ -592,7 +1336,7 @@ mod test {
                     code: KeyCode::Enter,
                     modifiers: KeyModifiers::empty(),
                 })),
-                Some(Operation::Execute)
+                vec![Operation::Execute]
             );
             assert_eq!(int.mode, Mode::View);
         }
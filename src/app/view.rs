@@ -0,0 +1,673 @@
+//! Implements how a pane displays a document and tracks the position of its cursor.
+use {
+    super::{
+        layout::{self, Glyph, VisualRow},
+        translate::Movement,
+        Document,
+    },
+    crate::io::{
+        config::{Configuration, LineNumbers, Wrap},
+        fs::File,
+        lsp::{self, InlayKind},
+        ui::{Dimensions, Row, Scope},
+    },
+    core::convert::TryFrom,
+    unicode_segmentation::UnicodeSegmentation,
+    unicode_width::UnicodeWidthStr,
+};
+
+/// How a pane displays a document: its size, its [`Appearance`], the position of its cursor and how far it is scrolled.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct View {
+    /// The [`Dimensions`] of the view.
+    pub(crate) dimensions: Dimensions,
+    /// How the view displays text.
+    pub(crate) appearance: Appearance,
+    /// The position of the cursor.
+    pub(crate) cursor: Cursor,
+    /// The first visual row that is displayed.
+    scroll: RowPosition,
+    /// The number of columns that are scrolled past on the left side of the view.
+    ///
+    /// Only applies when lines are not wrapped.
+    offset: usize,
+}
+
+impl View {
+    /// Places the cursor of `self` at `cursor` in a document that was not previously displayed.
+    pub(crate) fn show(&mut self, cursor: Cursor) {
+        self.cursor = cursor;
+        self.scroll = RowPosition::default();
+        self.offset = 0;
+    }
+
+    /// Moves the cursor of `self` to the closest position that is within `doc`.
+    pub(crate) fn clamp_cursor(&mut self, doc: &Document) {
+        let lines: Vec<&str> = doc.file.lines().collect();
+        let line = self.cursor.line.min(lines.len().saturating_sub(1));
+
+        self.cursor = Cursor {
+            line,
+            character: grapheme_boundaries(lines.get(line).copied().unwrap_or(""))
+                .into_iter()
+                .rev()
+                .find(|boundary| *boundary <= self.cursor.character)
+                .unwrap_or(0),
+        };
+    }
+
+    /// Returns the [`VisualRow`]s that display `line`, which is at `index` of `doc`, where the cursor is styled if `is_focused`.
+    ///
+    /// Only the lines that are needed are laid out so that the work of displaying a document does not grow with its length.
+    fn line_rows(
+        &self,
+        doc: &Document,
+        index: usize,
+        line: &str,
+        is_focused: bool,
+    ) -> Vec<VisualRow> {
+        layout::wrap(
+            index,
+            self.decorate(doc, index, line, is_focused),
+            self.text_width(doc),
+            self.appearance.wrap,
+            self.appearance.wrap_glyph,
+        )
+    }
+
+    /// Returns the position of the visual row that is `count` rows after `position` if `is_forward`, otherwise before it, where `lines` are the lines of `doc`.
+    ///
+    /// The position stops at the first or last visual row of `doc`.
+    fn step(
+        &self,
+        doc: &Document,
+        lines: &[&str],
+        position: RowPosition,
+        count: usize,
+        is_forward: bool,
+    ) -> RowPosition {
+        let row_count = |line: usize| {
+            self.line_rows(doc, line, lines.get(line).copied().unwrap_or(""), true)
+                .len()
+        };
+        let mut position = position;
+        let mut remaining = count;
+
+        if is_forward {
+            loop {
+                let available = row_count(position.line)
+                    .saturating_sub(1)
+                    .saturating_sub(position.row);
+
+                if remaining <= available {
+                    position.row = position.row.saturating_add(remaining);
+                    break;
+                } else if position.line.saturating_add(1) >= lines.len() {
+                    position.row = position.row.saturating_add(available);
+                    break;
+                }
+
+                remaining = remaining.saturating_sub(available.saturating_add(1));
+                position = RowPosition {
+                    line: position.line.saturating_add(1),
+                    row: 0,
+                };
+            }
+        } else {
+            loop {
+                if remaining <= position.row {
+                    position.row = position.row.saturating_sub(remaining);
+                    break;
+                } else if position.line == 0 {
+                    position.row = 0;
+                    break;
+                }
+
+                remaining = remaining.saturating_sub(position.row.saturating_add(1));
+                position.line = position.line.saturating_sub(1);
+                position.row = row_count(position.line).saturating_sub(1);
+            }
+        }
+
+        position
+    }
+
+    /// Returns a [`Vec`] of the rows of `self` that display `doc`, where the cursor is styled if `is_focused`.
+    ///
+    /// Rows past the end of the text are empty so that the entire view is filled.
+    ///
+    /// When lines are not wrapped, each row only displays the columns that are within the view.
+    pub(crate) fn rows(&self, doc: &Document, is_focused: bool) -> Vec<Row> {
+        let height = usize::from(*self.dimensions.height);
+        let width = self.text_width(doc);
+        let number_width = self.number_width(doc);
+        let mut rows: Vec<Row> = Vec::new();
+        let mut skipped = self.scroll.row;
+
+        for (index, line) in doc.file.lines().enumerate().skip(self.scroll.line) {
+            if rows.len() >= height {
+                break;
+            }
+
+            for row in self
+                .line_rows(doc, index, line, is_focused)
+                .into_iter()
+                .skip(skipped)
+                .take(height.saturating_sub(rows.len()))
+            {
+                let mut gutter = self.gutter(&row, number_width);
+
+                gutter.append(if self.appearance.wrap == Wrap::None {
+                    row.window(self.offset, width)
+                } else {
+                    Row::from(row)
+                });
+                rows.push(gutter);
+            }
+
+            skipped = 0;
+        }
+
+        rows.resize_with(height, Row::default);
+        rows
+    }
+
+    /// Returns the number of columns used to display the line numbers of `doc`.
+    ///
+    /// This is the number of digits in the number of the last line.
+    fn number_width(&self, doc: &Document) -> usize {
+        if self.appearance.line_numbers == LineNumbers::None {
+            0
+        } else {
+            doc.file.lines().count().to_string().len()
+        }
+    }
+
+    /// Returns the number of columns of the gutter of `self` when displaying `doc`.
+    fn gutter_width(&self, doc: &Document) -> usize {
+        let number_width = self.number_width(doc);
+
+        // Line numbers are separated from the text by a space.
+        self.appearance
+            .sign_columns
+            .saturating_add(if number_width > 0 {
+                number_width.saturating_add(1)
+            } else {
+                0
+            })
+    }
+
+    /// Returns the number of columns of `self` that are available for the text of `doc`.
+    fn text_width(&self, doc: &Document) -> usize {
+        usize::from(*self.dimensions.width).saturating_sub(self.gutter_width(doc))
+    }
+
+    /// Returns the gutter of `row`, where line numbers are `number_width` columns wide.
+    ///
+    /// Only the first row of each line displays its line number.
+    fn gutter(&self, row: &VisualRow, number_width: usize) -> Row {
+        let mut gutter = Row::default();
+
+        gutter.push_str(&" ".repeat(self.appearance.sign_columns), None);
+
+        if number_width > 0 {
+            let is_current = row.line == self.cursor.line;
+            let number = match self.appearance.line_numbers {
+                LineNumbers::None => None,
+                LineNumbers::Absolute => Some(row.line.saturating_add(1)),
+                LineNumbers::Hybrid if is_current => Some(row.line.saturating_add(1)),
+                LineNumbers::Relative | LineNumbers::Hybrid => {
+                    Some(row.line.max(self.cursor.line) - row.line.min(self.cursor.line))
+                }
+            }
+            .filter(|_| !row.is_continuation());
+            let scope = Scope::new(
+                if is_current {
+                    "ui.lineNumber.current"
+                } else {
+                    "ui.lineNumber"
+                }
+                .to_string(),
+            );
+
+            gutter.push_str(
+                &number.map_or_else(
+                    || " ".repeat(number_width),
+                    |number| format!("{:>width$}", number, width = number_width),
+                ),
+                Some(&scope),
+            );
+            gutter.push_str(" ", None);
+        }
+
+        gutter
+    }
+
+    /// Moves the cursor of `self` within `doc` according to `movement`.
+    pub(crate) fn move_cursor(&mut self, doc: &Document, movement: Movement) {
+        self.cursor = self.moved_cursor(doc, movement);
+    }
+
+    /// Returns the position of the cursor after it is moved according to `movement`.
+    fn moved_cursor(&self, doc: &Document, movement: Movement) -> Cursor {
+        let lines: Vec<&str> = doc.file.lines().collect();
+        let cursor = self.cursor;
+        let boundaries = |line: usize| grapheme_boundaries(lines.get(line).copied().unwrap_or(""));
+
+        match movement {
+            Movement::Left => Cursor {
+                character: boundaries(cursor.line)
+                    .into_iter()
+                    .rev()
+                    .find(|boundary| *boundary < cursor.character)
+                    .unwrap_or(0),
+                ..cursor
+            },
+            Movement::Right => Cursor {
+                character: boundaries(cursor.line)
+                    .into_iter()
+                    .find(|boundary| *boundary > cursor.character)
+                    .unwrap_or(cursor.character),
+                ..cursor
+            },
+            Movement::LineUp | Movement::LineDown | Movement::Line(..) => {
+                let last = lines.len().saturating_sub(1);
+                let line = match movement {
+                    Movement::LineUp => cursor.line.saturating_sub(1),
+                    Movement::Line(line) => line.min(last),
+                    Movement::LineDown
+                    | Movement::Left
+                    | Movement::Right
+                    | Movement::RowUp
+                    | Movement::RowDown => cursor.line.saturating_add(1).min(last),
+                };
+
+                Cursor {
+                    line,
+                    character: boundaries(line)
+                        .into_iter()
+                        .rev()
+                        .find(|boundary| *boundary <= cursor.character)
+                        .unwrap_or(0),
+                }
+            }
+            Movement::RowUp | Movement::RowDown => {
+                let line = lines.get(cursor.line).copied().unwrap_or("");
+                let rows = self.line_rows(doc, cursor.line, line, true);
+
+                rows.iter()
+                    .enumerate()
+                    .find(|(_, row)| row.contains(cursor.line, cursor.character))
+                    .and_then(|(index, row)| {
+                        let column = row.column_of(cursor.character)?;
+                        let position = RowPosition {
+                            line: cursor.line,
+                            row: index,
+                        };
+                        let target =
+                            self.step(doc, &lines, position, 1, movement == Movement::RowDown);
+                        let target_line = lines.get(target.line).copied().unwrap_or("");
+
+                        Some(target)
+                            .filter(|target| *target != position)
+                            .and_then(|target| {
+                                self.line_rows(doc, target.line, target_line, true)
+                                    .into_iter()
+                                    .nth(target.row)
+                            })
+                            .map(|row| Cursor {
+                                line: row.line,
+                                character: row.character_at(column),
+                            })
+                    })
+                    .unwrap_or(cursor)
+            }
+        }
+    }
+
+    /// Scrolls `self` so that the visual row of the cursor within `doc` is displayed.
+    ///
+    /// When lines are not wrapped, `self` is also scrolled horizontally so that the cursor is not hidden by an overflow indicator.
+    pub(crate) fn scroll_to_cursor(&mut self, doc: &Document) {
+        let height = usize::from(*self.dimensions.height);
+        let width = self.text_width(doc);
+        let lines: Vec<&str> = doc.file.lines().collect();
+        let line = lines.get(self.cursor.line).copied().unwrap_or("");
+
+        if let Some((index, row)) = self
+            .line_rows(doc, self.cursor.line, line, true)
+            .into_iter()
+            .enumerate()
+            .find(|(_, row)| row.contains(self.cursor.line, self.cursor.character))
+        {
+            let position = RowPosition {
+                line: self.cursor.line,
+                row: index,
+            };
+            // Keep `scroll_off` rows around the cursor, except beyond either end of the document.
+            let margin = self.appearance.scroll_off.min(height.saturating_sub(1) / 2);
+            let above = self.step(doc, &lines, position, margin, false);
+
+            if above < self.scroll {
+                self.scroll = above;
+            } else if height > 0 {
+                let below = self.step(doc, &lines, position, margin, true);
+                let top = self.step(doc, &lines, below, height.saturating_sub(1), false);
+
+                if top > self.scroll {
+                    self.scroll = top;
+                }
+            }
+
+            if self.appearance.wrap == Wrap::None {
+                let column = row.column_of(self.cursor.character).unwrap_or(0);
+
+                // Keep the cursor off of the columns used by the overflow indicators.
+                if column <= self.offset {
+                    self.offset = column.saturating_sub(1);
+                } else if width > 2 && column >= self.offset.saturating_add(width).saturating_sub(1)
+                {
+                    self.offset = column.saturating_add(2).saturating_sub(width);
+                }
+            } else {
+                self.offset = 0;
+            }
+        }
+    }
+
+    /// Returns the [`Glyph`]s of `line`, which is at `index`, scoped by the syntax highlighting and semantic tokens of `doc` and including its inlay hints.
+    ///
+    /// Semantic tokens take precedence over syntax highlighting. Inlay hints are only displayed; they are not a part of the text of `doc`. Tabs are expanded to the next tab stop. The cursor is displayed on the line that contains it, including after the last character of the line, but is only styled if `is_focused`.
+    fn decorate(&self, doc: &Document, index: usize, line: &str, is_focused: bool) -> Vec<Glyph> {
+        let line_number = u64::try_from(index).unwrap_or(u64::MAX);
+        let mut scopes: Vec<Option<&Scope>> = vec![None; line.chars().count()];
+
+        for highlight in doc.highlights.line(index) {
+            for scope in scopes
+                .iter_mut()
+                .skip(highlight.start)
+                .take(highlight.length)
+            {
+                *scope = Some(&highlight.scope);
+            }
+        }
+
+        for token in doc.tokens.iter().filter(|token| token.line == line_number) {
+            let start = lsp::utf16_to_char_index(line, token.start);
+            let end = lsp::utf16_to_char_index(line, token.start.saturating_add(token.length));
+
+            for scope in scopes.iter_mut().take(end).skip(start) {
+                *scope = Some(&token.scope);
+            }
+        }
+
+        let mut hints: Vec<(usize, String)> = doc
+            .hints
+            .iter()
+            .filter_map(|hint| {
+                let (position, label) = match hint.kind {
+                    InlayKind::ParameterHint => (hint.range.start, format!("{}: ", hint.label)),
+                    InlayKind::TypeHint => (hint.range.end, format!(": {}", hint.label)),
+                    InlayKind::ChainingHint => (hint.range.end, format!(" {}", hint.label)),
+                };
+
+                if position.line == line_number {
+                    Some((lsp::utf16_to_char_index(line, position.character), label))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        hints.sort_by_key(|(character, _)| *character);
+
+        let hint_scope = Scope::new("inlayHint".to_string());
+        let cursor_scope = Scope::new("ui.cursor".to_string());
+        let cursor = Some(self.cursor.character).filter(|_| self.cursor.line == index);
+        let tab_width = self.appearance.tab_width.max(1);
+        let mut hints = hints.into_iter().peekable();
+        let mut glyphs = Vec::new();
+        let mut character: usize = 0;
+
+        for grapheme in line.graphemes(true) {
+            while let Some((_, label)) = hints.next_if(|(position, _)| *position <= character) {
+                glyphs.push(hint_glyph(label, &hint_scope));
+            }
+
+            let scope = if is_focused && cursor == Some(character) {
+                Some(&cursor_scope)
+            } else {
+                scopes.get(character).copied().flatten()
+            };
+            let text = if grapheme == "\t" {
+                let column: usize = glyphs.iter().map(|glyph| glyph.text.width()).sum();
+
+                " ".repeat(tab_width.saturating_sub(column % tab_width))
+            } else {
+                grapheme.to_string()
+            };
+
+            glyphs.push(Glyph {
+                text,
+                scope: scope.cloned(),
+                character: Some(character),
+            });
+            character = character.saturating_add(grapheme.chars().count());
+        }
+
+        for (_, label) in hints {
+            glyphs.push(hint_glyph(label, &hint_scope));
+        }
+
+        if cursor.is_some_and(|cursor_character| cursor_character >= character) {
+            glyphs.push(Glyph {
+                text: " ".to_string(),
+                scope: Some(cursor_scope).filter(|_| is_focused),
+                character: Some(character),
+            });
+        }
+
+        glyphs
+    }
+}
+
+/// Returns the [`Glyph`] that displays the inlay hint `label`.
+fn hint_glyph(label: String, scope: &Scope) -> Glyph {
+    Glyph {
+        text: label,
+        scope: Some(scope.clone()),
+        character: None,
+    }
+}
+
+/// Returns the index of the first character of each grapheme cluster of `line`, followed by the number of characters in `line`.
+fn grapheme_boundaries(line: &str) -> Vec<usize> {
+    let mut character: usize = 0;
+    let mut boundaries = vec![0];
+
+    for grapheme in line.graphemes(true) {
+        character = character.saturating_add(grapheme.chars().count());
+        boundaries.push(character);
+    }
+
+    boundaries
+}
+
+/// How a view displays text.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Appearance {
+    /// How lines that are longer than the page are wrapped.
+    pub(crate) wrap: Wrap,
+    /// The glyph that begins each row that continues a wrapped line.
+    pub(crate) wrap_glyph: char,
+    /// The number of columns between tab stops.
+    pub(crate) tab_width: usize,
+    /// How line numbers are displayed.
+    pub(crate) line_numbers: LineNumbers,
+    /// The number of columns reserved for signs.
+    pub(crate) sign_columns: usize,
+    /// The number of lines kept visible above and below the cursor.
+    pub(crate) scroll_off: usize,
+}
+
+impl Appearance {
+    /// Returns the [`Appearance`] of a document of `file` as configured by `config`.
+    pub(crate) fn new(config: &Configuration, file: &File) -> Self {
+        let settings = config.doc_settings(file);
+
+        Self {
+            wrap: settings.wrap,
+            wrap_glyph: config.wrap_glyph,
+            tab_width: settings.tab_width,
+            line_numbers: config.line_numbers,
+            sign_columns: config.sign_columns,
+            scroll_off: config.scroll_off,
+        }
+    }
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Self {
+            wrap: Wrap::None,
+            wrap_glyph: '↪',
+            tab_width: 4,
+            line_numbers: LineNumbers::None,
+            sign_columns: 0,
+            scroll_off: 0,
+        }
+    }
+}
+
+/// The position of a visual row of a document.
+#[derive(Clone, Copy, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+struct RowPosition {
+    /// The index of the line that the row displays.
+    line: usize,
+    /// The index of the row among the rows that display the line.
+    row: usize,
+}
+
+/// A position within the text of a document.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) struct Cursor {
+    /// The index of the line.
+    pub(crate) line: usize,
+    /// The index of the character within the line.
+    pub(crate) character: usize,
+}
+
+/// Testing of the view module.
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Returns a [`Document`] of `text`.
+    fn doc(text: &str) -> Document {
+        Document::new(File::fixture("a.txt", text))
+    }
+
+    /// Returns a [`View`] that is `width` columns wide and `height` rows tall and wraps lines according to `wrap`.
+    fn view(width: u16, height: u16, wrap: Wrap) -> View {
+        View {
+            dimensions: Dimensions {
+                height: height.into(),
+                width: width.into(),
+            },
+            appearance: Appearance {
+                wrap,
+                wrap_glyph: '>',
+                ..Appearance::default()
+            },
+            ..View::default()
+        }
+    }
+
+    /// Returns the text of each row of `view` displaying `doc`.
+    fn texts(view: &View, doc: &Document) -> Vec<String> {
+        view.rows(doc, false).iter().map(Row::to_string).collect()
+    }
+
+    /// Scrolling shall keep the row of the cursor displayed and moving by rows shall cross the rows of wrapped lines.
+    #[test]
+    fn scroll() {
+        let doc = doc("abcdef\nghi\njklmnopq");
+        let mut view = view(4, 2, Wrap::Char);
+
+        view.cursor = Cursor {
+            line: 2,
+            character: 7,
+        };
+        view.scroll_to_cursor(&doc);
+        assert_eq!(texts(&view, &doc), vec!["> no", "> pq"]);
+
+        for _ in 0..3 {
+            view.move_cursor(&doc, Movement::RowUp);
+        }
+
+        assert_eq!(
+            view.cursor,
+            Cursor {
+                line: 1,
+                character: 2
+            }
+        );
+        view.scroll_to_cursor(&doc);
+        assert_eq!(texts(&view, &doc), vec!["ghi", "jklm"]);
+
+        view.move_cursor(&doc, Movement::RowUp);
+        view.scroll_to_cursor(&doc);
+        assert_eq!(
+            view.cursor,
+            Cursor {
+                line: 0,
+                character: 4
+            }
+        );
+        assert_eq!(texts(&view, &doc), vec!["> ef", "ghi"]);
+
+        view.move_cursor(&doc, Movement::RowUp);
+        view.move_cursor(&doc, Movement::RowUp);
+        assert_eq!(view.cursor.line, 0);
+        view.appearance.scroll_off = 1;
+        view.dimensions.height = 3.into();
+        view.cursor = Cursor {
+            line: 1,
+            character: 0,
+        };
+        view.scroll_to_cursor(&doc);
+        assert_eq!(texts(&view, &doc), vec!["> ef", "ghi", "jklm"]);
+        view.move_cursor(&doc, Movement::RowDown);
+        view.scroll_to_cursor(&doc);
+        assert_eq!(texts(&view, &doc), vec!["ghi", "jklm", "> no"]);
+    }
+
+    /// The gutter shall pad the sign columns, right-align line numbers relative to the cursor line and leave continuation rows unnumbered.
+    #[test]
+    fn gutter() {
+        let doc = doc("abcdefg\nb\nc\nd\ne\nf\ng\nh\ni\nj");
+        let mut view = view(9, 4, Wrap::Char);
+
+        view.appearance.sign_columns = 1;
+        view.appearance.line_numbers = LineNumbers::Relative;
+        view.cursor = Cursor {
+            line: 1,
+            character: 0,
+        };
+        assert_eq!(view.gutter_width(&doc), 4);
+        assert_eq!(
+            texts(&view, &doc),
+            vec!["  1 abcde", "    > fg", "  0 b", "  1 c"]
+        );
+
+        view.appearance.line_numbers = LineNumbers::Hybrid;
+        assert_eq!(
+            texts(&view, &doc),
+            vec!["  1 abcde", "    > fg", "  2 b", "  1 c"]
+        );
+
+        view.appearance.line_numbers = LineNumbers::None;
+        view.appearance.sign_columns = 0;
+        assert_eq!(view.gutter_width(&doc), 0);
+        assert_eq!(texts(&view, &doc), vec!["abcdefg", "b", "c", "d"]);
+    }
+}
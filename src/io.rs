@@ -1,38 +1,52 @@
 //! Implements the interface for all input and output to the application.
 pub mod config;
 pub mod fs;
+pub mod history;
 pub mod lsp;
 pub mod ui;
 
 use {
     crate::app::Document,
     clap::ArgMatches,
-    config::{ConsumeSettingError, CreateSettingConsumerError, Setting, SettingConsumer},
+    config::{
+        ConfigEvent, ConsumeSettingError, CreateSettingConsumerError, Setting, SettingConsumer,
+    },
     core::{
+        cell::{Cell, RefCell},
         convert::TryFrom,
         sync::atomic::{AtomicBool, Ordering},
+        time::Duration,
     },
     enum_map::Enum,
     fehler::{throw, throws},
-    fs::{ConsumeFileError, CreatePurlError, File, FileCommand, FileError, FileSystem, Purl},
+    fs::{
+        index::{Index, Indexer},
+        ConsumeFileError, CreatePurlError, File, FileCommand, FileError, FileEvent, FileSystem,
+        Purl,
+    },
+    history::{History, HistoryError},
     log::error,
     lsp::{
         ClientMessage, DocConfiguration, DocMessage, Fault, LanguageTool, SendNotificationError,
         ServerMessage, ToolMessage,
     },
-    lsp_types::{ShowMessageParams, ShowMessageRequestParams},
+    lsp_types::{MessageType, ShowMessageParams, ShowMessageRequestParams},
     market::{ClosedMarketFailure, Collector, ConsumeError, Consumer, ProduceError, Producer},
     parse_display::Display as ParseDisplay,
+    serde::Deserialize,
     starship::{context::Context, print},
     std::{
+        collections::VecDeque,
         env,
         io::{self, ErrorKind},
+        path::{Path, PathBuf},
+        time::Instant,
     },
     thiserror::Error,
     toml::{value::Table, Value},
     ui::{
-        CreateTerminalError, DisplayCmd, DisplayCmdFailure, Terminal, UserAction,
-        UserActionConsumer, UserActionFailure,
+        CreateTerminalError, DisplayCmd, DisplayCmdFailure, LoadThemeError, Row, Scope, Terminal,
+        UserAction, UserActionConsumer, UserActionFailure,
     },
     url::Url,
 };
@@ -65,6 +79,9 @@ pub enum CreateInterfaceError {
     /// An error creating a file.
     #[error(transparent)]
     CreateFile(#[from] CreateFileError),
+    /// An error creating the watcher of open files.
+    #[error("unable to create file watcher: {0}")]
+    FileWatcher(#[from] notify::Error),
 }
 
 /// An error while writing output.
@@ -99,7 +116,7 @@ pub enum CreateFileError {
     /// An error generating the [`Purl`] of the file.
     #[error(transparent)]
     Purl(#[from] CreatePurlError),
-    /// An error triggering a file read.
+    /// An error triggering a file read or write.
     #[error(transparent)]
     Read(#[from] ProduceError<FileError>),
 }
@@ -128,6 +145,21 @@ pub enum Glitch {
     /// Unable to convert config file to Config.
     #[error("config file invalid format: {0}")]
     ConfigFormat(#[from] toml::de::Error),
+    /// Unable to load theme file.
+    #[error(transparent)]
+    Theme(#[from] LoadThemeError),
+    /// Unable to read or write the history file.
+    #[error(transparent)]
+    History(#[from] HistoryError),
+    /// Unable to write the text of a document to a file.
+    #[error("unable to write `{path}`: {error}")]
+    WriteFile {
+        /// The path of the file.
+        path: String,
+        /// The error.
+        #[source]
+        error: CreateFileError,
+    },
 }
 
 /// An event that prevents [`Interface`] from consuming.
@@ -175,32 +207,64 @@ pub(crate) struct Interface {
     language_tool: LanguageTool,
     /// The root directory of the application.
     root_dir: Purl,
+    /// The directory that holds the config files of the application.
+    config_dir: PathBuf,
+    /// The file that persists the [`History`], if it is known and was able to be read.
+    history_file: Option<PathBuf>,
+    /// Maintains the index of the files of the project once it is first needed.
+    indexer: RefCell<Option<Indexer>>,
+    /// The inputs that have been generated by the interface itself and not consumed.
+    inputs: RefCell<VecDeque<Input>>,
+    /// The [`Glitch`]es that have occurred while producing outputs and have not been consumed.
+    glitches: RefCell<Vec<Glitch>>,
     /// The interface with the file system.
     file_system: FileSystem,
     /// The application has quit.
     has_quit: AtomicBool,
+    /// When the next [`Input::Timeout`] shall be consumed.
+    alarm: Cell<Option<Instant>>,
 }
 
 impl Interface {
     /// Creates a new interface.
     #[throws(CreateInterfaceError)]
-    pub(crate) fn new(initial_file: Option<&'_ str>) -> Self {
+    pub(crate) fn new(initial_file: Option<&'_ str>, config_file: Option<&'_ str>) -> Self {
         let root_dir = Purl::try_from(env::current_dir()?)?;
+        let config_dir = config::config_dir().ok_or(CreateInterfaceError::HomeDir)?;
         let mut consumers = Collector::new();
         consumers.convert_into_and_push(UserActionConsumer::new());
         consumers.convert_into_and_push(SettingConsumer::new(
-            &dirs::home_dir()
-                .ok_or(CreateInterfaceError::HomeDir)?
-                .join(".config/paper.toml"),
+            &config::config_file(config_file).ok_or(CreateInterfaceError::HomeDir)?,
+            root_dir.as_ref(),
         )?);
+        let mut history_file = history::history_file();
+        let mut glitches = Vec::new();
+        let mut inputs = VecDeque::new();
+
+        if let Some(file) = &history_file {
+            match History::read(file) {
+                Ok(history) => inputs.push_back(Input::History(history)),
+                Err(error) => {
+                    // The file is not written so that the history it holds is not lost.
+                    history_file = None;
+                    glitches.push(error.into());
+                }
+            }
+        }
 
         let interface = Self {
             consumers,
             user_interface: Terminal::new()?,
             language_tool: LanguageTool::new(&root_dir)?,
-            file_system: FileSystem::default(),
+            file_system: FileSystem::new()?,
             root_dir,
+            config_dir,
+            history_file,
+            indexer: RefCell::new(None),
+            inputs: RefCell::new(inputs),
+            glitches: RefCell::new(glitches),
             has_quit: AtomicBool::new(false),
+            alarm: Cell::new(None),
         };
 
         if let Some(file) = initial_file {
@@ -218,29 +282,45 @@ impl Interface {
         })?
     }
 
+    /// Writes `text` to the file at `path`, which is only replaced if `overwrite`.
+    #[throws(CreateFileError)]
+    fn write_file(&self, path: &str, text: String, overwrite: bool) {
+        self.file_system.produce(FileCommand::Write {
+            url: self.root_dir.join(path)?,
+            text,
+            backup: false,
+            overwrite: false,
+            is_new: !overwrite,
+        })?
+    }
+
     /// Edits the doc at `url`.
-    #[throws(ProduceError<ProduceOutputError>)]
+    ///
+    /// The outcome of a save is consumed as an [`Input::Saved`] so that the application only considers the document saved once its file has been written.
     fn edit_doc(&self, doc: &Document, edit: &DocEdit) {
         match edit {
-            DocEdit::Open { .. } => {
-                self.user_interface
-                    .produce(DisplayCmd::Rows { rows: doc.rows() })
-                    .map_err(|error| error.map(ProduceOutputError::from))?;
-            }
-            DocEdit::Save => {
-                self.file_system
-                    .produce(FileCommand::Write {
-                        url: doc.url().clone(),
-                        text: doc.text(),
-                    })
-                    .map_err(|error| error.map(ProduceOutputError::from))?;
+            DocEdit::Save { backup, overwrite } => {
+                let error = match self.file_system.produce(FileCommand::Write {
+                    url: doc.url().clone(),
+                    text: doc.text(),
+                    backup: *backup,
+                    overwrite: *overwrite,
+                    is_new: false,
+                }) {
+                    Ok(()) => None,
+                    Err(ProduceError::Failure(failure)) => Some(failure.to_string()),
+                    Err(error) => Some(error.to_string()),
+                };
+
+                self.inputs
+                    .borrow_mut()
+                    .push_back(Input::Saved(SaveOutcome {
+                        url: AsRef::<Url>::as_ref(doc.url()).clone(),
+                        version: doc.version(),
+                        error,
+                    }));
             }
-            DocEdit::Update => {
-                self.user_interface
-                    .produce(DisplayCmd::Rows { rows: doc.rows() })
-                    .map_err(|error| error.map(ProduceOutputError::from))?;
-            }
-            DocEdit::Close => {}
+            DocEdit::Open { .. } | DocEdit::Close => {}
         }
     }
 }
@@ -251,6 +331,22 @@ impl Consumer for Interface {
 
     #[throws(ConsumeError<Self::Failure>)]
     fn consume(&self) -> Self::Good {
+        let mut glitches = self.glitches.borrow_mut();
+
+        if !glitches.is_empty() {
+            return Input::Glitch(glitches.remove(0));
+        }
+
+        drop(glitches);
+
+        if let Some(input) = self.inputs.borrow_mut().pop_front() {
+            return input;
+        }
+
+        if let Some(index) = self.indexer.borrow().as_ref().and_then(Indexer::latest) {
+            return Input::Index(index);
+        }
+
         match self.consumers.consume() {
             Ok(input) => input,
             Err(ConsumeError::Failure(failure)) => {
@@ -267,7 +363,14 @@ impl Consumer for Interface {
                         throw!(ConsumeError::Failure(Self::Failure::Error(failure.into())))
                     }
                     Err(ConsumeError::EmptyStock) => {
-                        if self.has_quit.load(Ordering::Relaxed) {
+                        if self
+                            .alarm
+                            .get()
+                            .is_some_and(|alarm| alarm <= Instant::now())
+                        {
+                            self.alarm.set(None);
+                            Input::Timeout
+                        } else if self.has_quit.load(Ordering::Relaxed) {
                             throw!(ConsumeError::Failure(Self::Failure::Quit));
                         } else {
                             throw!(ConsumeError::EmptyStock);
@@ -353,8 +456,19 @@ impl Producer for Interface {
                 self.open_file(&path)
                     .map_err(|error| ProduceError::Failure(Self::Failure::from(error)))?;
             }
+            Output::WriteFile {
+                path,
+                text,
+                overwrite,
+            } => {
+                if let Err(error) = self.write_file(&path, text, overwrite) {
+                    self.glitches
+                        .borrow_mut()
+                        .push(Glitch::WriteFile { path, error });
+                }
+            }
             Output::EditDoc { doc, edit } => {
-                self.edit_doc(&doc, &edit)?;
+                self.edit_doc(&doc, &edit);
             }
             Output::UpdateHeader => {
                 let mut context = Context::new_with_dir(ArgMatches::new(), &self.root_dir);
@@ -381,24 +495,61 @@ impl Producer for Interface {
                     })
                     .map_err(|error| error.map(Self::Failure::from))?
             }
+            Output::UpdatePage { rows } => self
+                .user_interface
+                .produce(DisplayCmd::Rows { rows })
+                .map_err(|error| error.map(Self::Failure::from))?,
+            Output::UpdateStatus { status } => self
+                .user_interface
+                .produce(DisplayCmd::Status { status })
+                .map_err(|error| error.map(Self::Failure::from))?,
             Output::Notify { message } => self
                 .user_interface
-                .produce(DisplayCmd::Rows {
-                    rows: vec![message.message],
+                .produce(DisplayCmd::Message {
+                    message: message_row(message.typ, &message.message),
                 })
                 .map_err(|error| error.map(Self::Failure::from))?,
             Output::Question { request } => self
                 .user_interface
-                .produce(DisplayCmd::Rows {
-                    rows: vec![request.message],
+                .produce(DisplayCmd::Message {
+                    message: message_row(request.typ, &request.message),
                 })
                 .map_err(|error| error.map(Self::Failure::from))?,
             Output::Command { command } => self
                 .user_interface
-                .produce(DisplayCmd::Rows {
-                    rows: vec![command],
+                .produce(DisplayCmd::Message { message: command })
+                .map_err(|error| error.map(Self::Failure::from))?,
+            Output::ClearMessage => self
+                .user_interface
+                .produce(DisplayCmd::Message {
+                    message: Row::default(),
                 })
                 .map_err(|error| error.map(Self::Failure::from))?,
+            Output::IndexFiles => {
+                let _ = self.indexer.borrow_mut().get_or_insert_with(|| {
+                    Indexer::new(AsRef::<Path>::as_ref(&self.root_dir).to_path_buf())
+                });
+            }
+            Output::WriteHistory { history } => {
+                if let Some(file) = &self.history_file {
+                    if let Err(error) = history.write(file) {
+                        self.glitches.borrow_mut().push(error.into());
+                    }
+                }
+            }
+            Output::SetTheme { theme } => {
+                if let Err(error) = self.user_interface.set_theme(&self.config_dir.join(theme)) {
+                    self.glitches.borrow_mut().push(error.into());
+                }
+            }
+            Output::Schedule { delay } => {
+                let alarm = Instant::now() + delay;
+
+                // Only the earliest alarm is kept; the application schedules any later delays again when it is notified.
+                self.alarm.set(Some(
+                    self.alarm.get().map_or(alarm, |current| current.min(alarm)),
+                ));
+            }
             Output::Quit => {
                 self.has_quit.store(true, Ordering::Relaxed);
             }
@@ -406,13 +557,33 @@ impl Producer for Interface {
     }
 }
 
+/// Returns the [`Row`] that displays `text` as a message of `typ`.
+///
+/// Since a message is displayed on a single row, each newline is replaced by a space.
+fn message_row(typ: MessageType, text: &str) -> Row {
+    let scope = match typ {
+        MessageType::Error => "ui.message.error",
+        MessageType::Warning => "ui.message.warning",
+        MessageType::Info => "ui.message.info",
+        MessageType::Log => "ui.message.log",
+    };
+    let mut row = Row::default();
+
+    row.push_str(
+        &text.replace('\n', " "),
+        Some(&Scope::new(scope.to_string())),
+    );
+    row
+}
+
 /// An error occurred while converting a directory path to a URL.
 #[derive(Debug, Error)]
 #[error("while converting `{0}` to a URL")]
 pub struct UrlError(String);
 
 /// The language ids supported by `paper`.
-#[derive(Clone, Copy, Debug, Enum, Eq, Hash, ParseDisplay, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, Enum, Eq, Hash, ParseDisplay, PartialEq)]
+#[serde(rename_all = "snake_case")]
 pub enum LanguageId {
     /// The rust language.
     Rust,
@@ -423,7 +594,7 @@ impl LanguageId {
     #[allow(clippy::missing_const_for_fn)] // For stable rust, match is not allowed in const fn.
     fn server_cmd(&self) -> &str {
         match self {
-            Self::Rust => "rls",
+            Self::Rust => "rust-analyzer",
         }
     }
 }
@@ -433,14 +604,24 @@ impl LanguageId {
 pub enum Input {
     /// A file to be opened.
     File(File),
+    /// An open file that has been changed by another process.
+    Changed(File),
     /// An input from the user.
     User(UserAction),
     /// A setting.
     Setting(Setting),
     /// A glitch.
     Glitch(Glitch),
+    /// The history of the input given to each command.
+    History(History),
+    /// The files of the project.
+    Index(Index),
+    /// The outcome of saving a document.
+    Saved(SaveOutcome),
     /// A message from the language server.
     Lsp(ToolMessage<ServerMessage>),
+    /// A delay scheduled by [`Output::Schedule`] has expired.
+    Timeout,
 }
 
 impl From<File> for Input {
@@ -450,6 +631,26 @@ impl From<File> for Input {
     }
 }
 
+impl From<FileEvent> for Input {
+    #[inline]
+    fn from(value: FileEvent) -> Self {
+        match value {
+            FileEvent::Read(file) => Self::File(file),
+            FileEvent::Changed(file) => Self::Changed(file),
+        }
+    }
+}
+
+impl From<ConfigEvent> for Input {
+    #[inline]
+    fn from(value: ConfigEvent) -> Self {
+        match value {
+            ConfigEvent::Setting(setting) => Self::Setting(setting),
+            ConfigEvent::Glitch(glitch) => Self::Glitch(glitch),
+        }
+    }
+}
+
 impl From<ToolMessage<ServerMessage>> for Input {
     #[inline]
     fn from(value: ToolMessage<ServerMessage>) -> Self {
@@ -483,6 +684,16 @@ pub(crate) enum Output {
         /// The relative path of the file.
         path: String,
     },
+    /// Writes text to a file.
+    #[display("Write file `{path}`")]
+    WriteFile {
+        /// The relative path of the file.
+        path: String,
+        /// The text to be written.
+        text: String,
+        /// If an existing file is overwritten.
+        overwrite: bool,
+    },
     #[display("")]
     /// Edits a document.
     EditDoc {
@@ -491,9 +702,21 @@ pub(crate) enum Output {
         /// The edit to be performed.
         edit: DocEdit,
     },
+    /// Sets the rows of the page.
+    #[display("")]
+    UpdatePage {
+        /// The rows.
+        rows: Vec<Row>,
+    },
     /// Sets the header of the application.
     #[display("")]
     UpdateHeader,
+    /// Sets the status line.
+    #[display("")]
+    UpdateStatus {
+        /// The status.
+        status: Row,
+    },
     /// Notifies the user of a message.
     #[display("")]
     Notify {
@@ -509,8 +732,34 @@ pub(crate) enum Output {
     /// Adds an intake box.
     #[display("")]
     Command {
-        /// The prompt of the intake box.
-        command: String,
+        /// The prompt of the intake box, including its cursor.
+        command: Row,
+    },
+    /// Clears the message line.
+    #[display("")]
+    ClearMessage,
+    /// Styles text according to a theme file.
+    #[display("Set theme `{theme}`")]
+    SetTheme {
+        /// The path of the theme file, relative to the config directory.
+        theme: String,
+    },
+    /// Begins maintaining the index of the files of the project, if it is not already maintained.
+    ///
+    /// The index is built in the background and consumed as an [`Input::Index`] whenever it changes.
+    #[display("Index files")]
+    IndexFiles,
+    /// Persists the history of the input given to each command.
+    #[display("Write history")]
+    WriteHistory {
+        /// The history.
+        history: History,
+    },
+    /// Schedules an [`Input::Timeout`] after a delay.
+    #[display("Schedule timeout after {delay:?}")]
+    Schedule {
+        /// The delay.
+        delay: Duration,
     },
     /// Quit the application.
     #[display("")]
@@ -540,7 +789,6 @@ impl TryFrom<Output> for ToolMessage<ClientMessage> {
                                 },
                                 DocEdit::Save { .. } => DocMessage::Save,
                                 DocEdit::Close => DocMessage::Close,
-                                DocEdit::Update => throw!(TryIntoProtocolError::InvalidOutput),
                             },
                         )),
                     }
@@ -550,8 +798,16 @@ impl TryFrom<Output> for ToolMessage<ClientMessage> {
             }
             Output::SendLsp(message) => message,
             Output::OpenFile { .. }
+            | Output::WriteFile { .. }
             | Output::Command { .. }
+            | Output::UpdatePage { .. }
             | Output::UpdateHeader
+            | Output::UpdateStatus { .. }
+            | Output::ClearMessage
+            | Output::Schedule { .. }
+            | Output::IndexFiles
+            | Output::WriteHistory { .. }
+            | Output::SetTheme { .. }
             | Output::Notify { .. }
             | Output::Question { .. }
             | Output::Quit => throw!(TryIntoProtocolError::InvalidOutput),
@@ -567,6 +823,17 @@ pub enum TryIntoProtocolError {
     InvalidOutput,
 }
 
+/// The outcome of writing a document to its file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaveOutcome {
+    /// The URL of the file.
+    pub(crate) url: Url,
+    /// The version of the document that was written.
+    pub(crate) version: i64,
+    /// The error that prevented the file from being written, if any.
+    pub(crate) error: Option<String>,
+}
+
 /// Edits a document.
 #[derive(Clone, Copy, Debug)]
 pub(crate) enum DocEdit {
@@ -576,22 +843,16 @@ pub(crate) enum DocEdit {
         version: i64,
     },
     /// Saves the document.
-    Save,
-    /// Updates the display of the document.
-    Update,
+    Save {
+        /// If a backup of the previous contents of the file is kept.
+        backup: bool,
+        /// If the file is written even if another program changed it.
+        overwrite: bool,
+    },
     /// Closes the document.
     Close,
 }
 
-/// The changes to be made to a document.
-#[derive(Clone, Debug)]
-pub(crate) struct DocChange {
-    /// The new text.
-    new_text: String,
-    /// The version.
-    version: i64,
-}
-
 /// An error converting [`DocEdit`] into [`Message`].
 #[derive(Clone, Copy, Debug, Error)]
 pub enum TryIntoMessageError {
@@ -1,22 +1,165 @@
 //! Implements [`Consumer`] for configs.
+pub(crate) mod editorconfig;
+pub(crate) mod keymap;
+
 use {
+    super::{fs::File, Glitch, LanguageId},
     core::{
         cell::{Cell, RefCell},
+        convert::TryFrom,
         fmt::{self, Display},
-        //time::Duration,
+        iter,
+        time::Duration,
     },
+    enum_map::EnumMap,
     fehler::{throw, throws},
-    log::trace,
+    keymap::Keymap,
+    log::{trace, warn},
     market::{
-        channel::StdConsumer, ClosedMarketFailure, ConsumeError, Consumer, Inspector, StripFrom,
-        StrippingConsumer, VigilantConsumer,
+        channel::StdConsumer, ClosedMarketFailure, ConsumeError, Consumer, Inspector,
+        VigilantConsumer,
+    },
+    notify::{DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher},
+    parse_display::Display as ParseDisplay,
+    serde::{
+        de::{value, Error as _, IntoDeserializer},
+        Deserialize, Deserializer,
+    },
+    std::{
+        collections::HashMap,
+        env, fs, io,
+        path::{Path, PathBuf},
+        sync::mpsc,
     },
-    notify::DebouncedEvent, /*, RecommendedWatcher, RecursiveMode, Watcher}*/
-    serde::Deserialize,
-    std::{fs, io, path::PathBuf /*, sync::mpsc*/},
     thiserror::Error,
+    toml::{value::Table, Value},
 };
 
+/// How long the config file must go without events before a change is reported.
+const WATCH_DELAY: Duration = Duration::from_millis(100);
+/// The name of the config file within the config directory.
+const CONFIG_FILE_NAME: &str = "paper.toml";
+/// The byte order mark.
+const BOM: char = '\u{feff}';
+/// The name of the config file of a project.
+const PROJECT_CONFIG_FILE_NAME: &str = ".paper.toml";
+/// The text written by [`write_default_config`], which describes every setting along with its default value.
+const DEFAULT_CONFIG: &str = r#"# The config file of paper.
+#
+# Changes to this file are applied while paper is running. Settings in a `.paper.toml` file within the directory in which paper is started, or one of its ancestors, override the settings in this file. Settings from `.editorconfig` files override both for the files to which they apply.
+
+# How lines that are longer than the page are displayed: "none", "char", "word" or the column at which lines are wrapped.
+wrap = "none"
+
+# The glyph that begins each row that continues a wrapped line.
+wrap_glyph = "↪"
+
+# The number of columns between tab stops.
+tab_width = 4
+
+# If tabs within the indentation of each line are replaced by spaces, up to the next tab stop, when a document is saved.
+expand_tabs = false
+
+# How line numbers are displayed in the gutter: "none", "absolute", "relative" or "hybrid".
+line_numbers = "none"
+
+# The number of columns reserved in the gutter for signs.
+sign_columns = 0
+
+# The number of lines kept visible above and below the cursor.
+scroll_off = 0
+
+# The path of the theme file, relative to the config directory.
+theme = "paper-theme.toml"
+
+# If a document with unsaved changes is saved when a pane stops displaying it.
+auto_save = false
+
+# If whitespace at the end of each line is removed when a document is saved.
+trim_trailing_whitespace = false
+
+# If a document that does not end with a newline has one added when it is saved.
+final_newline = false
+
+# If saving a file keeps a copy of its previous contents with the `.bak` extension appended.
+backup = false
+
+# The number of milliseconds to wait for the next key of a sequence before the keys pressed so far are interpreted on their own.
+key_timeout = 1000
+
+# Each `[language.<id>]` section overrides `wrap`, `tab_width`, `expand_tabs`, `trim_trailing_whitespace` and `final_newline` for documents of that language.
+#
+# [language.rust]
+# tab_width = 4
+# trim_trailing_whitespace = true
+
+# Each `[keymap.<mode>]` section binds key sequences to actions in the "view", "confirm" or "collect" mode, replacing the default binding of the same sequence. A sequence is one or more chords separated by spaces. A chord is a key, such as "s", "S", "enter", "pagedown" or "f5", preceded by any of the modifiers "ctrl-", "alt-" and "shift-". A sequence may also be bound to a list of actions, which are performed in order. Binding a sequence to "ignore" removes its default binding. In the "view" mode, digits typed before a sequence are a count that repeats movements.
+#
+# [keymap.view]
+# "ctrl-q" = "confirm_quit"
+# "ctrl-s" = "save"
+# "alt-n" = "next_buffer"
+# "g e" = "last_line"
+# "ctrl-x" = ["save", "close_pane"]
+# S = "ignore"
+#
+# [keymap.collect]
+# "ctrl-h" = "delete_back"
+# "ctrl-a" = "cursor_start"
+# "ctrl-e" = "cursor_end"
+"#;
+
+/// Returns the directory that holds the config files of the application.
+///
+/// Follows the XDG Base Directory Specification: `$XDG_CONFIG_HOME` if it is an absolute path, otherwise `~/.config`.
+pub(crate) fn config_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| dirs::home_dir().map(|home| home.join(".config")))
+}
+
+/// Returns the path of the config file, which is `path` if given or `paper.toml` within [`config_dir`] otherwise.
+#[must_use]
+pub fn config_file(path: Option<&str>) -> Option<PathBuf> {
+    match path {
+        Some(path) => env::current_dir().ok().map(|dir| dir.join(path)),
+        None => config_dir().map(|dir| dir.join(CONFIG_FILE_NAME)),
+    }
+}
+
+/// Writes a config file with the default settings, and comments describing them, to `path`.
+///
+/// An existing file is not overwritten.
+#[throws(WriteConfigError)]
+pub fn write_default_config(path: &Path) {
+    if path.exists() {
+        throw!(WriteConfigError::Exists(path.to_path_buf()));
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|error| WriteConfigError::Write(path.to_path_buf(), error))?;
+    }
+
+    fs::write(path, DEFAULT_CONFIG)
+        .map_err(|error| WriteConfigError::Write(path.to_path_buf(), error))?;
+}
+
+/// An error writing the default config file.
+#[derive(Debug, Error)]
+pub enum WriteConfigError {
+    /// The location of the config file is unknown.
+    #[error("config directory is unknown")]
+    ConfigDir,
+    /// The config file already exists.
+    #[error("`{}` already exists", .0.display())]
+    Exists(PathBuf),
+    /// An error writing the config file.
+    #[error("unable to write `{}`: {1}", .0.display())]
+    Write(PathBuf, #[source] io::Error),
+}
+
 /// An error creating a [`SettingConsumer`].
 #[derive(Debug, Error)]
 pub enum CreateSettingConsumerError {
@@ -47,51 +190,58 @@ pub enum CreateConfigurationError {
 pub enum ConsumeSettingError {
     /// Consume.
     #[error("")]
-    Consume(
-        #[source]
-        <VigilantConsumer<
-            StrippingConsumer<StdConsumer<DebouncedEvent>, Setting>,
-            SettingDeduplicator,
-        > as Consumer>::Failure,
-    ),
+    Consume(#[source] <ConfigConsumer as Consumer>::Failure),
 }
 
+/// The consumer of the changes to the config files.
+type ConfigConsumer = VigilantConsumer<ConfigLoader, SettingDeduplicator>;
+
 /// The Change Filter.
 pub(crate) struct SettingConsumer {
-    ///// Watches for events on the config file.
-    //#[allow(dead_code)] // Must keep ownership of watcher.
-    //watcher: RecommendedWatcher,
-    ///// The consumer of settings.
-    //consumer: VigilantConsumer<
-    //    StrippingConsumer<StdConsumer<DebouncedEvent>, Setting>,
-    //    SettingDeduplicator,
-    //>,
-    /// The current [`Configuration`].
-    config: Configuration,
-    /// If the [`Configuration`] has been updated.
-    is_updated: RefCell<bool>,
+    /// The consumer of changes to the config files.
+    consumer: ConfigConsumer,
+    /// The [`Setting`]s of the initial [`Configuration`] that have not been consumed.
+    settings: RefCell<Vec<Setting>>,
+    /// If the connection with `watcher` has been lost.
+    is_disconnected: Cell<bool>,
 }
 
 impl SettingConsumer {
-    /// Creates a new [`SettingConsumer`].
+    /// Creates a new [`SettingConsumer`] of the config file at `path` and the project config file that applies to `root_dir`.
+    ///
+    /// If a file or its directory does not exist, it is ignored until it is created. If no project config file exists, the one in `root_dir` is used.
     #[throws(CreateSettingConsumerError)]
-    pub(crate) fn new(path: &PathBuf) -> Self {
-        //let (event_tx, event_rx) = mpsc::channel();
-        //let mut watcher = notify::watcher(event_tx, Duration::from_secs(0))
-        //    .map_err(CreateSettingConsumerError::CreateWatcher)?;
+    pub(crate) fn new(path: &Path, root_dir: &Path) -> Self {
+        let sources = ConfigSources {
+            user: path.to_path_buf(),
+            project: project_config_file(root_dir),
+        };
+        let config = sources.load()?;
+        let settings = config.settings();
+        let (event_tx, event_rx) = mpsc::channel();
+        let watcher = notify::watcher(event_tx, WATCH_DELAY)
+            .map_err(CreateSettingConsumerError::CreateWatcher)?;
+        let loader = ConfigLoader {
+            events: VigilantConsumer::new(
+                StdConsumer::from(event_rx),
+                EventFilter {
+                    paths: sources.files().cloned().collect(),
+                },
+            ),
+            sources,
+            watcher: RefCell::new(watcher),
+            watched_dirs: RefCell::default(),
+            parts: RefCell::default(),
+        };
 
-        //watcher
-        //    .watch(path, RecursiveMode::NonRecursive)
-        //    .map_err(CreateSettingConsumerError::BeginWatch)?;
+        loader
+            .watch_dirs()
+            .map_err(CreateSettingConsumerError::BeginWatch)?;
 
         Self {
-            //watcher,
-            //consumer: VigilantConsumer::new(
-            //    StrippingConsumer::new(StdConsumer::from(event_rx)),
-            //    SettingDeduplicator::new(path),
-            //),
-            config: Configuration::new(path)?,
-            is_updated: RefCell::new(true),
+            consumer: VigilantConsumer::new(loader, SettingDeduplicator::new(config)),
+            settings: RefCell::new(settings),
+            is_disconnected: Cell::new(false),
         }
     }
 }
@@ -104,31 +254,202 @@ impl fmt::Debug for SettingConsumer {
 }
 
 impl Consumer for SettingConsumer {
-    type Good = Setting;
+    type Good = ConfigEvent;
     type Failure = ClosedMarketFailure;
 
     #[throws(ConsumeError<Self::Failure>)]
     fn consume(&self) -> Self::Good {
-        if self.is_updated.replace(false) {
-            Setting::Wrap(self.config.wrap)
-        } else {
+        let mut settings = self.settings.borrow_mut();
+
+        if !settings.is_empty() {
+            ConfigEvent::Setting(settings.remove(0))
+        } else if self.is_disconnected.get() {
             throw!(ConsumeError::EmptyStock);
+        } else {
+            match self.consumer.consume() {
+                Ok(event) => event,
+                Err(ConsumeError::EmptyStock) => throw!(ConsumeError::EmptyStock),
+                Err(ConsumeError::Failure(_)) => {
+                    self.is_disconnected.set(true);
+                    ConfigEvent::Glitch(Glitch::WatcherConnection)
+                }
+            }
+        }
+    }
+}
+
+/// An event of the config file.
+#[derive(Debug)]
+pub enum ConfigEvent {
+    /// A setting of the config file.
+    Setting(Setting),
+    /// The config file could not be applied.
+    Glitch(Glitch),
+}
+
+/// The files that make up the [`Configuration`].
+#[derive(Debug)]
+struct ConfigSources {
+    /// The config file of the user.
+    user: PathBuf,
+    /// The config file of the project, which overrides the config file of the user.
+    project: PathBuf,
+}
+
+impl ConfigSources {
+    /// Returns the paths of the config files.
+    fn files(&self) -> impl Iterator<Item = &PathBuf> {
+        iter::once(&self.user).chain(iter::once(&self.project))
+    }
+
+    /// Returns the [`Configuration`] defined by merging the config files.
+    ///
+    /// A table within the project config file is merged into the matching table of the user config file; any other value replaces the value of the user config file.
+    #[throws(CreateConfigurationError)]
+    fn load(&self) -> Configuration {
+        let mut value = Value::Table(Table::new());
+
+        for file in self.files() {
+            match fs::read_to_string(file) {
+                Ok(text) => merge(&mut value, text.parse()?),
+                Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+                Err(error) => throw!(error),
+            }
+        }
+
+        value.try_into()?
+    }
+}
+
+/// Merges `overlay` into `base`.
+fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Table(base), Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                if let Some(base_value) = base.get_mut(&key) {
+                    merge(base_value, value);
+                } else {
+                    let _ = base.insert(key, value);
+                }
+            }
         }
+        (base, overlay) => *base = overlay,
     }
 }
 
-impl StripFrom<DebouncedEvent> for Setting {
+/// Returns the path of the project config file found by searching `root_dir` and its ancestors.
+///
+/// If no ancestor has a project config file, the path of the one in `root_dir` is returned so that it is noticed once it is created.
+fn project_config_file(root_dir: &Path) -> PathBuf {
+    root_dir
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_FILE_NAME))
+        .find(|file| file.is_file())
+        .unwrap_or_else(|| root_dir.join(PROJECT_CONFIG_FILE_NAME))
+}
+
+/// Consumes the [`ConfigEvent`]s caused by changes to the config files.
+pub struct ConfigLoader {
+    /// The events that change the config files.
+    events: VigilantConsumer<StdConsumer<DebouncedEvent>, EventFilter>,
+    /// The config files.
+    sources: ConfigSources,
+    /// Watches for events on the directories of the config files.
+    watcher: RefCell<RecommendedWatcher>,
+    /// The directories that `watcher` watches.
+    watched_dirs: RefCell<Vec<PathBuf>>,
+    /// The [`ConfigEvent`]s of the last change that have not been consumed.
+    parts: RefCell<Vec<ConfigEvent>>,
+}
+
+impl fmt::Debug for ConfigLoader {
     #[inline]
-    fn strip_from(good: &DebouncedEvent) -> Vec<Self> {
-        let mut finished_goods = Vec::new();
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ConfigLoader {{ .. }}")
+    }
+}
 
-        if let DebouncedEvent::Write(file) = good {
-            if let Ok(config) = Configuration::new(file) {
-                finished_goods.push(Self::Wrap(config.wrap));
+impl ConfigLoader {
+    /// Watches the nearest existing directory of each config file.
+    ///
+    /// Directories are watched rather than files so that the creation of a file is noticed and editors that save by replacing a file do not end the watch. A directory that does not exist yet is noticed through its nearest existing ancestor, which is replaced by the directory once it is created.
+    #[throws(notify::Error)]
+    fn watch_dirs(&self) {
+        let mut watched_dirs = self.watched_dirs.borrow_mut();
+
+        for file in self.sources.files() {
+            if let Some(dir) = file.ancestors().skip(1).find(|dir| dir.is_dir()) {
+                if !watched_dirs.iter().any(|watched| watched == dir) {
+                    self.watcher
+                        .borrow_mut()
+                        .watch(dir, RecursiveMode::NonRecursive)?;
+                    watched_dirs.push(dir.to_path_buf());
+                }
             }
         }
+    }
+}
 
-        finished_goods
+impl Consumer for ConfigLoader {
+    type Good = ConfigEvent;
+    type Failure = ClosedMarketFailure;
+
+    #[throws(ConsumeError<Self::Failure>)]
+    fn consume(&self) -> Self::Good {
+        let mut parts = self.parts.borrow_mut();
+
+        if parts.is_empty() {
+            let _ = self.events.consume()?;
+
+            if let Err(error) = self.watch_dirs() {
+                warn!(
+                    "Unable to watch the directories of the config files: {}",
+                    error
+                );
+            }
+
+            *parts = match self.sources.load() {
+                Ok(config) => config
+                    .settings()
+                    .into_iter()
+                    .map(ConfigEvent::Setting)
+                    .collect(),
+                Err(CreateConfigurationError::ReadFile(error)) => {
+                    vec![ConfigEvent::Glitch(Glitch::ReadConfig(error))]
+                }
+                Err(CreateConfigurationError::Deserialize(error)) => {
+                    vec![ConfigEvent::Glitch(Glitch::ConfigFormat(error))]
+                }
+            };
+            // Reversed so that popping consumes the settings in order.
+            parts.reverse();
+        }
+
+        parts.pop().ok_or(ConsumeError::EmptyStock)?
+    }
+}
+
+/// Filters the events that change the config files or create their directories.
+#[derive(Debug)]
+pub struct EventFilter {
+    /// The paths of the config files.
+    paths: Vec<PathBuf>,
+}
+
+impl Inspector for EventFilter {
+    type Good = DebouncedEvent;
+
+    #[inline]
+    fn allows(&self, good: &Self::Good) -> bool {
+        match good {
+            DebouncedEvent::Write(file)
+            | DebouncedEvent::Create(file)
+            | DebouncedEvent::Remove(file)
+            | DebouncedEvent::Rename(_, file) => {
+                self.paths.iter().any(|path| path.starts_with(file))
+            }
+            _ => false,
+        }
     }
 }
 
@@ -136,61 +457,500 @@ impl StripFrom<DebouncedEvent> for Setting {
 #[derive(Debug)]
 pub struct SettingDeduplicator {
     /// The current configuration.
-    config: Cell<Configuration>,
+    config: RefCell<Configuration>,
 }
 
-//impl SettingDeduplicator {
-//    /// Creates a new [`SettingDeduplicator`].
-//    fn new(path: &PathBuf) -> Self {
-//        Self {
-//            config: Cell::new(Configuration::new(path).unwrap_or_default()),
-//        }
-//    }
-//}
+impl SettingDeduplicator {
+    /// Creates a new [`SettingDeduplicator`] of `config`.
+    const fn new(config: Configuration) -> Self {
+        Self {
+            config: RefCell::new(config),
+        }
+    }
+}
 
 impl Inspector for SettingDeduplicator {
-    type Good = Setting;
+    type Good = ConfigEvent;
 
     #[inline]
     fn allows(&self, good: &Self::Good) -> bool {
-        trace!("Inspecting setting `{}`", good);
-        let config = self.config.get();
-        let mut new_config = config;
-        let result;
-
         match good {
-            Self::Good::Wrap(wrap) => {
-                result = *wrap == config.wrap;
-                new_config.wrap = *wrap;
+            Self::Good::Setting(setting) => {
+                trace!("Inspecting setting `{}`", setting);
+                self.config.borrow_mut().apply(setting.clone())
             }
+            Self::Good::Glitch(_) => true,
         }
-
-        self.config.set(new_config);
-        result
     }
 }
 
 /// The configuration of the application.
-#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct Configuration {
-    /// If documents shall wrap.
-    #[serde(default)]
-    pub(crate) wrap: bool,
+    /// How documents wrap lines that are longer than the page.
+    pub(crate) wrap: Wrap,
+    /// The glyph that begins each row that continues a wrapped line.
+    pub(crate) wrap_glyph: char,
+    /// The number of columns between tab stops.
+    pub(crate) tab_width: usize,
+    /// If tabs within the indentation of each line are replaced by spaces when a document is saved.
+    pub(crate) expand_tabs: bool,
+    /// How line numbers are displayed in the gutter.
+    pub(crate) line_numbers: LineNumbers,
+    /// The number of columns reserved in the gutter for signs.
+    pub(crate) sign_columns: usize,
+    /// The number of lines kept visible above and below the cursor.
+    pub(crate) scroll_off: usize,
+    /// The path of the theme file, relative to the config directory.
+    pub(crate) theme: String,
+    /// If a document with unsaved changes is saved when a pane stops displaying it.
+    pub(crate) auto_save: bool,
+    /// If whitespace at the end of each line is removed when a document is saved.
+    pub(crate) trim_trailing_whitespace: bool,
+    /// If a document that does not end with a newline has one added when it is saved.
+    pub(crate) final_newline: bool,
+    /// If saving a file keeps a copy of its previous contents with the `.bak` extension appended.
+    pub(crate) backup: bool,
+    /// The number of milliseconds to wait for the next key of a sequence.
+    pub(crate) key_timeout: u64,
+    /// The settings that override the global settings for documents of each language.
+    #[serde(deserialize_with = "deserialize_languages")]
+    pub(crate) language: HashMap<LanguageId, Overrides>,
+    /// The key bindings that are added to the default key bindings.
+    pub(crate) keymap: Keymap,
 }
 
 impl Configuration {
-    /// Creates a new [`Configuration`].
-    #[throws(CreateConfigurationError)]
-    fn new(file: &PathBuf) -> Self {
-        toml::from_str(&fs::read_to_string(file)?)?
+    /// Returns the [`Setting`]s that make up `self`.
+    ///
+    /// Every language is included so that removing the overrides of a language is a change of its setting.
+    fn settings(&self) -> Vec<Setting> {
+        let mut settings = vec![
+            Setting::Wrap(self.wrap),
+            Setting::WrapGlyph(self.wrap_glyph),
+            Setting::TabWidth(self.tab_width),
+            Setting::ExpandTabs(self.expand_tabs),
+            Setting::LineNumbers(self.line_numbers),
+            Setting::SignColumns(self.sign_columns),
+            Setting::ScrollOff(self.scroll_off),
+            Setting::Theme(self.theme.clone()),
+            Setting::AutoSave(self.auto_save),
+            Setting::TrimTrailingWhitespace(self.trim_trailing_whitespace),
+            Setting::FinalNewline(self.final_newline),
+            Setting::Backup(self.backup),
+            Setting::KeyTimeout(self.key_timeout),
+            Setting::Keymap(self.keymap.clone()),
+        ];
+
+        settings.extend(
+            EnumMap::<LanguageId, ()>::default()
+                .iter()
+                .map(|(language_id, _)| {
+                    Setting::Language(
+                        language_id,
+                        self.language.get(&language_id).copied().unwrap_or_default(),
+                    )
+                }),
+        );
+        settings
+    }
+
+    /// Updates `self` according to `setting`, returning if `self` changed.
+    pub(crate) fn apply(&mut self, setting: Setting) -> bool {
+        let old = self.clone();
+
+        match setting {
+            Setting::Wrap(wrap) => self.wrap = wrap,
+            Setting::WrapGlyph(glyph) => self.wrap_glyph = glyph,
+            Setting::TabWidth(tab_width) => self.tab_width = tab_width,
+            Setting::ExpandTabs(expand_tabs) => self.expand_tabs = expand_tabs,
+            Setting::LineNumbers(line_numbers) => self.line_numbers = line_numbers,
+            Setting::SignColumns(sign_columns) => self.sign_columns = sign_columns,
+            Setting::ScrollOff(scroll_off) => self.scroll_off = scroll_off,
+            Setting::Theme(theme) => self.theme = theme,
+            Setting::AutoSave(auto_save) => self.auto_save = auto_save,
+            Setting::TrimTrailingWhitespace(trim) => self.trim_trailing_whitespace = trim,
+            Setting::FinalNewline(final_newline) => self.final_newline = final_newline,
+            Setting::Backup(backup) => self.backup = backup,
+            Setting::KeyTimeout(key_timeout) => self.key_timeout = key_timeout,
+            Setting::Keymap(keymap) => self.keymap = keymap,
+            Setting::Language(language_id, overrides) => {
+                if overrides == Overrides::default() {
+                    let _ = self.language.remove(&language_id);
+                } else {
+                    let _ = self.language.insert(language_id, overrides);
+                }
+            }
+        }
+
+        *self != old
+    }
+
+    /// Returns the [`DocSettings`] of a document of `file`.
+    ///
+    /// The overrides of `file`, such as those from EditorConfig files, take precedence over the overrides of its language, which take precedence over the global settings.
+    pub(crate) fn doc_settings(&self, file: &File) -> DocSettings {
+        let overrides = file.overrides().or(file
+            .language_id()
+            .and_then(|id| self.language.get(&id).copied())
+            .unwrap_or_default());
+
+        DocSettings {
+            wrap: overrides.wrap.unwrap_or(self.wrap),
+            tab_width: overrides.tab_width.unwrap_or(self.tab_width),
+            expand_tabs: overrides.expand_tabs.unwrap_or(self.expand_tabs),
+            trim_trailing_whitespace: overrides
+                .trim_trailing_whitespace
+                .unwrap_or(self.trim_trailing_whitespace),
+            final_newline: overrides.final_newline.unwrap_or(self.final_newline),
+            end_of_line: overrides.end_of_line,
+            charset: overrides.charset,
+        }
     }
 }
 
-/// Signifies a configuration.
+impl Default for Configuration {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            wrap: Wrap::None,
+            wrap_glyph: '↪',
+            tab_width: 4,
+            expand_tabs: false,
+            line_numbers: LineNumbers::None,
+            sign_columns: 0,
+            scroll_off: 0,
+            theme: "paper-theme.toml".to_string(),
+            auto_save: false,
+            trim_trailing_whitespace: false,
+            final_newline: false,
+            backup: false,
+            key_timeout: 1000,
+            language: HashMap::new(),
+            keymap: Keymap::default(),
+        }
+    }
+}
+
+/// Deserializes the `[language.<id>]` sections of a config file.
+///
+/// The keys of a TOML table are only able to be deserialized as strings, so each key is converted to a [`LanguageId`] separately.
+fn deserialize_languages<'de, D>(
+    deserializer: D,
+) -> Result<HashMap<LanguageId, Overrides>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    HashMap::<String, Overrides>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(name, overrides)| {
+            LanguageId::deserialize(name.as_str().into_deserializer())
+                .map(|language_id| (language_id, overrides))
+                .map_err(|error: value::Error| D::Error::custom(error))
+        })
+        .collect()
+}
+
+/// The settings that override the global settings for some documents, such as those within a `[language.<id>]` section of the config file.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(default)]
+pub struct Overrides {
+    /// Overrides [`Configuration::wrap`].
+    pub(crate) wrap: Option<Wrap>,
+    /// Overrides [`Configuration::tab_width`].
+    pub(crate) tab_width: Option<usize>,
+    /// Overrides [`Configuration::expand_tabs`].
+    pub(crate) expand_tabs: Option<bool>,
+    /// Overrides [`Configuration::trim_trailing_whitespace`].
+    pub(crate) trim_trailing_whitespace: Option<bool>,
+    /// Overrides [`Configuration::final_newline`].
+    pub(crate) final_newline: Option<bool>,
+    /// The line ending of the document.
+    pub(crate) end_of_line: Option<LineEnding>,
+    /// The character encoding of the document.
+    pub(crate) charset: Option<Charset>,
+}
+
+impl Overrides {
+    /// Returns the overrides of `self`, falling back to those of `fallback` for settings that `self` does not override.
+    pub(crate) fn or(self, fallback: Self) -> Self {
+        Self {
+            wrap: self.wrap.or(fallback.wrap),
+            tab_width: self.tab_width.or(fallback.tab_width),
+            expand_tabs: self.expand_tabs.or(fallback.expand_tabs),
+            trim_trailing_whitespace: self
+                .trim_trailing_whitespace
+                .or(fallback.trim_trailing_whitespace),
+            final_newline: self.final_newline.or(fallback.final_newline),
+            end_of_line: self.end_of_line.or(fallback.end_of_line),
+            charset: self.charset.or(fallback.charset),
+        }
+    }
+}
+
+/// The sequence that ends a line.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEnding {
+    /// A line feed.
+    Lf,
+    /// A carriage return followed by a line feed.
+    Crlf,
+    /// A carriage return.
+    Cr,
+}
+
+impl LineEnding {
+    /// Returns the text of `self`.
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::Crlf => "\r\n",
+            Self::Cr => "\r",
+        }
+    }
+}
+
+/// A character encoding.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, ParseDisplay, PartialEq)]
+pub enum Charset {
+    /// ISO-8859-1.
+    #[serde(rename = "latin1")]
+    #[display("latin1")]
+    Latin1,
+    /// UTF-8 without a byte order mark.
+    #[serde(rename = "utf-8")]
+    #[display("utf-8")]
+    Utf8,
+    /// UTF-8 with a byte order mark.
+    #[serde(rename = "utf-8-bom")]
+    #[display("utf-8-bom")]
+    Utf8Bom,
+    /// Big endian UTF-16.
+    #[serde(rename = "utf-16be")]
+    #[display("utf-16be")]
+    Utf16Be,
+    /// Little endian UTF-16.
+    #[serde(rename = "utf-16le")]
+    #[display("utf-16le")]
+    Utf16Le,
+}
+
+impl Charset {
+    /// Returns if text is able to be written in `self`.
+    ///
+    /// Text is always written as UTF-8, so no other encoding is supported.
+    pub(crate) const fn is_writable(self) -> bool {
+        matches!(self, Self::Utf8 | Self::Utf8Bom)
+    }
+}
+
+/// The settings that apply to a single document, after applying its overrides.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct DocSettings {
+    /// How lines that are longer than the page are wrapped.
+    pub(crate) wrap: Wrap,
+    /// The number of columns between tab stops.
+    pub(crate) tab_width: usize,
+    /// If tabs within the indentation of each line are replaced by spaces, up to the next tab stop, when the document is saved.
+    pub(crate) expand_tabs: bool,
+    /// If whitespace at the end of each line is removed when the document is saved.
+    pub(crate) trim_trailing_whitespace: bool,
+    /// If a newline is added to the end of the document when it is saved.
+    pub(crate) final_newline: bool,
+    /// The line ending that every line is given when the document is saved.
+    ///
+    /// [`None`] indicates that line endings are kept as they are.
+    pub(crate) end_of_line: Option<LineEnding>,
+    /// The character encoding of the document.
+    ///
+    /// Only the byte order mark of UTF-8 is applied; a document whose charset is not [writable](Charset::is_writable) is not saved.
+    pub(crate) charset: Option<Charset>,
+}
+
+impl DocSettings {
+    /// Returns `text` as it is written when saving a document with `self`.
+    pub(crate) fn format(&self, text: &str) -> String {
+        // Line endings are converted to line feeds first so that each line is able to be trimmed.
+        let text = if self.end_of_line.is_some() {
+            text.replace("\r\n", "\n").replace('\r', "\n")
+        } else {
+            text.to_string()
+        };
+        let text = if self.expand_tabs {
+            text.split('\n')
+                .map(|line| expand_indentation(line, self.tab_width))
+                .collect::<Vec<String>>()
+                .join("\n")
+        } else {
+            text
+        };
+        let mut formatted = if self.trim_trailing_whitespace {
+            text.split('\n')
+                .map(|line| {
+                    let content = line.strip_suffix('\r').unwrap_or(line);
+                    let trimmed = content.trim_end_matches([' ', '\t']);
+
+                    if content.len() == line.len() {
+                        trimmed.to_string()
+                    } else {
+                        format!("{}\r", trimmed)
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join("\n")
+        } else {
+            text
+        };
+
+        if self.final_newline && !formatted.is_empty() && !formatted.ends_with(['\n', '\r']) {
+            formatted.push_str(if formatted.contains("\r\n") {
+                "\r\n"
+            } else {
+                "\n"
+            });
+        }
+
+        if let Some(end_of_line) = self.end_of_line.filter(|ending| *ending != LineEnding::Lf) {
+            formatted = formatted.replace('\n', end_of_line.as_str());
+        }
+
+        match self.charset {
+            Some(Charset::Utf8Bom) if !formatted.starts_with(BOM) => formatted.insert(0, BOM),
+            Some(Charset::Utf8) => {
+                if let Some(text) = formatted.strip_prefix(BOM) {
+                    formatted = text.to_string();
+                }
+            }
+            Some(Charset::Utf8Bom | Charset::Latin1 | Charset::Utf16Be | Charset::Utf16Le)
+            | None => {}
+        }
+
+        formatted
+    }
+}
+
+/// Returns `line` with each tab within its indentation replaced by the spaces up to the next tab stop, where tab stops are `tab_width` columns apart.
+fn expand_indentation(line: &str, tab_width: usize) -> String {
+    let content = line.trim_start_matches([' ', '\t']);
+    let indentation = line
+        .get(..line.len().saturating_sub(content.len()))
+        .unwrap_or_default();
+    let tab_width = tab_width.max(1);
+    let mut expanded = String::with_capacity(line.len());
+
+    for c in indentation.chars() {
+        if c == '\t' {
+            let column = expanded.len();
+
+            expanded.push_str(&" ".repeat(tab_width.saturating_sub(column % tab_width)));
+        } else {
+            expanded.push(c);
+        }
+    }
+
+    expanded.push_str(content);
+    expanded
+}
+
+/// The names of the settings that are able to be changed by [`Setting::parse`].
+pub(crate) const SETTING_NAMES: [&str; 13] = [
+    "wrap",
+    "wrap_glyph",
+    "tab_width",
+    "expand_tabs",
+    "line_numbers",
+    "sign_columns",
+    "scroll_off",
+    "theme",
+    "auto_save",
+    "trim_trailing_whitespace",
+    "final_newline",
+    "backup",
+    "key_timeout",
+];
+
+/// Signifies a configuration.
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Setting {
-    /// If the document shall wrap long text.
-    Wrap(bool),
+    /// How the document shall wrap long lines.
+    Wrap(Wrap),
+    /// The glyph that begins each row that continues a wrapped line.
+    WrapGlyph(char),
+    /// The number of columns between tab stops.
+    TabWidth(usize),
+    /// If tabs within indentation are replaced by spaces when saving.
+    ExpandTabs(bool),
+    /// How line numbers are displayed.
+    LineNumbers(LineNumbers),
+    /// The number of columns reserved for signs.
+    SignColumns(usize),
+    /// The number of lines kept visible around the cursor.
+    ScrollOff(usize),
+    /// The path of the theme file.
+    Theme(String),
+    /// If documents are saved when they are no longer displayed.
+    AutoSave(bool),
+    /// If trailing whitespace is removed on save.
+    TrimTrailingWhitespace(bool),
+    /// If a final newline is added on save.
+    FinalNewline(bool),
+    /// If saving a file keeps a backup.
+    Backup(bool),
+    /// The milliseconds to wait for the next key of a sequence.
+    KeyTimeout(u64),
+    /// The overrides of a language.
+    Language(LanguageId, Overrides),
+    /// The key bindings added to the defaults.
+    Keymap(Keymap),
+}
+
+impl Setting {
+    /// Returns the setting named `name` with `value`, which is written as within a config file except that the quotes around a string are optional.
+    ///
+    /// If `name` is not within [`SETTING_NAMES`] or `value` is invalid, a message that explains why is returned.
+    pub(crate) fn parse(name: &str, value: &str) -> Result<Self, String> {
+        if !SETTING_NAMES.contains(&name) {
+            return Err(format!("unknown setting `{}`", name));
+        }
+
+        // A value that is not valid TOML is a string without quotes.
+        let toml_value = if toml::from_str::<Table>(&format!("value = {}", value)).is_ok() {
+            value.to_string()
+        } else {
+            Value::String(value.to_string()).to_string()
+        };
+        let config = toml::from_str::<Configuration>(&format!("{} = {}", name, toml_value))
+            .map_err(|error| format!("invalid value `{}` for `{}`: {}", value, name, error))?;
+
+        config
+            .settings()
+            .into_iter()
+            .find(|setting| setting.name() == name)
+            .ok_or_else(|| format!("unknown setting `{}`", name))
+    }
+
+    /// Returns the name of `self` within a config file.
+    const fn name(&self) -> &'static str {
+        match self {
+            Self::Wrap(..) => "wrap",
+            Self::WrapGlyph(..) => "wrap_glyph",
+            Self::TabWidth(..) => "tab_width",
+            Self::ExpandTabs(..) => "expand_tabs",
+            Self::LineNumbers(..) => "line_numbers",
+            Self::SignColumns(..) => "sign_columns",
+            Self::ScrollOff(..) => "scroll_off",
+            Self::Theme(..) => "theme",
+            Self::AutoSave(..) => "auto_save",
+            Self::TrimTrailingWhitespace(..) => "trim_trailing_whitespace",
+            Self::FinalNewline(..) => "final_newline",
+            Self::Backup(..) => "backup",
+            Self::KeyTimeout(..) => "key_timeout",
+            Self::Language(..) => "language",
+            Self::Keymap(..) => "keymap",
+        }
+    }
 }
 
 impl Display for Setting {
@@ -198,6 +958,282 @@ impl Display for Setting {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Wrap(value) => write!(f, "Setting::Wrap({})", value),
+            Self::WrapGlyph(value) => write!(f, "Setting::WrapGlyph({})", value),
+            Self::TabWidth(value) => write!(f, "Setting::TabWidth({})", value),
+            Self::ExpandTabs(value) => write!(f, "Setting::ExpandTabs({})", value),
+            Self::LineNumbers(value) => write!(f, "Setting::LineNumbers({})", value),
+            Self::SignColumns(value) => write!(f, "Setting::SignColumns({})", value),
+            Self::ScrollOff(value) => write!(f, "Setting::ScrollOff({})", value),
+            Self::Theme(value) => write!(f, "Setting::Theme({})", value),
+            Self::AutoSave(value) => write!(f, "Setting::AutoSave({})", value),
+            Self::TrimTrailingWhitespace(value) => {
+                write!(f, "Setting::TrimTrailingWhitespace({})", value)
+            }
+            Self::FinalNewline(value) => write!(f, "Setting::FinalNewline({})", value),
+            Self::Backup(value) => write!(f, "Setting::Backup({})", value),
+            Self::KeyTimeout(value) => write!(f, "Setting::KeyTimeout({})", value),
+            Self::Language(language_id, value) => {
+                write!(f, "Setting::Language({}, {:?})", language_id, value)
+            }
+            Self::Keymap(value) => write!(f, "Setting::Keymap({:?})", value),
         }
     }
 }
+
+/// How lines that are longer than the page are displayed.
+///
+/// Within a config file, `wrap` may be `"none"`, `"char"`, `"word"` or a column number. For compatibility, `true` and `false` are equivalent to `"char"` and `"none"`.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(try_from = "RawWrap")]
+pub enum Wrap {
+    /// Lines are not wrapped.
+    None,
+    /// Lines are wrapped at the last character that fits on the page.
+    Char,
+    /// Lines are wrapped between words.
+    Word,
+    /// Lines are wrapped between words at the given column or the width of the page, whichever is less.
+    Column(usize),
+}
+
+impl Display for Wrap {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Char => write!(f, "char"),
+            Self::Word => write!(f, "word"),
+            Self::Column(column) => write!(f, "{}", column),
+        }
+    }
+}
+
+/// The representation of a [`Wrap`] within a config file.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawWrap {
+    /// If lines are wrapped at characters.
+    Enabled(bool),
+    /// The column at which lines are wrapped.
+    Column(usize),
+    /// The name of the wrap mode.
+    Mode(String),
+}
+
+impl TryFrom<RawWrap> for Wrap {
+    type Error = String;
+
+    #[inline]
+    fn try_from(value: RawWrap) -> Result<Self, Self::Error> {
+        match value {
+            RawWrap::Enabled(true) => Ok(Self::Char),
+            RawWrap::Enabled(false) => Ok(Self::None),
+            RawWrap::Column(0) => Err("wrap column must be greater than 0".to_string()),
+            RawWrap::Column(column) => Ok(Self::Column(column)),
+            RawWrap::Mode(mode) => match mode.as_str() {
+                "none" => Ok(Self::None),
+                "char" => Ok(Self::Char),
+                "word" => Ok(Self::Word),
+                _ => Err(format!("unknown wrap mode `{}`", mode)),
+            },
+        }
+    }
+}
+
+/// How line numbers are displayed in the gutter.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, ParseDisplay, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[display(style = "snake_case")]
+pub enum LineNumbers {
+    /// Line numbers are not displayed.
+    None,
+    /// The number of each line is displayed.
+    Absolute,
+    /// The distance of each line from the line of the cursor is displayed.
+    Relative,
+    /// The distance of each line from the line of the cursor is displayed, except the line of the cursor displays its number.
+    Hybrid,
+}
+
+/// Testing of the config module.
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// The wrap mode shall be parsed from a name, a column or a boolean.
+    #[test]
+    fn wrap() {
+        let wrap = |text: &str| toml::from_str::<Configuration>(text).map(|config| config.wrap);
+
+        assert_eq!(wrap("").unwrap(), Wrap::None);
+        assert_eq!(wrap("wrap = \"word\"").unwrap(), Wrap::Word);
+        assert_eq!(wrap("wrap = 80").unwrap(), Wrap::Column(80));
+        assert_eq!(wrap("wrap = true").unwrap(), Wrap::Char);
+        assert!(wrap("wrap = \"line\"").is_err());
+        assert!(wrap("wrap = 0").is_err());
+    }
+
+    /// Only settings that differ from the current configuration shall be allowed.
+    #[test]
+    fn deduplicate() {
+        let deduplicator = SettingDeduplicator::new(Configuration::default());
+        let allows = |setting| deduplicator.allows(&ConfigEvent::Setting(setting));
+
+        assert!(!allows(Setting::TabWidth(4)));
+        assert!(allows(Setting::TabWidth(8)));
+        assert!(!allows(Setting::TabWidth(8)));
+        assert!(allows(Setting::TabWidth(4)));
+        assert!(deduplicator.allows(&ConfigEvent::Glitch(Glitch::WatcherConnection)));
+    }
+
+    /// The default config file shall match the default configuration.
+    #[test]
+    fn default_config() {
+        assert_eq!(
+            toml::from_str::<Configuration>(DEFAULT_CONFIG).unwrap(),
+            Configuration::default()
+        );
+    }
+
+    /// The settings of a language section shall override the global settings for documents of that language and the overrides of a file shall override both.
+    #[test]
+    fn language() {
+        let config: Configuration =
+            toml::from_str("tab_width = 8\n[language.rust]\ntab_width = 2\nfinal_newline = true")
+                .unwrap();
+        let rust = config.doc_settings(&File::fixture("a.rs", ""));
+
+        assert_eq!(rust.tab_width, 2);
+        assert!(rust.final_newline);
+        assert_eq!(
+            config.doc_settings(&File::fixture("a.txt", "")).tab_width,
+            8
+        );
+        assert!(
+            !config
+                .doc_settings(&File::fixture("a.txt", ""))
+                .final_newline
+        );
+
+        let editorconfig = File::fixture("a.rs", "").with_overrides(Overrides {
+            tab_width: Some(3),
+            ..Overrides::default()
+        });
+
+        assert_eq!(config.doc_settings(&editorconfig).tab_width, 3);
+        assert!(config.doc_settings(&editorconfig).final_newline);
+
+        let mut reverted = config.clone();
+
+        assert!(reverted.apply(Setting::Language(LanguageId::Rust, Overrides::default())));
+        assert_eq!(
+            reverted.doc_settings(&File::fixture("a.rs", "")).tab_width,
+            8
+        );
+    }
+
+    /// Saving shall trim trailing whitespace, add a final newline, convert line endings and expand indentation as configured.
+    #[test]
+    fn format() {
+        let mut settings = DocSettings {
+            wrap: Wrap::None,
+            tab_width: 4,
+            expand_tabs: true,
+            trim_trailing_whitespace: true,
+            final_newline: true,
+            end_of_line: None,
+            charset: None,
+        };
+
+        assert_eq!(settings.format("a  \r\nb\t\r\nc "), "a\r\nb\r\nc\r\n");
+        assert_eq!(settings.format(""), "");
+
+        settings.end_of_line = Some(LineEnding::Lf);
+        settings.charset = Some(Charset::Utf8Bom);
+        assert_eq!(settings.format("a \r\nb\rc"), "\u{feff}a\nb\nc\n");
+
+        settings.charset = None;
+        assert_eq!(settings.format("\tif\n  \tx\ty"), "    if\n    x\ty\n");
+        settings.expand_tabs = false;
+        assert_eq!(settings.format("\tif"), "\tif\n");
+    }
+
+    /// A config file shall be noticed when it or a missing directory that contains it is created, and the project config file shall default to the one in the root directory.
+    #[test]
+    fn watched_paths() {
+        let root = env::temp_dir().join(format!("paper-project-{}", std::process::id()));
+        let project = root.join(PROJECT_CONFIG_FILE_NAME);
+        let filter = EventFilter {
+            paths: vec![
+                PathBuf::from("/home/.config/paper/paper.toml"),
+                project.clone(),
+            ],
+        };
+
+        fs::create_dir_all(&root).unwrap();
+        assert_eq!(project_config_file(&root), project);
+        assert!(filter.allows(&DebouncedEvent::Create(PathBuf::from(
+            "/home/.config/paper"
+        ))));
+        assert!(filter.allows(&DebouncedEvent::Create(project.clone())));
+        assert!(!filter.allows(&DebouncedEvent::Create(root.join("src"))));
+        assert!(!filter.allows(&DebouncedEvent::Write(PathBuf::from(
+            "/home/.config/other.toml"
+        ))));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    /// Project config files shall be merged into the config file of the user.
+    #[test]
+    fn merge_tables() {
+        let mut base: Value = "tab_width = 8\n[language.rust]\ntab_width = 2"
+            .parse()
+            .unwrap();
+
+        merge(
+            &mut base,
+            "wrap = \"word\"\n[language.rust]\nfinal_newline = true"
+                .parse()
+                .unwrap(),
+        );
+
+        let config: Configuration = base.try_into().unwrap();
+        let rust = config.doc_settings(&File::fixture("a.rs", ""));
+
+        assert_eq!(config.tab_width, 8);
+        assert_eq!(config.wrap, Wrap::Word);
+        assert_eq!(rust.tab_width, 2);
+        assert!(rust.final_newline);
+    }
+
+    /// Line numbers shall be parsed from their name.
+    #[test]
+    fn line_numbers() {
+        let config: Configuration =
+            toml::from_str("line_numbers = \"hybrid\"\nsign_columns = 2").unwrap();
+
+        assert_eq!(config.line_numbers, LineNumbers::Hybrid);
+        assert_eq!(config.sign_columns, 2);
+    }
+
+    /// A setting shall be parsed from its name and a value whose quotes are optional.
+    #[test]
+    fn parse_setting() {
+        assert_eq!(
+            Setting::parse("wrap", "word"),
+            Ok(Setting::Wrap(Wrap::Word))
+        );
+        assert_eq!(Setting::parse("wrap", "\"80\""), Err("invalid value `\"80\"` for `wrap`: unknown wrap mode `80` for key `wrap` at line 1 column 1".to_string()));
+        assert_eq!(Setting::parse("tab_width", "2"), Ok(Setting::TabWidth(2)));
+        assert_eq!(
+            Setting::parse("theme", "dark.toml"),
+            Ok(Setting::Theme("dark.toml".to_string()))
+        );
+        assert!(Setting::parse("expand_tabs", "maybe").is_err());
+        assert_eq!(
+            Setting::parse("keymap", "{}"),
+            Err("unknown setting `keymap`".to_string())
+        );
+    }
+}
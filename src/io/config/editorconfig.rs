@@ -0,0 +1,374 @@
+//! Implements support for [EditorConfig] files.
+//!
+//! [EditorConfig]: https://editorconfig.org
+use {
+    super::{Charset, LineEnding, Overrides},
+    log::warn,
+    std::{
+        collections::HashMap,
+        fs, io,
+        path::{Path, PathBuf},
+    },
+};
+
+/// The name of an EditorConfig file.
+const FILE_NAME: &str = ".editorconfig";
+
+/// Returns the [`Overrides`] that the EditorConfig files define for the file at `path`.
+///
+/// The EditorConfig files in the directory of `path` and each of its ancestors are applied, stopping at a file that declares `root = true`. Files closer to `path` take precedence.
+pub(crate) fn overrides(path: &Path) -> Overrides {
+    let mut configs = Vec::new();
+
+    for dir in path.ancestors().skip(1) {
+        let file = dir.join(FILE_NAME);
+
+        match fs::read_to_string(&file) {
+            Ok(text) => {
+                let config = EditorConfig::parse(dir.to_path_buf(), &text);
+                let is_root = config.is_root;
+
+                configs.push(config);
+
+                if is_root {
+                    break;
+                }
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => warn!("Unable to read `{}`: {}", file.display(), error),
+        }
+    }
+
+    let mut properties = HashMap::new();
+
+    for config in configs.iter().rev() {
+        config.apply(path, &mut properties);
+    }
+
+    overrides_of(&properties)
+}
+
+/// Returns the [`Overrides`] defined by `properties`.
+fn overrides_of(properties: &HashMap<String, String>) -> Overrides {
+    let property = |name: &str| properties.get(name).map(String::as_str);
+    let flag = |name: &str| match property(name) {
+        Some("true") => Some(true),
+        Some("false") => Some(false),
+        _ => None,
+    };
+
+    Overrides {
+        wrap: None,
+        tab_width: property("tab_width")
+            .or_else(|| property("indent_size"))
+            .and_then(|width| width.parse().ok())
+            .filter(|width| *width > 0),
+        expand_tabs: match property("indent_style") {
+            Some("space") => Some(true),
+            Some("tab") => Some(false),
+            _ => None,
+        },
+        trim_trailing_whitespace: flag("trim_trailing_whitespace"),
+        final_newline: flag("insert_final_newline"),
+        end_of_line: match property("end_of_line") {
+            Some("lf") => Some(LineEnding::Lf),
+            Some("crlf") => Some(LineEnding::Crlf),
+            Some("cr") => Some(LineEnding::Cr),
+            _ => None,
+        },
+        charset: match property("charset") {
+            Some("latin1") => Some(Charset::Latin1),
+            Some("utf-8") => Some(Charset::Utf8),
+            Some("utf-8-bom") => Some(Charset::Utf8Bom),
+            Some("utf-16be") => Some(Charset::Utf16Be),
+            Some("utf-16le") => Some(Charset::Utf16Le),
+            _ => None,
+        },
+    }
+}
+
+/// The contents of an EditorConfig file.
+#[derive(Debug)]
+struct EditorConfig {
+    /// The directory of the file.
+    dir: PathBuf,
+    /// If the file stops the search for EditorConfig files.
+    is_root: bool,
+    /// The sections of the file, in order.
+    sections: Vec<Section>,
+}
+
+impl EditorConfig {
+    /// Parses `text` as the EditorConfig file in `dir`.
+    ///
+    /// Lines that are not understood are ignored.
+    fn parse(dir: PathBuf, text: &str) -> Self {
+        let mut is_root = false;
+        let mut sections: Vec<Section> = Vec::new();
+
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            if let Some(glob) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                sections.push(Section {
+                    glob: glob.to_string(),
+                    properties: Vec::new(),
+                });
+            } else if let Some((key, value)) = line.split_once('=') {
+                let key = key.trim().to_lowercase();
+                let value = value.trim().to_lowercase();
+
+                if let Some(section) = sections.last_mut() {
+                    section.properties.push((key, value));
+                } else if key == "root" {
+                    is_root = value == "true";
+                }
+            }
+        }
+
+        Self {
+            dir,
+            is_root,
+            sections,
+        }
+    }
+
+    /// Sets the properties of each section of `self` that matches `path` in `properties`.
+    ///
+    /// A property with the value `unset` is removed.
+    fn apply(&self, path: &Path, properties: &mut HashMap<String, String>) {
+        let relative = match path.strip_prefix(&self.dir) {
+            Ok(relative) => relative.to_string_lossy().replace('\\', "/"),
+            Err(_) => return,
+        };
+        let name = path
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+
+        for section in &self.sections {
+            // A glob without a slash matches a file of that name in any directory.
+            let is_match = if section.glob.contains('/') {
+                matches(section.glob.trim_start_matches('/'), &relative)
+            } else {
+                matches(&section.glob, &name)
+            };
+
+            if is_match {
+                for (key, value) in &section.properties {
+                    if value == "unset" {
+                        let _ = properties.remove(key);
+                    } else {
+                        let _ = properties.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A section of an EditorConfig file.
+#[derive(Debug)]
+struct Section {
+    /// The glob that selects the files to which the section applies.
+    glob: String,
+    /// The properties of the section, in order.
+    properties: Vec<(String, String)>,
+}
+
+/// Returns if `text` matches the EditorConfig glob `glob`.
+fn matches(glob: &str, text: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    match_chars(&glob, &text)
+}
+
+/// Returns if `text` matches `glob`.
+///
+/// Supports `*`, `**`, `?`, `[...]`, `[!...]`, `{a,b}`, `{n..m}` and escaping with `\`.
+fn match_chars(glob: &[char], text: &[char]) -> bool {
+    match glob {
+        [] => text.is_empty(),
+        ['*', '*', rest @ ..] => (0..=text.len()).any(|start| {
+            text.get(start..)
+                .is_some_and(|remaining| match_chars(rest, remaining))
+        }),
+        ['*', rest @ ..] => {
+            let end = text.iter().position(|c| *c == '/').unwrap_or(text.len());
+
+            (0..=end).any(|start| {
+                text.get(start..)
+                    .is_some_and(|remaining| match_chars(rest, remaining))
+            })
+        }
+        ['?', rest @ ..] => {
+            matches!(text, [first, remaining @ ..] if *first != '/' && match_chars(rest, remaining))
+        }
+        ['[', rest @ ..] => match rest.iter().position(|c| *c == ']') {
+            Some(close) => match text {
+                [first, remaining @ ..] => {
+                    let class = rest.get(..close).unwrap_or(&[]);
+                    let after = rest.get(close.saturating_add(1)..).unwrap_or(&[]);
+
+                    is_in_class(class, *first) && match_chars(after, remaining)
+                }
+                [] => false,
+            },
+            None => matches!(text, ['[', remaining @ ..] if match_chars(rest, remaining)),
+        },
+        ['{', rest @ ..] => match close_brace(rest) {
+            Some(close) => {
+                let inner = rest.get(..close).unwrap_or(&[]);
+                let after = rest.get(close.saturating_add(1)..).unwrap_or(&[]);
+
+                if let Some((low, high)) = numeric_range(inner) {
+                    (1..=text.len()).any(|end| {
+                        text.get(..end)
+                            .and_then(|number| {
+                                number.iter().collect::<String>().parse::<i64>().ok()
+                            })
+                            .is_some_and(|number| number >= low && number <= high)
+                            && text
+                                .get(end..)
+                                .is_some_and(|remaining| match_chars(after, remaining))
+                    })
+                } else if inner.contains(&',') {
+                    alternatives(inner).into_iter().any(|alternative| {
+                        let mut glob = alternative.to_vec();
+
+                        glob.extend_from_slice(after);
+                        match_chars(&glob, text)
+                    })
+                } else {
+                    matches!(text, ['{', remaining @ ..] if match_chars(rest, remaining))
+                }
+            }
+            None => matches!(text, ['{', remaining @ ..] if match_chars(rest, remaining)),
+        },
+        ['\\', escaped, rest @ ..] | [escaped, rest @ ..] => {
+            matches!(text, [first, remaining @ ..] if first == escaped && match_chars(rest, remaining))
+        }
+    }
+}
+
+/// Returns if `c` is within the character class `class`, which is the text between `[` and `]`.
+fn is_in_class(class: &[char], c: char) -> bool {
+    let (is_negated, class) = match class {
+        ['!', rest @ ..] => (true, rest),
+        _ => (false, class),
+    };
+    let mut is_in = false;
+    let mut index = 0;
+
+    while let Some(start) = class.get(index) {
+        if let (Some('-'), Some(end)) = (
+            class.get(index.saturating_add(1)),
+            class.get(index.saturating_add(2)),
+        ) {
+            is_in |= (*start..=*end).contains(&c);
+            index = index.saturating_add(3);
+        } else {
+            is_in |= *start == c;
+            index = index.saturating_add(1);
+        }
+    }
+
+    is_in != is_negated
+}
+
+/// Returns the index of the `}` that closes the `{` before `glob`.
+fn close_brace(glob: &[char]) -> Option<usize> {
+    let mut depth = 0_usize;
+
+    for (index, c) in glob.iter().enumerate() {
+        match c {
+            '{' => depth = depth.saturating_add(1),
+            '}' if depth == 0 => return Some(index),
+            '}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Returns the bounds of `inner` if it is a numeric range such as `1..10`.
+fn numeric_range(inner: &[char]) -> Option<(i64, i64)> {
+    let inner: String = inner.iter().collect();
+    let (low, high) = inner.split_once("..")?;
+
+    Some((low.parse().ok()?, high.parse().ok()?))
+}
+
+/// Returns the alternatives within `inner`, which are separated by commas that are not within nested braces.
+fn alternatives(inner: &[char]) -> Vec<&[char]> {
+    let mut alternatives = Vec::new();
+    let mut depth = 0_usize;
+    let mut start = 0;
+
+    for (index, c) in inner.iter().enumerate() {
+        match c {
+            '{' => depth = depth.saturating_add(1),
+            '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                alternatives.push(inner.get(start..index).unwrap_or(&[]));
+                start = index.saturating_add(1);
+            }
+            _ => {}
+        }
+    }
+
+    alternatives.push(inner.get(start..).unwrap_or(&[]));
+    alternatives
+}
+
+/// Testing of the editorconfig module.
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Globs shall match as defined by the EditorConfig specification.
+    #[test]
+    fn globs() {
+        assert!(matches("*.rs", "main.rs"));
+        assert!(!matches("*.rs", "src/main.rs"));
+        assert!(matches("src/**.rs", "src/io/main.rs"));
+        assert!(matches("{*.toml,Makefile}", "Makefile"));
+        assert!(matches("file[0-9].txt", "file5.txt"));
+        assert!(!matches("file[!0-9].txt", "file5.txt"));
+        assert!(matches("v{1..10}", "v10"));
+        assert!(!matches("v{1..10}", "v11"));
+        assert!(matches("a?c", "abc"));
+    }
+
+    /// Closer sections shall take precedence and `unset` shall remove a property.
+    #[test]
+    fn properties() {
+        let root = EditorConfig::parse(
+            PathBuf::from("/project"),
+            "root = true\n[*]\nindent_style = tab\ninsert_final_newline = true\n[*.rs]\nindent_size = 2\nend_of_line = CRLF",
+        );
+        let nested = EditorConfig::parse(
+            PathBuf::from("/project/src"),
+            "[*.rs]\nindent_style = space\ninsert_final_newline = unset",
+        );
+        let path = Path::new("/project/src/main.rs");
+        let mut properties = HashMap::new();
+
+        root.apply(path, &mut properties);
+        nested.apply(path, &mut properties);
+
+        let overrides = overrides_of(&properties);
+
+        assert!(root.is_root);
+        assert_eq!(overrides.expand_tabs, Some(true));
+        assert_eq!(overrides.tab_width, Some(2));
+        assert_eq!(overrides.final_newline, None);
+        assert_eq!(overrides.end_of_line, Some(LineEnding::Crlf));
+    }
+}
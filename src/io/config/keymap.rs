@@ -0,0 +1,545 @@
+//! Implements the bindings of key sequences to the actions of the application.
+use {
+    core::{
+        convert::TryFrom,
+        fmt::{self, Display},
+    },
+    crossterm::event::{KeyCode, KeyModifiers},
+    serde::{
+        de::{value, IntoDeserializer},
+        Deserialize,
+    },
+    std::collections::HashMap,
+};
+
+/// The names of the keys that are not characters.
+const KEY_NAMES: [(&str, KeyCode); 15] = [
+    ("esc", KeyCode::Esc),
+    ("enter", KeyCode::Enter),
+    ("tab", KeyCode::Tab),
+    ("backtab", KeyCode::BackTab),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+];
+
+/// The bindings of each mode.
+///
+/// Within a config file, each mode is a section such as `[keymap.view]` that maps sequences like `"ctrl-s"` or `"g g"` to the names of actions like `"save"`, or to a list of names of actions that are performed in order. Bindings of the config file are added to the default bindings, replacing any default binding of the same sequence.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Keymap {
+    /// The bindings used while viewing documents.
+    pub(crate) view: HashMap<Sequence, Binding>,
+    /// The bindings used while confirming an action.
+    pub(crate) confirm: HashMap<Sequence, Binding>,
+    /// The bindings used while collecting the input of a command.
+    pub(crate) collect: HashMap<Sequence, Binding>,
+}
+
+impl Keymap {
+    /// Returns the default bindings with the bindings of `self` added.
+    pub(crate) fn with_defaults(&self) -> Self {
+        let mut keymap = Self::defaults();
+
+        keymap.view.extend(self.view.clone());
+        keymap.confirm.extend(self.confirm.clone());
+        keymap.collect.extend(self.collect.clone());
+        keymap
+    }
+
+    /// Returns the default bindings.
+    fn defaults() -> Self {
+        /// Returns `bindings` as a map from parsed chords.
+        fn map(bindings: &[(&str, Action)]) -> HashMap<Sequence, Binding> {
+            bindings
+                .iter()
+                .filter_map(|(sequence, action)| {
+                    Some((Sequence::parse(sequence).ok()?, Binding(vec![*action])))
+                })
+                .collect()
+        }
+
+        Self {
+            view: map(&[
+                ("esc", Action::Cancel),
+                ("ctrl-w", Action::ConfirmQuit),
+                ("ctrl-s", Action::Save),
+                ("S", Action::Overwrite),
+                ("r", Action::Reload),
+                ("m", Action::ShowMessages),
+                ("ctrl-o", Action::Open),
+                ("b", Action::Buffer),
+                (":", Action::CommandLine),
+                ("n", Action::NextBuffer),
+                ("p", Action::PreviousBuffer),
+                ("_", Action::SplitHorizontal),
+                ("|", Action::SplitVertical),
+                ("c", Action::ClosePane),
+                ("+", Action::GrowHeight),
+                ("-", Action::ShrinkHeight),
+                (">", Action::GrowWidth),
+                ("<", Action::ShrinkWidth),
+                ("alt-h", Action::FocusLeft),
+                ("alt-j", Action::FocusDown),
+                ("alt-k", Action::FocusUp),
+                ("alt-l", Action::FocusRight),
+                ("left", Action::MoveLeft),
+                ("right", Action::MoveRight),
+                ("up", Action::RowUp),
+                ("down", Action::RowDown),
+                ("k", Action::LineUp),
+                ("j", Action::LineDown),
+                ("g g", Action::FirstLine),
+                ("G", Action::LastLine),
+            ]),
+            confirm: map(&[("y", Action::Quit), ("s", Action::SaveAndQuit)]),
+            collect: map(&[
+                ("esc", Action::Cancel),
+                ("enter", Action::Execute),
+                ("tab", Action::Complete),
+                ("backspace", Action::DeleteBack),
+                ("delete", Action::DeleteForward),
+                ("ctrl-w", Action::DeleteWord),
+                ("left", Action::CursorLeft),
+                ("right", Action::CursorRight),
+                ("home", Action::CursorStart),
+                ("end", Action::CursorEnd),
+                ("up", Action::HistoryPrevious),
+                ("down", Action::HistoryNext),
+                ("ctrl-n", Action::NextMatch),
+                ("ctrl-p", Action::PreviousMatch),
+            ]),
+        }
+    }
+}
+
+/// The actions that a sequence performs, in order.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(try_from = "RawBinding")]
+pub struct Binding(Vec<Action>);
+
+impl Binding {
+    /// Returns the actions of `self`.
+    pub(crate) fn actions(&self) -> &[Action] {
+        &self.0
+    }
+}
+
+/// The representation of a [`Binding`] within a config file.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawBinding {
+    /// The name of a single action.
+    Action(String),
+    /// The names of actions.
+    Actions(Vec<String>),
+}
+
+impl TryFrom<RawBinding> for Binding {
+    type Error = String;
+
+    #[inline]
+    fn try_from(value: RawBinding) -> Result<Self, Self::Error> {
+        let names = match value {
+            RawBinding::Action(name) => vec![name],
+            RawBinding::Actions(names) => names,
+        };
+
+        if names.is_empty() {
+            return Err("invalid binding: no actions are given".to_string());
+        }
+
+        names
+            .iter()
+            .map(|name| {
+                Action::deserialize(name.as_str().into_deserializer())
+                    .map_err(|error: value::Error| format!("invalid binding: {}", error))
+            })
+            .collect::<Result<Vec<Action>, String>>()
+            .map(Self)
+    }
+}
+
+/// Chords that are pressed one after another.
+///
+/// Within a config file, a sequence is its chords separated by spaces, such as `"g g"`.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(try_from = "String")]
+pub struct Sequence(Vec<Chord>);
+
+impl Sequence {
+    /// Creates a new [`Sequence`] of `chords`.
+    pub(crate) fn new(chords: Vec<Chord>) -> Self {
+        Self(chords)
+    }
+
+    /// Returns if `self` begins with `chords` and has more chords after them.
+    pub(crate) fn continues(&self, chords: &[Chord]) -> bool {
+        self.0.len() > chords.len() && self.0.starts_with(chords)
+    }
+
+    /// Parses `text`, such as `g g`, as a [`Sequence`].
+    ///
+    /// If `text` is invalid, a message that explains why is returned.
+    fn parse(text: &str) -> Result<Self, String> {
+        let chords = text
+            .split_whitespace()
+            .map(Chord::parse)
+            .collect::<Result<Vec<Chord>, String>>()?;
+
+        if chords.is_empty() {
+            Err("invalid key sequence: no keys are given".to_string())
+        } else {
+            Ok(Self(chords))
+        }
+    }
+}
+
+impl Display for Sequence {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.0
+                .iter()
+                .map(Chord::to_string)
+                .collect::<Vec<String>>()
+                .join(" ")
+        )
+    }
+}
+
+impl TryFrom<String> for Sequence {
+    type Error = String;
+
+    #[inline]
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+/// A key pressed while holding a set of modifiers.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Chord {
+    /// The key.
+    code: KeyCode,
+    /// The modifiers.
+    modifiers: KeyModifiers,
+}
+
+impl Chord {
+    /// Creates a new [`Chord`] of `code` pressed with `modifiers`.
+    ///
+    /// Since the character of a key already reflects if shift was held, shift is only kept for keys that are not characters.
+    pub(crate) fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self {
+            code,
+            modifiers: match code {
+                KeyCode::Char(..) | KeyCode::BackTab => modifiers - KeyModifiers::SHIFT,
+                KeyCode::Backspace
+                | KeyCode::Enter
+                | KeyCode::Left
+                | KeyCode::Right
+                | KeyCode::Up
+                | KeyCode::Down
+                | KeyCode::Home
+                | KeyCode::End
+                | KeyCode::PageUp
+                | KeyCode::PageDown
+                | KeyCode::Tab
+                | KeyCode::Delete
+                | KeyCode::Insert
+                | KeyCode::F(..)
+                | KeyCode::Null
+                | KeyCode::Esc => modifiers,
+            },
+        }
+    }
+
+    /// Returns the character typed by `self` if it is a character pressed without control or alt.
+    pub(crate) fn character(&self) -> Option<char> {
+        match self.code {
+            KeyCode::Char(c) if self.modifiers.is_empty() => Some(c),
+            _ => None,
+        }
+    }
+
+    /// Parses `text`, such as `ctrl-s` or `alt-shift-left`, as a [`Chord`].
+    ///
+    /// If `text` is invalid, a message that explains why is returned.
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::empty();
+        let mut key = text;
+
+        // A key of a single character is not a modifier even if it is followed by `-`, such as in `ctrl--`.
+        while let Some((modifier, rest)) = key.split_once('-').filter(|(_, rest)| !rest.is_empty())
+        {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => {
+                    return Err(format!(
+                        "invalid key `{}`: unknown modifier `{}`",
+                        text, modifier
+                    ))
+                }
+            };
+            key = rest;
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(if modifiers.contains(KeyModifiers::SHIFT) {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }),
+            _ => {
+                let name = key.to_lowercase();
+
+                if name == "space" {
+                    KeyCode::Char(' ')
+                } else if let Some(number) = name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    KeyCode::F(number)
+                } else if name == "tab" && modifiers.contains(KeyModifiers::SHIFT) {
+                    KeyCode::BackTab
+                } else {
+                    KEY_NAMES
+                        .iter()
+                        .find(|(key_name, _)| *key_name == name)
+                        .map(|(_, code)| *code)
+                        .ok_or_else(|| format!("invalid key `{}`: unknown key `{}`", text, key))?
+                }
+            }
+        };
+
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl Display for Chord {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in &[
+            (KeyModifiers::CONTROL, "ctrl-"),
+            (KeyModifiers::ALT, "alt-"),
+            (KeyModifiers::SHIFT, "shift-"),
+        ] {
+            if self.modifiers.contains(*modifier) {
+                write!(f, "{}", name)?;
+            }
+        }
+
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(number) => write!(f, "f{}", number),
+            KeyCode::Null => write!(f, "null"),
+            code => write!(
+                f,
+                "{}",
+                KEY_NAMES
+                    .iter()
+                    .find(|(_, key_code)| *key_code == code)
+                    .map_or("?", |(name, _)| name)
+            ),
+        }
+    }
+}
+
+/// A named action that a sequence is able to perform.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Does nothing, which removes a default binding.
+    Ignore,
+    /// Cancels the current command or confirmation.
+    Cancel,
+    /// Asks the user to confirm quitting.
+    ConfirmQuit,
+    /// Quits without saving.
+    Quit,
+    /// Saves every document with unsaved changes, then quits.
+    SaveAndQuit,
+    /// Saves the document.
+    Save,
+    /// Saves the document even if it was changed by another program.
+    Overwrite,
+    /// Reloads the document that was changed by another program.
+    Reload,
+    /// Displays the history of messages.
+    ShowMessages,
+    /// Requests the path of a file to open.
+    Open,
+    /// Requests the name of an open document to display.
+    Buffer,
+    /// Requests a command of the command line.
+    CommandLine,
+    /// Displays the next open document.
+    NextBuffer,
+    /// Displays the previous open document.
+    PreviousBuffer,
+    /// Splits the focused pane into stacked panes.
+    SplitHorizontal,
+    /// Splits the focused pane into side by side panes.
+    SplitVertical,
+    /// Closes the focused pane.
+    ClosePane,
+    /// Increases the height of the focused pane.
+    GrowHeight,
+    /// Decreases the height of the focused pane.
+    ShrinkHeight,
+    /// Increases the width of the focused pane.
+    GrowWidth,
+    /// Decreases the width of the focused pane.
+    ShrinkWidth,
+    /// Focuses the pane to the left.
+    FocusLeft,
+    /// Focuses the pane below.
+    FocusDown,
+    /// Focuses the pane above.
+    FocusUp,
+    /// Focuses the pane to the right.
+    FocusRight,
+    /// Moves the cursor to the previous grapheme.
+    MoveLeft,
+    /// Moves the cursor to the next grapheme.
+    MoveRight,
+    /// Moves the cursor to the previous row.
+    RowUp,
+    /// Moves the cursor to the next row.
+    RowDown,
+    /// Moves the cursor to the previous line.
+    LineUp,
+    /// Moves the cursor to the next line.
+    LineDown,
+    /// Moves the cursor to the first line, or the line of the count if one is given.
+    FirstLine,
+    /// Moves the cursor to the last line, or the line of the count if one is given.
+    LastLine,
+    /// Executes the current command.
+    Execute,
+    /// Completes the last word of the command line.
+    Complete,
+    /// Deletes the grapheme before the cursor of the input.
+    DeleteBack,
+    /// Deletes the grapheme after the cursor of the input.
+    DeleteForward,
+    /// Deletes the word before the cursor of the input.
+    DeleteWord,
+    /// Moves the cursor of the input to the previous grapheme.
+    CursorLeft,
+    /// Moves the cursor of the input to the next grapheme.
+    CursorRight,
+    /// Moves the cursor of the input to its start.
+    CursorStart,
+    /// Moves the cursor of the input to its end.
+    CursorEnd,
+    /// Replaces the input with the previous input given to the command.
+    HistoryPrevious,
+    /// Replaces the input with the next input given to the command, or the input that was being edited.
+    HistoryNext,
+    /// Selects the next match of the file finder.
+    NextMatch,
+    /// Selects the previous match of the file finder.
+    PreviousMatch,
+}
+
+/// Testing of the keymap module.
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Chords shall be parsed from modifiers and key names and invalid chords shall be explained.
+    #[test]
+    fn chords() {
+        assert_eq!(
+            Chord::parse("ctrl-s"),
+            Ok(Chord::new(KeyCode::Char('s'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(
+            Chord::parse("shift-s"),
+            Ok(Chord::new(KeyCode::Char('S'), KeyModifiers::empty()))
+        );
+        assert_eq!(
+            Chord::parse("alt--"),
+            Ok(Chord::new(KeyCode::Char('-'), KeyModifiers::ALT))
+        );
+        assert_eq!(
+            Chord::parse("Alt-PageDown"),
+            Ok(Chord::new(KeyCode::PageDown, KeyModifiers::ALT))
+        );
+        assert_eq!(
+            Chord::parse("hyper-s"),
+            Err("invalid key `hyper-s`: unknown modifier `hyper`".to_string())
+        );
+        assert_eq!(
+            Chord::parse("ctrl-alt-f5").unwrap().to_string(),
+            "ctrl-alt-f5"
+        );
+    }
+
+    /// Sequences shall be parsed from chords separated by spaces.
+    #[test]
+    fn sequences() {
+        let g = Chord::new(KeyCode::Char('g'), KeyModifiers::empty());
+
+        assert_eq!(Sequence::parse("g  g"), Ok(Sequence::new(vec![g, g])));
+        assert!(Sequence::parse("g g").unwrap().continues(&[g]));
+        assert!(!Sequence::parse("g g").unwrap().continues(&[g, g]));
+        assert_eq!(
+            Sequence::parse(" "),
+            Err("invalid key sequence: no keys are given".to_string())
+        );
+        assert_eq!(
+            Sequence::parse("ctrl-w  alt-l").unwrap().to_string(),
+            "ctrl-w alt-l"
+        );
+    }
+
+    /// Bindings of the config file shall be added to the default bindings.
+    #[test]
+    fn bindings() {
+        let keymap: Keymap = toml::from_str(
+            "[view]\n\"ctrl-q\" = \"quit\"\nw = \"ignore\"\n\"ctrl-x\" = [\"save\", \"close_pane\"]",
+        )
+        .unwrap();
+        let view = keymap.with_defaults().view;
+        let actions = |code, modifiers| {
+            view.get(&Sequence::new(vec![Chord::new(code, modifiers)]))
+                .map(Binding::actions)
+        };
+
+        assert_eq!(
+            actions(KeyCode::Char('q'), KeyModifiers::CONTROL),
+            Some(&[Action::Quit][..])
+        );
+        assert_eq!(
+            actions(KeyCode::Char('w'), KeyModifiers::empty()),
+            Some(&[Action::Ignore][..])
+        );
+        assert_eq!(
+            actions(KeyCode::Char('s'), KeyModifiers::CONTROL),
+            Some(&[Action::Save][..])
+        );
+        assert_eq!(actions(KeyCode::Char('s'), KeyModifiers::empty()), None);
+        assert_eq!(
+            actions(KeyCode::Char('x'), KeyModifiers::CONTROL),
+            Some(&[Action::Save, Action::ClosePane][..])
+        );
+        assert!(toml::from_str::<Keymap>("[view]\nw = \"sav\"").is_err());
+        assert!(toml::from_str::<Keymap>("[view]\nw = []").is_err());
+        assert!(toml::from_str::<Keymap>("[edit]\nw = \"save\"").is_err());
+    }
+}
@@ -1,24 +1,40 @@
 //! Handles filesystem operations.
+pub(crate) mod index;
+
 use {
-    crate::io::LanguageId,
+    crate::io::{
+        config::{editorconfig, Overrides},
+        LanguageId,
+    },
     core::{
+        cell::RefCell,
         convert::{TryFrom, TryInto},
         fmt::{self, Display},
+        time::Duration,
     },
-    fehler::throws,
+    fehler::{throw, throws},
+    log::warn,
     market::{ClosedMarketFailure, ConsumeError, Consumer, ProduceError, Producer, UnlimitedQueue},
+    notify::{DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher},
     parse_display::Display as ParseDisplay,
     std::{
-        ffi::OsStr,
-        fs,
-        io::{self, ErrorKind},
+        collections::HashMap,
+        ffi::{OsStr, OsString},
+        fs::{self, Metadata},
+        io::{self, ErrorKind, Write},
         path::{Path, PathBuf},
-        str::Lines,
+        process,
+        sync::mpsc::{self, Receiver},
     },
     thiserror::Error,
     url::Url,
 };
 
+/// How long the watcher waits for changes to a file to finish before reporting them.
+const WATCH_DELAY: Duration = Duration::from_millis(100);
+/// The maximum number of symbolic links that are followed to find the file that a path refers to.
+const MAX_LINKS: usize = 40;
+
 /// A **P**ath **URL** - a path and its appropriate URL.
 ///
 /// Analysis that path converts to a valid URL is performed one time, when the `Purl` is created.
@@ -54,7 +70,6 @@ impl Purl {
 
 impl AsRef<OsStr> for Purl {
     #[inline]
-    #[must_use]
     fn as_ref(&self) -> &OsStr {
         self.path.as_ref()
     }
@@ -62,7 +77,6 @@ impl AsRef<OsStr> for Purl {
 
 impl AsRef<Path> for Purl {
     #[inline]
-    #[must_use]
     fn as_ref(&self) -> &Path {
         self.path.as_ref()
     }
@@ -70,7 +84,6 @@ impl AsRef<Path> for Purl {
 
 impl AsRef<Url> for Purl {
     #[inline]
-    #[must_use]
     fn as_ref(&self) -> &Url {
         &self.url
     }
@@ -108,32 +121,137 @@ pub enum CreatePurlError {
 }
 
 /// The interface to the file system.
-#[derive(Debug, Default)]
+///
+/// Every file that is read or written is watched so that changes made by other processes are reported.
 pub(crate) struct FileSystem {
     /// Queue of URLs to read.
     files_to_read: UnlimitedQueue<Purl>,
+    /// Watches the directories that contain the watched files.
+    ///
+    /// Directories are watched rather than files so that files which are replaced, rather than written in place, are still watched.
+    watcher: RefCell<RecommendedWatcher>,
+    /// The events reported by `watcher`.
+    events: Receiver<DebouncedEvent>,
+    /// The [`Purl`] and the text, as last read or written by the application, of each watched file by the path of the file after following links.
+    watched: RefCell<HashMap<PathBuf, (Purl, String)>>,
+}
+
+impl FileSystem {
+    /// Creates a new [`FileSystem`].
+    #[throws(notify::Error)]
+    pub(crate) fn new() -> Self {
+        let (event_tx, events) = mpsc::channel();
+
+        Self {
+            files_to_read: UnlimitedQueue::default(),
+            watcher: RefCell::new(notify::watcher(event_tx, WATCH_DELAY)?),
+            events,
+            watched: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Records that the file at `url` contains `text`, beginning to watch it if it is not already watched.
+    fn watch(&self, url: &Purl, text: String) {
+        let path = resolve_links(url.as_ref());
+
+        if let Some(dir) = path.parent() {
+            if !self
+                .watched
+                .borrow()
+                .keys()
+                .any(|watched| watched.parent() == Some(dir))
+            {
+                if let Err(error) = self
+                    .watcher
+                    .borrow_mut()
+                    .watch(dir, RecursiveMode::NonRecursive)
+                {
+                    warn!("Unable to watch `{}`: {}", dir.display(), error);
+                }
+            }
+        }
+
+        let _ = self.watched.borrow_mut().insert(path, (url.clone(), text));
+    }
+
+    /// Returns if the watched file at `url` holds text other than the text last read or written by the application.
+    ///
+    /// The file is read rather than relying on the watcher so that a change that has not yet been reported is found.
+    fn is_changed(&self, url: &Purl) -> bool {
+        let path = resolve_links(url.as_ref());
+
+        self.watched.borrow().get(&path).is_some_and(|(_, text)| {
+            fs::read_to_string(&path).is_ok_and(|current| current != *text)
+        })
+    }
+
+    /// Returns the watched file changed by `event` if its text differs from the text last read or written by the application.
+    fn changed_file(&self, event: DebouncedEvent) -> Option<File> {
+        let path = match event {
+            DebouncedEvent::Write(path)
+            | DebouncedEvent::Create(path)
+            | DebouncedEvent::Rename(_, path) => path,
+            DebouncedEvent::NoticeWrite(..)
+            | DebouncedEvent::NoticeRemove(..)
+            | DebouncedEvent::Chmod(..)
+            | DebouncedEvent::Remove(..)
+            | DebouncedEvent::Rescan
+            | DebouncedEvent::Error(..) => return None,
+        };
+        let mut watched = self.watched.borrow_mut();
+        let (url, text) = watched.get_mut(&path)?;
+        let new_text = fs::read_to_string(&path).ok()?;
+
+        if new_text == *text {
+            None
+        } else {
+            text.clone_from(&new_text);
+            Some(
+                File::new(url.clone(), new_text)
+                    .with_overrides(editorconfig::overrides(url.as_ref())),
+            )
+        }
+    }
+}
+
+impl fmt::Debug for FileSystem {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FileSystem {{ .. }}")
+    }
 }
 
 impl Consumer for FileSystem {
-    type Good = File;
+    type Good = FileEvent;
     type Failure = ConsumeFileError;
 
     #[throws(ConsumeError<Self::Failure>)]
     fn consume(&self) -> Self::Good {
-        let path_url = self.files_to_read.consume().map_err(|error| match error {
-            ConsumeError::EmptyStock => ConsumeError::EmptyStock,
-            ConsumeError::Failure(failure) => ConsumeError::Failure(failure.into()),
-        })?;
-
-        File {
-            text: fs::read_to_string(&path_url)
-                .map_err(|error| ReadFileError {
-                    file: path_url.to_string(),
-                    error: error.kind(),
-                })
-                .map_err(|error| ConsumeError::Failure(error.into()))?,
-            url: path_url,
-        }
+        let path_url = match self.files_to_read.consume() {
+            Ok(path_url) => path_url,
+            Err(ConsumeError::EmptyStock) => {
+                for event in self.events.try_iter() {
+                    if let Some(file) = self.changed_file(event) {
+                        return FileEvent::Changed(file);
+                    }
+                }
+
+                throw!(ConsumeError::EmptyStock);
+            }
+            Err(ConsumeError::Failure(failure)) => throw!(ConsumeError::Failure(failure.into())),
+        };
+        let text = fs::read_to_string(&path_url)
+            .map_err(|error| ReadFileError {
+                file: path_url.to_string(),
+                error: error.kind(),
+            })
+            .map_err(|error| ConsumeError::Failure(error.into()))?;
+
+        self.watch(&path_url, text.clone());
+        FileEvent::Read(
+            File::new(path_url.clone(), text)
+                .with_overrides(editorconfig::overrides(path_url.as_ref())),
+        )
     }
 }
 
@@ -148,13 +266,44 @@ impl Producer for FileSystem {
                 .files_to_read
                 .produce(url)
                 .map_err(|error| error.map(Self::Failure::from))?,
-            Self::Good::Write { url, text } => {
-                fs::write(&url, text).map_err(|error| ProduceError::Failure(error.into()))?
+            Self::Good::Write {
+                url,
+                text,
+                backup,
+                overwrite,
+                is_new,
+            } => {
+                if !overwrite && self.is_changed(&url) {
+                    throw!(ProduceError::Failure(FileError::Changed));
+                }
+
+                if is_new {
+                    create(url.as_ref(), &text).map_err(|error| {
+                        ProduceError::Failure(if error.kind() == ErrorKind::AlreadyExists {
+                            FileError::Exists
+                        } else {
+                            error.into()
+                        })
+                    })?;
+                } else {
+                    save(url.as_ref(), &text, backup)
+                        .map_err(|error| ProduceError::Failure(error.into()))?;
+                }
+                self.watch(&url, text);
             }
         }
     }
 }
 
+/// An event concerning a file.
+#[derive(Debug)]
+pub(crate) enum FileEvent {
+    /// The file has been read.
+    Read(File),
+    /// The file has been changed by another process.
+    Changed(File),
+}
+
 /// An error executing a file command.
 #[derive(Debug, Error)]
 pub enum FileError {
@@ -162,8 +311,14 @@ pub enum FileError {
     #[error(transparent)]
     Closed(#[from] ClosedMarketFailure),
     /// An IO error.
-    #[error("")]
+    #[error("{0}")]
     Io(#[from] io::Error),
+    /// The file was changed by another program since it was last read or written by the application.
+    #[error("the file was changed by another program since it was read")]
+    Changed,
+    /// The file already exists but was to be newly created.
+    #[error("the file already exists; use `write!` to overwrite it")]
+    Exists,
 }
 
 /// Specifies a command to be executed on a file.
@@ -182,9 +337,203 @@ pub(crate) enum FileCommand {
         url: Purl,
         /// The text to be written.
         text: String,
+        /// If the previous contents of the file are kept in a backup.
+        backup: bool,
+        /// If the file is written even if another program changed it since it was last read or written.
+        overwrite: bool,
+        /// If the file is only written when it does not already exist.
+        is_new: bool,
     },
 }
 
+/// Writes `text` to a new file at `path`.
+///
+/// Fails with [`ErrorKind::AlreadyExists`] if a file already exists at `path`; the check is made when the file is created so that no file created in the meantime is replaced.
+#[throws(io::Error)]
+fn create(path: &Path, text: &str) {
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)?;
+
+    file.write_all(text.as_bytes())?;
+    file.sync_all()?;
+}
+
+/// Writes `text` to the file at `path` so that the file is never left partially written, where possible.
+///
+/// If `path` is a symbolic link, the file that it links to is written. The text is first written to a temporary file in the same directory, which then replaces the file; the permissions and ownership of the file are preserved. If the temporary file is not able to be created because the directory is not writable, or is not able to be given the owner of the file, the file is overwritten in place instead so that it keeps its owner. If `backup`, the previous contents of the file are copied to a file with `.bak` appended to its name.
+#[throws(io::Error)]
+pub(crate) fn save(path: &Path, text: &str, backup: bool) {
+    let target = resolve_links(path);
+    let metadata = match fs::metadata(&target) {
+        Ok(metadata) => Some(metadata),
+        Err(error) if error.kind() == ErrorKind::NotFound => None,
+        Err(error) => throw!(error),
+    };
+
+    if backup && metadata.is_some() {
+        let _ = fs::copy(&target, with_suffix(&target, ".bak"))?;
+    }
+
+    let mut temp_name = OsString::from(".");
+
+    temp_name.push(target.file_name().unwrap_or_else(|| OsStr::new("paper")));
+    temp_name.push(format!(".{}.tmp", process::id()));
+    let temp = target.with_file_name(temp_name);
+    let is_replaced = match create_temp(&temp, metadata.as_ref()) {
+        Ok(file) => {
+            let outcome = replace(file, &temp, &target, text, metadata.as_ref());
+
+            if !matches!(outcome, Ok(true)) {
+                let _ = fs::remove_file(&temp);
+            }
+
+            outcome?
+        }
+        Err(error) if metadata.is_some() && error.kind() == ErrorKind::PermissionDenied => false,
+        Err(error) => throw!(error),
+    };
+
+    if !is_replaced {
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .open(&target)?;
+
+        file.write_all(text.as_bytes())?;
+        file.sync_all()?;
+    }
+}
+
+/// Creates the temporary file at `temp`, where `metadata` is that of the file it replaces, if it exists.
+///
+/// Fails if anything already exists at `temp` so that a planted file or link is never written through. The file is created with the permissions of `metadata` so that its contents are never more accessible than those of the file it replaces.
+#[throws(io::Error)]
+fn create_temp(temp: &Path, metadata: Option<&Metadata>) -> fs::File {
+    let mut options = fs::OpenOptions::new();
+
+    let _ = options.write(true).create_new(true);
+
+    if let Some(metadata) = metadata {
+        restrict_mode(&mut options, metadata);
+    }
+
+    options.open(temp)?
+}
+
+/// Writes `text` to `file`, which is open at `temp`, and then moves `temp` to `target`, where `metadata` is that of the file at `target`, if it exists.
+///
+/// Returns if `target` was replaced; it is not if `temp` is not able to be given the owner of `metadata`.
+#[throws(io::Error)]
+fn replace(
+    mut file: fs::File,
+    temp: &Path,
+    target: &Path,
+    text: &str,
+    metadata: Option<&Metadata>,
+) -> bool {
+    file.write_all(text.as_bytes())?;
+    file.sync_all()?;
+
+    if let Some(metadata) = metadata {
+        fs::set_permissions(temp, metadata.permissions())?;
+
+        if !preserve_owner(temp, metadata) {
+            return false;
+        }
+    }
+
+    fs::rename(temp, target)?;
+
+    // The file has been replaced at this point, so failing to persist the rename is only logged.
+    if let Err(error) = sync_parent(target) {
+        warn!(
+            "Unable to sync the directory of `{}`: {}",
+            target.display(),
+            error
+        );
+    }
+
+    true
+}
+
+/// Flushes the directory that contains `path` to disk so that a rename of `path` persists.
+#[cfg(unix)]
+#[throws(io::Error)]
+fn sync_parent(path: &Path) {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::File::open(dir)?.sync_all()?;
+    }
+}
+
+/// Flushes the directory that contains `path` to disk so that a rename of `path` persists.
+///
+/// Directories are not able to be opened as files on this platform, so nothing is done.
+#[cfg(not(unix))]
+#[throws(io::Error)]
+fn sync_parent(_path: &Path) {}
+
+/// Returns the path of the file that `path` refers to after following symbolic links.
+///
+/// The file need not exist.
+fn resolve_links(path: &Path) -> PathBuf {
+    let mut target = path.to_path_buf();
+
+    for _ in 0..MAX_LINKS {
+        match fs::read_link(&target) {
+            // A relative link is relative to the directory that contains the link.
+            Ok(link) => target = target.with_file_name("").join(link),
+            Err(_) => break,
+        }
+    }
+
+    target
+}
+
+/// Returns `path` with `suffix` appended to its file name.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path
+        .file_name()
+        .map_or_else(OsString::new, OsStr::to_os_string);
+
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Sets the mode with which `options` creates a file to the permissions of `metadata`.
+#[cfg(unix)]
+fn restrict_mode(options: &mut fs::OpenOptions, metadata: &Metadata) {
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+    let _ = options.mode(metadata.permissions().mode() & 0o7777);
+}
+
+/// Sets the mode with which `options` creates a file to the permissions of `metadata`.
+///
+/// Files are not created with a mode on this platform, so nothing is done.
+#[cfg(not(unix))]
+fn restrict_mode(_options: &mut fs::OpenOptions, _metadata: &Metadata) {}
+
+/// Changes the owner of the file at `path` to match `metadata`.
+///
+/// Returns if the file has the owner of `metadata`.
+#[cfg(unix)]
+fn preserve_owner(path: &Path, metadata: &Metadata) -> bool {
+    use std::os::unix::fs::{chown, MetadataExt};
+
+    fs::metadata(path).is_ok_and(|new| new.uid() == metadata.uid() && new.gid() == metadata.gid())
+        || chown(path, Some(metadata.uid()), Some(metadata.gid())).is_ok()
+}
+
+/// Changes the owner of the file at `path` to match `metadata`.
+///
+/// Returns if the file has the owner of `metadata`.
+#[cfg(not(unix))]
+fn preserve_owner(_path: &Path, _metadata: &Metadata) -> bool {
+    true
+}
+
 /// A struct that represents a file.
 #[derive(Clone, Debug, PartialEq)]
 pub struct File {
@@ -192,12 +541,56 @@ pub struct File {
     url: Purl,
     /// The text of a file.
     text: String,
+    /// The settings that apply specifically to the file, such as those from EditorConfig files.
+    overrides: Overrides,
 }
 
 impl File {
-    /// Returns the  [`Lines`] of the text.
-    pub(crate) fn lines(&self) -> Lines<'_> {
-        self.text.lines()
+    /// Creates a new [`File`] at `url` with `text`.
+    pub(crate) fn new(url: Purl, text: String) -> Self {
+        Self {
+            url,
+            text,
+            overrides: Overrides::default(),
+        }
+    }
+
+    /// Returns `self` with the settings that apply specifically to it set to `overrides`.
+    pub(crate) const fn with_overrides(mut self, overrides: Overrides) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// Returns a [`File`] named `name` within the root directory with `text`, for use by tests.
+    #[cfg(test)]
+    pub(crate) fn fixture(name: &str, text: &str) -> Self {
+        Self::new(
+            Purl::try_from(PathBuf::from("/").join(name)).unwrap(),
+            text.to_string(),
+        )
+    }
+
+    /// Returns a [`File`] at the URL of `self` with `text` and the settings of `self`.
+    pub(crate) fn with_text(&self, text: String) -> Self {
+        Self {
+            url: self.url.clone(),
+            text,
+            overrides: self.overrides,
+        }
+    }
+
+    /// Returns the settings that apply specifically to `self`.
+    pub(crate) const fn overrides(&self) -> Overrides {
+        self.overrides
+    }
+
+    /// Returns the lines of the text.
+    ///
+    /// Unlike [`str::lines`], text that ends with a newline has an empty last line.
+    pub(crate) fn lines(&self) -> impl Iterator<Item = &str> {
+        self.text
+            .split('\n')
+            .map(|line| line.strip_suffix('\r').unwrap_or(line))
     }
 
     /// Returns a reference to the text of `self`.
@@ -236,3 +629,135 @@ pub struct ReadFileError {
     /// The path of the file being read.
     file: String,
 }
+
+/// Testing of the fs module.
+#[cfg(test)]
+mod test {
+    use {super::*, std::env};
+
+    /// Saving through a symbolic link shall replace the linked file, preserving its permissions and keeping a backup.
+    #[cfg(unix)]
+    #[test]
+    fn save_link() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let dir = env::temp_dir().join(format!("paper-save-{}", process::id()));
+        let target = dir.join("target.txt");
+        let link = dir.join("link.txt");
+
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&target, "old").unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o640)).unwrap();
+        symlink("target.txt", &link).unwrap();
+
+        save(&link, "new", true).unwrap();
+
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+        assert_eq!(
+            fs::metadata(&target).unwrap().permissions().mode() & 0o777,
+            0o640
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("target.txt.bak")).unwrap(),
+            "old"
+        );
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Saving shall not write through a file placed at the path of its temporary file, and the temporary file shall be created with the permissions of the file it replaces.
+    #[cfg(unix)]
+    #[test]
+    fn planted_temp() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let dir = env::temp_dir().join(format!("paper-temp-{}", process::id()));
+        let target = dir.join("a.txt");
+        let temp = dir.join(format!(".a.txt.{}.tmp", process::id()));
+        let victim = dir.join("victim.txt");
+
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&target, "old").unwrap();
+        fs::write(&victim, "victim").unwrap();
+        symlink("victim.txt", &temp).unwrap();
+
+        assert_eq!(
+            save(&target, "new", false).unwrap_err().kind(),
+            ErrorKind::AlreadyExists
+        );
+        assert_eq!(fs::read_to_string(&victim).unwrap(), "victim");
+        assert_eq!(fs::read_to_string(&target).unwrap(), "old");
+
+        fs::remove_file(&temp).unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o600)).unwrap();
+        let file = create_temp(&temp, Some(&fs::metadata(&target).unwrap())).unwrap();
+        assert_eq!(file.metadata().unwrap().permissions().mode() & 0o777, 0o600);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Writing a file that another program changed since it was written shall fail unless it is overwritten.
+    #[test]
+    fn write_changed() {
+        let dir = env::temp_dir().join(format!("paper-changed-{}", process::id()));
+        let url = Purl::try_from(dir.join("a.txt")).unwrap();
+        let file_system = FileSystem::new().unwrap();
+        let write = |text: &str, overwrite| {
+            file_system.produce(FileCommand::Write {
+                url: url.clone(),
+                text: text.to_string(),
+                backup: false,
+                overwrite,
+                is_new: false,
+            })
+        };
+
+        fs::create_dir_all(&dir).unwrap();
+        write("a", false).unwrap();
+        write("b", false).unwrap();
+        fs::write(dir.join("a.txt"), "other").unwrap();
+
+        assert!(matches!(
+            write("c", false),
+            Err(ProduceError::Failure(FileError::Changed))
+        ));
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "other");
+        write("c", true).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "c");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Writing a new file shall fail without changing the file if it already exists.
+    #[test]
+    fn write_new() {
+        let dir = env::temp_dir().join(format!("paper-new-{}", process::id()));
+        let url = Purl::try_from(dir.join("a.txt")).unwrap();
+        let file_system = FileSystem::new().unwrap();
+        let write = |text: &str| {
+            file_system.produce(FileCommand::Write {
+                url: url.clone(),
+                text: text.to_string(),
+                backup: false,
+                overwrite: false,
+                is_new: true,
+            })
+        };
+
+        fs::create_dir_all(&dir).unwrap();
+        write("a").unwrap();
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "a");
+        assert!(matches!(
+            write("b"),
+            Err(ProduceError::Failure(FileError::Exists))
+        ));
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "a");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,504 @@
+//! Implements the index of the files of a project, which omits the files ignored by `.gitignore` files.
+use {
+    super::WATCH_DELAY,
+    log::warn,
+    notify::{DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher},
+    std::{
+        collections::HashSet,
+        fs, io,
+        path::{Path, PathBuf},
+        sync::mpsc::{self, Receiver},
+        thread,
+    },
+};
+
+/// The name of a file that lists the files to be ignored.
+const FILE_NAME: &str = ".gitignore";
+
+/// The maximum number of files that are indexed.
+const INDEX_LIMIT: usize = 20_000;
+
+/// The files of a project.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Index {
+    /// The path of each file, relative to the root directory and separated by `/`, in order.
+    pub(crate) files: Vec<String>,
+    /// If files were omitted because the project has more than [`INDEX_LIMIT`] files.
+    pub(crate) is_truncated: bool,
+}
+
+/// Maintains the [`Index`] of a project in the background.
+#[derive(Debug)]
+pub(crate) struct Indexer {
+    /// The [`Index`]es that have been built and not consumed, from oldest to newest.
+    indexes: Receiver<Index>,
+}
+
+impl Indexer {
+    /// Creates an [`Indexer`] that indexes `root` on another thread.
+    ///
+    /// `root` is indexed again whenever a file within an indexed directory is created, removed or renamed or a `.gitignore` file within it is changed. Ignored directories are not watched.
+    pub(crate) fn new(root: PathBuf) -> Self {
+        let (index_tx, indexes) = mpsc::channel();
+
+        let _ = thread::spawn(move || {
+            let (event_tx, events) = mpsc::channel();
+            // The watcher is kept until the thread ends so that it continues to report events.
+            let mut watcher = notify::watcher(event_tx, WATCH_DELAY)
+                .map_err(|error| warn!("Unable to watch `{}`: {}", root.display(), error))
+                .ok();
+            let mut watched = HashSet::new();
+
+            loop {
+                let (index, dirs) = index(&root);
+
+                if let Some(watcher) = &mut watcher {
+                    watch_dirs(watcher, &mut watched, dirs);
+                }
+
+                // Ends once the indexes are no longer consumed or the events are no longer reported.
+                if index_tx.send(index).is_err()
+                    || !events.iter().any(|event| changes_index(&event))
+                {
+                    break;
+                }
+
+                // Any events that have already been reported are covered by the next index.
+                let _ = events.try_iter().count();
+            }
+        });
+
+        Self { indexes }
+    }
+
+    /// Returns the newest [`Index`] that has been built since the last call, if any.
+    pub(crate) fn latest(&self) -> Option<Index> {
+        self.indexes.try_iter().last()
+    }
+}
+
+/// Makes `watcher` watch each of `dirs`, where `watched` holds the directories that it already watches.
+///
+/// Each directory is watched on its own rather than recursively so that ignored directories, which may hold many files that change often, are not watched.
+fn watch_dirs(
+    watcher: &mut RecommendedWatcher,
+    watched: &mut HashSet<PathBuf>,
+    dirs: Vec<PathBuf>,
+) {
+    let dirs: HashSet<PathBuf> = dirs.into_iter().collect();
+
+    watched.retain(|dir| {
+        dirs.contains(dir) || {
+            // The directory may no longer exist, in which case it is no longer watched anyway.
+            let _ = watcher.unwatch(dir);
+            false
+        }
+    });
+
+    for dir in dirs {
+        if !watched.contains(&dir) {
+            match watcher.watch(&dir, RecursiveMode::NonRecursive) {
+                Ok(()) => {
+                    let _ = watched.insert(dir);
+                }
+                Err(error) => warn!("Unable to watch `{}`: {}", dir.display(), error),
+            }
+        }
+    }
+}
+
+/// Returns if `event` may change the [`Index`].
+///
+/// Changes within the `.git` directory are ignored.
+fn changes_index(event: &DebouncedEvent) -> bool {
+    let path = match event {
+        DebouncedEvent::Create(path)
+        | DebouncedEvent::Remove(path)
+        | DebouncedEvent::Rename(_, path) => path,
+        DebouncedEvent::Write(path) if path.ends_with(FILE_NAME) => path,
+        DebouncedEvent::Rescan => return true,
+        DebouncedEvent::Write(..)
+        | DebouncedEvent::NoticeWrite(..)
+        | DebouncedEvent::NoticeRemove(..)
+        | DebouncedEvent::Chmod(..)
+        | DebouncedEvent::Error(..) => return false,
+    };
+
+    !path
+        .components()
+        .any(|component| component.as_os_str() == ".git")
+}
+
+/// Returns the [`Index`] of the files within `root` and the directories that were visited to build it.
+///
+/// A file or directory that is ignored by a `.gitignore` file within `root`, as well as the `.git` directory, is omitted. Links to directories are not followed. If `root` holds more than [`INDEX_LIMIT`] files, only the first of them are indexed and a warning is logged.
+fn index(root: &Path) -> (Index, Vec<PathBuf>) {
+    let mut files = Vec::new();
+    let mut dirs = Vec::new();
+
+    let is_truncated = visit(root, "", &mut Vec::new(), &mut files, &mut dirs);
+
+    if is_truncated {
+        warn!(
+            "`{}` holds more than {} files; only the first {} are indexed",
+            root.display(),
+            INDEX_LIMIT,
+            INDEX_LIMIT
+        );
+    }
+
+    (
+        Index {
+            files,
+            is_truncated,
+        },
+        dirs,
+    )
+}
+
+/// Adds the files within `dir`, whose path relative to the root is `relative`, to `files` and `dir` and the directories within it to `dirs`, where `ignores` are the [`Ignore`]s of its ancestors.
+///
+/// Returns if a file was omitted because `files` reached [`INDEX_LIMIT`].
+fn visit(
+    dir: &Path,
+    relative: &str,
+    ignores: &mut Vec<Ignore>,
+    files: &mut Vec<String>,
+    dirs: &mut Vec<PathBuf>,
+) -> bool {
+    dirs.push(dir.to_path_buf());
+
+    let ignore_file = dir.join(FILE_NAME);
+    let has_ignore = match fs::read_to_string(&ignore_file) {
+        Ok(text) => {
+            ignores.push(Ignore::parse(relative, &text));
+            true
+        }
+        Err(error) => {
+            if error.kind() != io::ErrorKind::NotFound {
+                warn!("Unable to read `{}`: {}", ignore_file.display(), error);
+            }
+
+            false
+        }
+    };
+    let mut entries: Vec<(String, PathBuf, bool)> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .map(|entry| {
+                (
+                    entry.file_name().to_string_lossy().into_owned(),
+                    entry.path(),
+                    entry.file_type().is_ok_and(|file_type| file_type.is_dir()),
+                )
+            })
+            .collect(),
+        Err(error) => {
+            warn!("Unable to read directory `{}`: {}", dir.display(), error);
+            Vec::new()
+        }
+    };
+
+    entries.sort();
+
+    let mut is_truncated = false;
+
+    for (name, path, is_dir) in entries {
+        let file = if relative.is_empty() {
+            name.clone()
+        } else {
+            format!("{}/{}", relative, name)
+        };
+
+        if name == ".git" || is_ignored(ignores, &file, &name, is_dir) {
+            continue;
+        }
+
+        if is_dir {
+            is_truncated = visit(&path, &file, ignores, files, dirs);
+        } else if files.len() >= INDEX_LIMIT {
+            is_truncated = true;
+        } else {
+            files.push(file);
+        }
+
+        if is_truncated {
+            break;
+        }
+    }
+
+    if has_ignore {
+        let _ = ignores.pop();
+    }
+
+    is_truncated
+}
+
+/// Returns if the file at `path`, relative to the root, with `name` is ignored by `ignores`.
+///
+/// The last pattern that matches the file determines if it is ignored.
+fn is_ignored(ignores: &[Ignore], path: &str, name: &str, is_dir: bool) -> bool {
+    let mut is_ignored = false;
+
+    for ignore in ignores {
+        let relative = if ignore.dir.is_empty() {
+            Some(path)
+        } else {
+            path.strip_prefix(&ignore.dir)
+                .and_then(|rest| rest.strip_prefix('/'))
+        };
+
+        if let Some(relative) = relative {
+            for pattern in &ignore.patterns {
+                if pattern.matches(relative, name, is_dir) {
+                    is_ignored = !pattern.is_negated;
+                }
+            }
+        }
+    }
+
+    is_ignored
+}
+
+/// The patterns of a `.gitignore` file.
+#[derive(Debug)]
+struct Ignore {
+    /// The path, relative to the root, of the directory that holds the file.
+    dir: String,
+    /// The patterns, in order.
+    patterns: Vec<Pattern>,
+}
+
+impl Ignore {
+    /// Parses the `.gitignore` file in `dir` from `text`.
+    fn parse(dir: &str, text: &str) -> Self {
+        Self {
+            dir: dir.to_string(),
+            patterns: text.lines().filter_map(Pattern::parse).collect(),
+        }
+    }
+}
+
+/// A pattern of a `.gitignore` file.
+#[derive(Debug)]
+struct Pattern {
+    /// The glob that matches the files.
+    glob: Vec<char>,
+    /// If a matching file is included rather than ignored.
+    is_negated: bool,
+    /// If the pattern only matches directories.
+    is_dir_only: bool,
+    /// If the glob matches the path relative to the directory of the `.gitignore` file rather than the name of the file.
+    is_anchored: bool,
+}
+
+impl Pattern {
+    /// Parses a line of a `.gitignore` file, returning [`None`] if it is blank or a comment.
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim_end();
+
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let (is_negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let (is_dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+
+        Some(Self {
+            glob: line.trim_start_matches('/').chars().collect(),
+            is_negated,
+            is_dir_only,
+            is_anchored: line.contains('/'),
+        })
+    }
+
+    /// Returns if `self` matches the file at `path`, relative to the directory of the `.gitignore` file, with `name`.
+    fn matches(&self, path: &str, name: &str, is_dir: bool) -> bool {
+        if self.is_dir_only && !is_dir {
+            false
+        } else if self.is_anchored {
+            glob_matches(&self.glob, &path.chars().collect::<Vec<char>>())
+        } else {
+            glob_matches(&self.glob, &name.chars().collect::<Vec<char>>())
+        }
+    }
+}
+
+/// Returns if `text` matches the `.gitignore` glob `glob`.
+///
+/// Supports `*`, `**`, `?`, `[...]`, `[!...]`, `[^...]` and escaping with `\`; `**/` matches zero or more whole directories. Unlike an EditorConfig glob, braces match themselves.
+fn glob_matches(glob: &[char], text: &[char]) -> bool {
+    match glob {
+        [] => text.is_empty(),
+        ['*', '*', '/', rest @ ..] => {
+            glob_matches(rest, text)
+                || text.iter().enumerate().any(|(index, c)| {
+                    *c == '/'
+                        && text
+                            .get(index.saturating_add(1)..)
+                            .is_some_and(|remaining| glob_matches(rest, remaining))
+                })
+        }
+        ['*', '*', rest @ ..] => (0..=text.len()).any(|start| {
+            text.get(start..)
+                .is_some_and(|remaining| glob_matches(rest, remaining))
+        }),
+        ['*', rest @ ..] => {
+            let end = text.iter().position(|c| *c == '/').unwrap_or(text.len());
+
+            (0..=end).any(|start| {
+                text.get(start..)
+                    .is_some_and(|remaining| glob_matches(rest, remaining))
+            })
+        }
+        ['?', rest @ ..] => {
+            matches!(text, [first, remaining @ ..] if *first != '/' && glob_matches(rest, remaining))
+        }
+        ['[', rest @ ..] => {
+            // A `]` that immediately follows the opening `[` or its negation is a member of the class.
+            let first_member =
+                usize::from(matches!(rest.first(), Some('!' | '^'))).saturating_add(1);
+
+            match rest
+                .iter()
+                .skip(first_member)
+                .position(|c| *c == ']')
+                .map(|close| close.saturating_add(first_member))
+            {
+                Some(close) => match text {
+                    [first, remaining @ ..] => {
+                        let class = rest.get(..close).unwrap_or(&[]);
+                        let after = rest.get(close.saturating_add(1)..).unwrap_or(&[]);
+
+                        is_in_class(class, *first) && glob_matches(after, remaining)
+                    }
+                    [] => false,
+                },
+                None => matches!(text, ['[', remaining @ ..] if glob_matches(rest, remaining)),
+            }
+        }
+        ['\\', escaped, rest @ ..] | [escaped, rest @ ..] => {
+            matches!(text, [first, remaining @ ..] if first == escaped && glob_matches(rest, remaining))
+        }
+    }
+}
+
+/// Returns if `c` is a member of `class`, the text between the brackets of a `.gitignore` glob.
+fn is_in_class(class: &[char], c: char) -> bool {
+    let (is_negated, mut members) = match class {
+        ['!' | '^', rest @ ..] => (true, rest),
+        _ => (false, class),
+    };
+    let mut is_member = false;
+
+    loop {
+        match members {
+            [low, '-', high, rest @ ..] => {
+                is_member |= (*low..=*high).contains(&c);
+                members = rest;
+            }
+            [member, rest @ ..] => {
+                is_member |= *member == c;
+                members = rest;
+            }
+            [] => break,
+        }
+    }
+
+    c != '/' && is_member != is_negated
+}
+
+/// Testing of the index module.
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        std::{env, process},
+    };
+
+    /// Files ignored by `.gitignore` files shall be omitted, with nested files taking precedence.
+    #[test]
+    fn ignored() {
+        let root = env::temp_dir().join(format!("paper-index-{}", process::id()));
+
+        for dir in &["src/app", "target/debug", ".git", "docs"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+
+        for (file, text) in &[
+            (".gitignore", "/target/\n*.log\n# comment\n"),
+            ("src/.gitignore", "!keep.log\napp/*.tmp\n"),
+            ("src/main.rs", ""),
+            ("src/keep.log", ""),
+            ("src/app/view.rs", ""),
+            ("src/app/view.tmp", ""),
+            ("target/debug/paper", ""),
+            (".git/HEAD", ""),
+            ("docs/build.log", ""),
+        ] {
+            fs::write(root.join(file), text).unwrap();
+        }
+
+        let (index, dirs) = index(&root);
+
+        assert_eq!(
+            index,
+            Index {
+                files: vec![
+                    ".gitignore".to_string(),
+                    "src/.gitignore".to_string(),
+                    "src/app/view.rs".to_string(),
+                    "src/keep.log".to_string(),
+                    "src/main.rs".to_string(),
+                ],
+                is_truncated: false,
+            }
+        );
+        assert_eq!(
+            dirs,
+            vec![
+                root.clone(),
+                root.join("docs"),
+                root.join("src"),
+                root.join("src/app")
+            ]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    /// Globs shall match as in `.gitignore` files, where braces are literal.
+    #[test]
+    fn globs() {
+        let matches = |glob: &str, text: &str| {
+            glob_matches(
+                &glob.chars().collect::<Vec<char>>(),
+                &text.chars().collect::<Vec<char>>(),
+            )
+        };
+
+        assert!(matches("*.log", "build.log"));
+        assert!(!matches("*.log", "logs/build.log"));
+        assert!(matches("**/build.log", "a/b/build.log"));
+        assert!(matches("**/build.log", "build.log"));
+        assert!(matches("a/**/b", "a/b"));
+        assert!(matches("a/**/b", "a/x/y/b"));
+        assert!(!matches("a/**/b", "a/xb"));
+        assert!(matches("a/**", "a/x/y"));
+        assert!(matches("file[0-9].txt", "file5.txt"));
+        assert!(!matches("file[!0-9].txt", "file5.txt"));
+        assert!(matches("file[^0-9].txt", "fileA.txt"));
+        assert!(matches("[]a].txt", "].txt"));
+        assert!(matches("{a,b}.txt", "{a,b}.txt"));
+        assert!(!matches("{a,b}.txt", "a.txt"));
+        assert!(!matches("file{1..3}", "file2"));
+        assert!(matches("\\*.txt", "*.txt"));
+        assert!(!matches("\\*.txt", "a.txt"));
+    }
+}
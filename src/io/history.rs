@@ -0,0 +1,145 @@
+//! Implements the history of the input given to each command, which persists across sessions.
+use {
+    fehler::{throw, throws},
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::BTreeMap,
+        env, fs, io,
+        path::{Path, PathBuf},
+    },
+    thiserror::Error,
+};
+
+/// The number of inputs of each command that are kept.
+const HISTORY_LIMIT: usize = 100;
+
+/// Returns the path of the history file.
+///
+/// Follows the XDG Base Directory Specification: `paper/history.toml` within `$XDG_STATE_HOME` if it is an absolute path, otherwise within `~/.local/state`.
+pub(crate) fn history_file() -> Option<PathBuf> {
+    env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| dirs::home_dir().map(|home| home.join(".local").join("state")))
+        .map(|dir| dir.join("paper").join("history.toml"))
+}
+
+/// An error reading or writing the history file.
+#[derive(Debug, Error)]
+pub enum HistoryError {
+    /// Unable to read the history file.
+    #[error("unable to read history file: {0}")]
+    Read(#[source] io::Error),
+    /// The history file is not valid.
+    #[error("history file invalid format: {0}")]
+    Format(#[from] toml::de::Error),
+    /// Unable to convert the history into TOML.
+    #[error("unable to serialize history: {0}")]
+    Serialize(#[from] toml::ser::Error),
+    /// Unable to write the history file.
+    #[error("unable to write history file: {0}")]
+    Write(#[source] io::Error),
+}
+
+/// The inputs that have been given to each command, from oldest to newest.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct History(BTreeMap<String, Vec<String>>);
+
+impl History {
+    /// Reads the history from the file at `path`.
+    ///
+    /// If the file does not exist, the history is empty.
+    #[throws(HistoryError)]
+    pub(crate) fn read(path: &Path) -> Self {
+        match fs::read_to_string(path) {
+            Ok(text) => toml::from_str(&text)?,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(error) => throw!(HistoryError::Read(error)),
+        }
+    }
+
+    /// Writes `self` to the file at `path`, creating its directory if needed.
+    ///
+    /// The file is replaced as a whole so that it is never left partially written.
+    #[throws(HistoryError)]
+    pub(crate) fn write(&self, path: &Path) {
+        let text = toml::to_string(self)?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(HistoryError::Write)?;
+        }
+
+        super::fs::save(path, &text, false).map_err(HistoryError::Write)?;
+    }
+
+    /// Returns the inputs given to `command`, from oldest to newest.
+    pub(crate) fn entries(&self, command: &str) -> &[String] {
+        self.0.get(command).map_or(&[], Vec::as_slice)
+    }
+
+    /// Records `input` as the newest input given to `command`.
+    ///
+    /// An earlier occurrence of `input` is removed and only the newest [`HISTORY_LIMIT`] inputs are kept.
+    pub(crate) fn record(&mut self, command: &str, input: &str) {
+        if input.trim().is_empty() {
+            return;
+        }
+
+        let entries = self.0.entry(command.to_string()).or_default();
+
+        entries.retain(|entry| entry != input);
+        entries.push(input.to_string());
+
+        if entries.len() > HISTORY_LIMIT {
+            let _ = entries.drain(..entries.len().saturating_sub(HISTORY_LIMIT));
+        }
+    }
+}
+
+/// Testing of the history module.
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Recording an input shall move it to the newest position and the history shall be written as TOML.
+    #[test]
+    fn record() {
+        let mut history = History::default();
+
+        history.record("open", "a.txt");
+        history.record("open", "b.txt");
+        history.record("open", "a.txt");
+        history.record("open", " ");
+        history.record("line", "quit");
+
+        assert_eq!(history.entries("open"), ["b.txt", "a.txt"]);
+        assert_eq!(history.entries("buffer"), [] as [String; 0]);
+        assert_eq!(
+            toml::to_string(&history).unwrap(),
+            "line = [\"quit\"]\nopen = [\"b.txt\", \"a.txt\"]\n"
+        );
+        assert_eq!(
+            toml::from_str::<History>(&toml::to_string(&history).unwrap()).unwrap(),
+            history
+        );
+    }
+
+    /// The history shall be written to a new directory and read back unchanged, leaving no temporary file.
+    #[test]
+    fn write() {
+        let dir = env::temp_dir().join(format!("paper-history-{}", std::process::id()));
+        let file = dir.join("paper").join("history.toml");
+        let mut history = History::default();
+
+        history.record("open", "a.txt");
+        history.write(&file).unwrap();
+        history.record("open", "b.txt");
+        history.write(&file).unwrap();
+
+        assert_eq!(History::read(&file).unwrap(), history);
+        assert_eq!(fs::read_dir(dir.join("paper")).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -4,7 +4,7 @@ pub(crate) mod utils;
 pub(crate) use utils::SendNotificationError;
 
 use {
-    crate::io::{LanguageId, Purl},
+    crate::io::{ui::Scope, LanguageId, Purl},
     core::{
         cell::{Cell, RefCell},
         convert::{TryFrom, TryInto},
@@ -20,19 +20,24 @@ use {
         },
         request::{Initialize, RegisterCapability, Request, Shutdown},
         ClientCapabilities, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
-        InitializeParams, InitializeResult, InitializedParams, MessageType, ShowMessageParams,
-        SynchronizationCapability, TextDocumentClientCapabilities, TextDocumentIdentifier,
-        TextDocumentItem, TextDocumentSaveReason, TextDocumentSyncCapability, TextDocumentSyncKind,
-        Url, WillSaveTextDocumentParams,
+        InitializeParams, InitializeResult, InitializedParams, MessageType, PartialResultParams,
+        Range, SemanticToken, SemanticTokenModifier, SemanticTokenType,
+        SemanticTokensClientCapabilities, SemanticTokensDocumentProvider, SemanticTokensLegend,
+        SemanticTokensParams, SemanticTokensResult, SemanticTokensServerCapabilities,
+        ShowMessageParams, SynchronizationCapability, TextDocumentClientCapabilities,
+        TextDocumentIdentifier, TextDocumentItem, TextDocumentSaveReason,
+        TextDocumentSyncCapability, TextDocumentSyncKind, Url, WillSaveTextDocumentParams,
+        WorkDoneProgressParams,
     },
     market::{
         io::{Reader, Writer},
         ClosedMarketFailure, ConsumeError, Consumer, ProduceError, Producer,
     },
     parse_display::Display as ParseDisplay,
-    serde::{de::DeserializeOwned, Serialize},
-    serde_json::error::Error as SerdeJsonError,
+    serde::{de::DeserializeOwned, Deserialize, Serialize},
+    serde_json::{error::Error as SerdeJsonError, Value},
     std::{
+        collections::HashMap,
         io,
         process::{self, Child, ChildStderr, ChildStdin, ChildStdout, Command, Stdio},
         rc::Rc,
@@ -89,7 +94,6 @@ pub enum Fault {
 
 impl From<Fault> for ShowMessageParams {
     #[inline]
-    #[must_use]
     fn from(value: Fault) -> Self {
         Self {
             typ: MessageType::Error,
@@ -155,7 +159,9 @@ pub(crate) struct LanguageClient {
     /// Processes output from the stderr of the language server.
     error_processor: LspErrorProcessor,
     /// Controls settings for the language server.
-    settings: Cell<LspSettings>,
+    settings: RefCell<LspSettings>,
+    /// The requests that are awaiting a response, mapped by their ids.
+    pending: RefCell<HashMap<u64, PendingRequest>>,
     /// Reads messages from the language server process.
     reader: Reader<Message>,
 }
@@ -170,7 +176,7 @@ impl LanguageClient {
         let mut server = LangServer::new(language_id)?;
         let writer = Writer::new(server.stdin()?);
         let reader = Reader::new(server.stdout()?);
-        let settings = Cell::new(LspSettings::default());
+        let settings = RefCell::new(LspSettings::default());
 
         #[allow(deprecated)] // root_path is a required field.
         writer.produce(Message::request::<Initialize>(
@@ -208,6 +214,41 @@ impl LanguageClient {
                         rename: None,
                         publish_diagnostics: None,
                         folding_range: None,
+                        semantic_highlighting_capabilities: None,
+                        semantic_tokens: Some(SemanticTokensClientCapabilities {
+                            dynamic_registration: None,
+                            token_types: vec![
+                                SemanticTokenType::COMMENT,
+                                SemanticTokenType::KEYWORD,
+                                SemanticTokenType::STRING,
+                                SemanticTokenType::NUMBER,
+                                SemanticTokenType::REGEXP,
+                                SemanticTokenType::OPERATOR,
+                                SemanticTokenType::NAMESPACE,
+                                SemanticTokenType::TYPE,
+                                SemanticTokenType::STRUCT,
+                                SemanticTokenType::CLASS,
+                                SemanticTokenType::INTERFACE,
+                                SemanticTokenType::ENUM,
+                                SemanticTokenType::TYPE_PARAMETER,
+                                SemanticTokenType::FUNCTION,
+                                SemanticTokenType::MEMBER,
+                                SemanticTokenType::PROPERTY,
+                                SemanticTokenType::MACRO,
+                                SemanticTokenType::VARIABLE,
+                                SemanticTokenType::PARAMETER,
+                                SemanticTokenType::LABEL,
+                            ],
+                            token_modifiers: vec![
+                                SemanticTokenModifier::DOCUMENTATION,
+                                SemanticTokenModifier::DECLARATION,
+                                SemanticTokenModifier::DEFINITION,
+                                SemanticTokenModifier::STATIC,
+                                SemanticTokenModifier::ABSTRACT,
+                                SemanticTokenModifier::DEPRECATED,
+                                SemanticTokenModifier::READONLY,
+                            ],
+                        }),
                     }),
                     window: None,
                     experimental: None,
@@ -225,6 +266,7 @@ impl LanguageClient {
             writer,
             reader,
             settings,
+            pending: RefCell::new(HashMap::new()),
             id: Cell::new(1),
         }
     }
@@ -240,6 +282,70 @@ impl LanguageClient {
         self.id.set(id);
         Message::request::<T>(params, id)?
     }
+
+    /// Returns the appropriate request message, recording that its response is the result of `pending`.
+    #[throws(RequestResponseError)]
+    fn tracked_request<T: Request>(&self, params: T::Params, pending: PendingRequest) -> Message
+    where
+        T::Params: Serialize,
+        T::Result: DeserializeOwned + Default,
+    {
+        let message = self.request::<T>(params)?;
+
+        let _ = self.pending.borrow_mut().insert(self.id.get(), pending);
+        message
+    }
+
+    /// Converts `value`, the result of the request that was recorded as `pending`, into a [`ServerMessage`].
+    #[throws(ConsumeError<ClosedMarketFailure>)]
+    fn decode_result(&self, value: Value, pending: Option<PendingRequest>) -> ServerMessage {
+        match pending {
+            Some(PendingRequest::SemanticTokens(url)) => {
+                match serde_json::from_value::<<SemanticTokensFull as Request>::Result>(value) {
+                    Ok(result) => ServerMessage::SemanticTokens {
+                        url,
+                        tokens: match (result, &self.settings.borrow().legend) {
+                            (Some(SemanticTokensResult::Tokens(tokens)), Some(legend)) => {
+                                decode_tokens(legend, &tokens.data)
+                            }
+                            (Some(SemanticTokensResult::Partial(tokens)), Some(legend)) => {
+                                decode_tokens(legend, &tokens.data)
+                            }
+                            (None, _) | (_, None) => Vec::new(),
+                        },
+                    },
+                    Err(error) => {
+                        warn!("Received invalid semantic tokens: {}", error);
+                        throw!(ConsumeError::EmptyStock);
+                    }
+                }
+            }
+            Some(PendingRequest::InlayHints(url)) => {
+                match serde_json::from_value::<<InlayHints as Request>::Result>(value) {
+                    Ok(hints) => ServerMessage::InlayHints { url, hints },
+                    Err(error) => {
+                        warn!("Received invalid inlay hints: {}", error);
+                        throw!(ConsumeError::EmptyStock);
+                    }
+                }
+            }
+            None => {
+                if let Ok(result) = serde_json::from_value::<InitializeResult>(value.clone()) {
+                    self.settings.replace(LspSettings::from(result));
+                    ServerMessage::Initialize
+                } else if serde_json::from_value::<()>(value.clone()).is_ok() {
+                    ServerMessage::Shutdown
+                } else {
+                    warn!(
+                        "Received unknown response outcome from language client: {}",
+                        value
+                    );
+                    // TODO: Perhaps have a failure thrown here?
+                    throw!(ConsumeError::EmptyStock);
+                }
+            }
+        }
+    }
 }
 
 impl Consumer for LanguageClient {
@@ -261,25 +367,21 @@ impl Consumer for LanguageClient {
                 ..
             } => ServerMessage::Request { id: request_id },
             Message {
-                object:
-                    utils::Object::Response {
-                        outcome: utils::Outcome::Result(value),
-                        ..
-                    },
+                object: utils::Object::Response { outcome, id },
                 ..
             } => {
-                if let Ok(result) = serde_json::from_value::<InitializeResult>(value.clone()) {
-                    self.settings.set(LspSettings::from(result));
-                    ServerMessage::Initialize
-                } else if serde_json::from_value::<()>(value.clone()).is_ok() {
-                    ServerMessage::Shutdown
+                let pending = if let Id::Num(num) = id {
+                    self.pending.borrow_mut().remove(&num)
                 } else {
-                    warn!(
-                        "Received unknown response outcome from language client: {}",
-                        value
-                    );
-                    // TODO: Perhaps have a failure thrown here?
-                    throw!(ConsumeError::EmptyStock);
+                    None
+                };
+
+                match outcome {
+                    utils::Outcome::Result(value) => self.decode_result(value, pending)?,
+                    utils::Outcome::Error(error) => {
+                        warn!("Received error response from language client: {}", error);
+                        throw!(ConsumeError::EmptyStock);
+                    }
                 }
             }
             _ => throw!(ConsumeError::EmptyStock),
@@ -297,7 +399,7 @@ impl Producer for LanguageClient {
             ClientMessage::Doc(configuration) => {
                 match &configuration.message {
                     DocMessage::Open { .. } | DocMessage::Close => {
-                        if self.settings.get().notify_open_close {
+                        if self.settings.borrow().notify_open_close {
                             Some(good.clone().try_into().map_err(
                                 |error: TryIntoMessageError| ProduceError::Failure(error.into()),
                             )?)
@@ -306,7 +408,7 @@ impl Producer for LanguageClient {
                         }
                     }
                     DocMessage::Save => {
-                        if self.settings.get().notify_save {
+                        if self.settings.borrow().notify_save {
                             Some(good.clone().try_into().map_err(
                                 |error: TryIntoMessageError| ProduceError::Failure(error.into()),
                             )?)
@@ -314,6 +416,43 @@ impl Producer for LanguageClient {
                             None
                         }
                     }
+                    DocMessage::SemanticTokens => {
+                        if self.settings.borrow().legend.is_some() {
+                            Some(
+                                self.tracked_request::<SemanticTokensFull>(
+                                    SemanticTokensParams {
+                                        work_done_progress_params: WorkDoneProgressParams::default(
+                                        ),
+                                        partial_result_params: PartialResultParams::default(),
+                                        text_document: TextDocumentIdentifier::new(
+                                            configuration.url.clone(),
+                                        ),
+                                    },
+                                    PendingRequest::SemanticTokens(configuration.url.clone()),
+                                )
+                                .map_err(|error| ProduceError::Failure(error.into()))?,
+                            )
+                        } else {
+                            None
+                        }
+                    }
+                    DocMessage::InlayHints => {
+                        if self.settings.borrow().provides_inlay_hints {
+                            Some(
+                                self.tracked_request::<InlayHints>(
+                                    InlayHintsParams {
+                                        text_document: TextDocumentIdentifier::new(
+                                            configuration.url.clone(),
+                                        ),
+                                    },
+                                    PendingRequest::InlayHints(configuration.url.clone()),
+                                )
+                                .map_err(|error| ProduceError::Failure(error.into()))?,
+                            )
+                        } else {
+                            None
+                        }
+                    }
                 }
             }
             ClientMessage::RegisterCapability { .. }
@@ -365,7 +504,6 @@ pub enum EditLanguageToolError {
 
 impl From<EditLanguageToolError> for ShowMessageParams {
     #[inline]
-    #[must_use]
     fn from(value: EditLanguageToolError) -> Self {
         Self {
             typ: MessageType::Error,
@@ -387,7 +525,7 @@ impl LanguageTool {
     #[throws(CreateLanguageToolError)]
     pub(crate) fn new(root_dir: &Purl) -> Self {
         let rust_server = Rc::new(RefCell::new(
-            LanguageClient::new(LanguageId::Rust, &root_dir).map_err(|error| {
+            LanguageClient::new(LanguageId::Rust, root_dir).map_err(|error| {
                 CreateLanguageToolError {
                     language_id: LanguageId::Rust,
                     error,
@@ -461,6 +599,154 @@ pub enum ServerMessage {
         /// Id of the request.
         id: Id,
     },
+    /// The semantic tokens of a document.
+    SemanticTokens {
+        /// The URL of the document.
+        url: Url,
+        /// The tokens.
+        tokens: Vec<TokenSpan>,
+    },
+    /// The inlay hints of a document.
+    InlayHints {
+        /// The URL of the document.
+        url: Url,
+        /// The hints.
+        hints: Vec<InlayHint>,
+    },
+}
+
+/// A request that is awaiting a response from the language server.
+#[derive(Clone, Debug)]
+enum PendingRequest {
+    /// Requests the semantic tokens of the document at the URL.
+    SemanticTokens(Url),
+    /// Requests the inlay hints of the document at the URL.
+    InlayHints(Url),
+}
+
+/// Requests the semantic tokens of an entire document.
+///
+/// Defined here because the proposed request provided by `lsp_types` uses an outdated method.
+enum SemanticTokensFull {}
+
+impl Request for SemanticTokensFull {
+    type Params = SemanticTokensParams;
+    type Result = Option<SemanticTokensResult>;
+    const METHOD: &'static str = "textDocument/semanticTokens/full";
+}
+
+/// Requests the inlay hints of a document from `rust-analyzer`.
+enum InlayHints {}
+
+impl Request for InlayHints {
+    type Params = InlayHintsParams;
+    type Result = Vec<InlayHint>;
+    const METHOD: &'static str = "rust-analyzer/inlayHints";
+}
+
+/// The parameters of an [`InlayHints`] request.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InlayHintsParams {
+    /// The document.
+    text_document: TextDocumentIdentifier,
+}
+
+/// Text that is displayed within a document without being a part of its text.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct InlayHint {
+    /// The range of text that is described by the hint.
+    pub(crate) range: Range,
+    /// The kind of the hint.
+    pub(crate) kind: InlayKind,
+    /// The text of the hint.
+    pub(crate) label: String,
+}
+
+/// The kind of an [`InlayHint`].
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum InlayKind {
+    /// The inferred type of a binding, displayed after the binding.
+    TypeHint,
+    /// The name of a parameter, displayed before its argument.
+    ParameterHint,
+    /// The type of an expression in a method chain, displayed after the expression.
+    ChainingHint,
+}
+
+/// A semantic token located by its absolute position within a document.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TokenSpan {
+    /// The zero-based line of the token.
+    pub(crate) line: u64,
+    /// The zero-based UTF-16 code unit at which the token starts.
+    pub(crate) start: u64,
+    /// The number of UTF-16 code units in the token.
+    pub(crate) length: u64,
+    /// The [`Scope`] of the token.
+    pub(crate) scope: Scope,
+}
+
+/// Converts `tokens`, which are positioned relative to each other, into [`TokenSpan`]s.
+///
+/// Each [`TokenSpan`] is scoped by its type followed by each of its modifiers, as named by `legend`.
+fn decode_tokens(legend: &SemanticTokensLegend, tokens: &[SemanticToken]) -> Vec<TokenSpan> {
+    let mut line = 0_u64;
+    let mut start = 0_u64;
+    let mut spans = Vec::new();
+
+    for token in tokens {
+        if token.delta_line == 0 {
+            start = start.saturating_add(token.delta_start.into());
+        } else {
+            line = line.saturating_add(token.delta_line.into());
+            start = token.delta_start.into();
+        }
+
+        if let Some(token_type) = usize::try_from(token.token_type)
+            .ok()
+            .and_then(|index| legend.token_types.get(index))
+        {
+            let mut name = token_type.as_str().to_string();
+
+            for (index, modifier) in legend.token_modifiers.iter().enumerate() {
+                if u32::try_from(index)
+                    .ok()
+                    .and_then(|shift| 1_u32.checked_shl(shift))
+                    .is_some_and(|bit| token.token_modifiers_bitset & bit != 0)
+                {
+                    name.push('.');
+                    name.push_str(modifier.as_str());
+                }
+            }
+
+            spans.push(TokenSpan {
+                line,
+                start,
+                length: token.length.into(),
+                scope: Scope::new(name),
+            });
+        }
+    }
+
+    spans
+}
+
+/// Returns the index of the character of `line` that begins at `offset`.
+///
+/// Language servers measure positions within a line in UTF-16 code units. An `offset` past the end of `line` returns the number of characters in `line`.
+pub(crate) fn utf16_to_char_index(line: &str, offset: u64) -> usize {
+    let mut units = 0_u64;
+
+    for (index, ch) in line.chars().enumerate() {
+        if units >= offset {
+            return index;
+        }
+
+        units = units.saturating_add(u64::try_from(ch.len_utf16()).unwrap_or(u64::MAX));
+    }
+
+    line.chars().count()
 }
 
 /// Tool message of language server.
@@ -541,6 +827,9 @@ impl TryFrom<ClientMessage> for Message {
                         text_document: TextDocumentIdentifier::new(configuration.url),
                     })?
                 }
+                DocMessage::SemanticTokens | DocMessage::InlayHints => {
+                    throw!(Self::Error::Null);
+                }
             },
             ClientMessage::Initialized => Self::notification::<Initialized>(InitializedParams {})?,
             ClientMessage::Exit => Self::notification::<Exit>(())?,
@@ -599,6 +888,12 @@ pub(crate) enum DocMessage {
     /// Close a doc.
     #[display("Close")]
     Close,
+    /// Request the semantic tokens of a doc.
+    #[display("Request semantic tokens")]
+    SemanticTokens,
+    /// Request the inlay hints of a doc.
+    #[display("Request inlay hints")]
+    InlayHints,
 }
 
 /// An error producing protocol.
@@ -674,7 +969,7 @@ impl LangServer {
 }
 
 /// Settings of the language server.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 struct LspSettings {
     /// The client should send open and close notifications.
     notify_open_close: bool,
@@ -682,6 +977,12 @@ struct LspSettings {
     notify_changes_kind: TextDocumentSyncKind,
     /// The client should send save notifications.
     notify_save: bool,
+    /// The legend of the semantic tokens of full documents.
+    ///
+    /// [`None`] indicates the server does not provide semantic tokens of full documents.
+    legend: Option<SemanticTokensLegend>,
+    /// The server provides inlay hints.
+    provides_inlay_hints: bool,
 }
 
 impl Default for LspSettings {
@@ -690,6 +991,8 @@ impl Default for LspSettings {
             notify_open_close: false,
             notify_changes_kind: TextDocumentSyncKind::None,
             notify_save: false,
+            legend: None,
+            provides_inlay_hints: false,
         }
     }
 }
@@ -722,6 +1025,106 @@ impl From<InitializeResult> for LspSettings {
             }
         }
 
+        if let Some(provider) = value.capabilities.semantic_tokens_provider {
+            let options = match provider {
+                SemanticTokensServerCapabilities::SemanticTokensOptions(options) => options,
+                SemanticTokensServerCapabilities::SemanticTokensRegistrationOptions(options) => {
+                    options.semantic_tokens_options
+                }
+            };
+
+            match options.document_provider {
+                Some(SemanticTokensDocumentProvider::Bool(true))
+                | Some(SemanticTokensDocumentProvider::Edits { .. }) => {
+                    settings.legend = Some(options.legend);
+                }
+                Some(SemanticTokensDocumentProvider::Bool(false)) | None => {}
+            }
+        }
+
+        // Inlay hints are an extension of the protocol that is only provided by rust-analyzer.
+        settings.provides_inlay_hints = value
+            .server_info
+            .is_some_and(|info| info.name == "rust-analyzer");
         settings
     }
 }
+
+/// Testing of the lsp module.
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// UTF-16 offsets shall be converted to the index of the character at that offset.
+    #[test]
+    fn utf16_offsets() {
+        let line = "a😀é世b";
+
+        assert_eq!(utf16_to_char_index(line, 0), 0);
+        assert_eq!(utf16_to_char_index(line, 1), 1);
+        assert_eq!(utf16_to_char_index(line, 3), 2);
+        assert_eq!(utf16_to_char_index(line, 5), 4);
+        assert_eq!(utf16_to_char_index(line, 9), 5);
+    }
+
+    /// Semantic tokens shall be positioned absolutely and scoped by their type and modifiers.
+    #[test]
+    fn decode_semantic_tokens() {
+        let legend = SemanticTokensLegend {
+            token_types: vec![SemanticTokenType::KEYWORD, SemanticTokenType::FUNCTION],
+            token_modifiers: vec![
+                SemanticTokenModifier::DECLARATION,
+                SemanticTokenModifier::STATIC,
+            ],
+        };
+
+        assert_eq!(
+            decode_tokens(
+                &legend,
+                &[
+                    SemanticToken {
+                        delta_line: 1,
+                        delta_start: 0,
+                        length: 2,
+                        token_type: 0,
+                        token_modifiers_bitset: 0,
+                    },
+                    SemanticToken {
+                        delta_line: 0,
+                        delta_start: 3,
+                        length: 4,
+                        token_type: 1,
+                        token_modifiers_bitset: 0b11,
+                    },
+                    SemanticToken {
+                        delta_line: 2,
+                        delta_start: 4,
+                        length: 1,
+                        token_type: 1,
+                        token_modifiers_bitset: 0,
+                    },
+                ]
+            ),
+            vec![
+                TokenSpan {
+                    line: 1,
+                    start: 0,
+                    length: 2,
+                    scope: Scope::new("keyword".to_string()),
+                },
+                TokenSpan {
+                    line: 1,
+                    start: 3,
+                    length: 4,
+                    scope: Scope::new("function.declaration.static".to_string()),
+                },
+                TokenSpan {
+                    line: 3,
+                    start: 4,
+                    length: 1,
+                    scope: Scope::new("function".to_string()),
+                },
+            ]
+        );
+    }
+}
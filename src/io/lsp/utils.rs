@@ -347,12 +347,15 @@ impl Display for Object {
 pub(crate) enum Outcome {
     /// The result was successful.
     Result(Value),
+    /// The request failed.
+    Error(Value),
 }
 
 impl Display for Outcome {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Result(value) => write!(f, "Success {}", value),
+            Self::Error(value) => write!(f, "Failure {}", value),
         }
     }
 }
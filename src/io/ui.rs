@@ -3,14 +3,21 @@
 //! Visual output is organized as follows:
 //! - A header is displayed on a single row at the top of the display. The header displays general information about the current state of the system.
 //! - A page is displayed in the remaining space of the display. The page displays the text of the currently viewed document.
+//! - A status line is displayed on the row below the page. The status line displays the state of the application and the current document.
+//! - A message line is displayed on the bottom row of the display. The message line displays notifications, questions and commands.
 mod error;
+mod screen;
+mod theme;
 
-pub use error::{CreateTerminalError, DisplayCmdFailure, UserActionFailure};
+pub use {
+    error::{CreateTerminalError, DisplayCmdFailure, UserActionFailure},
+    theme::LoadThemeError,
+};
 
 use {
     core::{
-        cell::{RefCell, RefMut},
-        convert::{TryFrom, TryInto},
+        cell::{Cell, RefCell, RefMut},
+        fmt, iter,
         ops::Deref,
         time::Duration,
     },
@@ -19,16 +26,26 @@ use {
         event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
         execute,
         style::Print,
-        terminal::{EnterAlternateScreen, LeaveAlternateScreen},
+        terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
     },
-    error::{DestroyError, InitError, PollFailure, ReachedEnd, ReadFailure, WriteFailure},
+    error::{DestroyError, InitError, PollFailure, ReadFailure, WriteFailure},
     fehler::{throw, throws},
     log::{trace, warn},
     market::{ConsumeError, Consumer, ProduceError, Producer},
     parse_display::Display as ParseDisplay,
-    std::io::{self, Stdout, Write},
+    screen::Screen,
+    std::{
+        io::{self, Stdout, Write},
+        path::Path,
+    },
+    theme::{ColorDepth, Theme},
+    unicode_segmentation::UnicodeSegmentation,
+    unicode_width::UnicodeWidthStr,
 };
 
+/// The number of rows of the display that are not a part of the page: the header, the status line and the message line.
+const RESERVED_ROWS: u16 = 3;
+
 /// A instantaneous duration of time.
 static NO_DURATION: Duration = Duration::from_secs(0);
 
@@ -46,12 +63,17 @@ fn read_action() -> UserAction {
 
 /// Consumes all [`UserAction`]s from the user.
 #[derive(Debug, Default)]
-pub(crate) struct UserActionConsumer;
+pub(crate) struct UserActionConsumer {
+    /// If the initial size of the display has been consumed.
+    is_sized: Cell<bool>,
+}
 
 impl UserActionConsumer {
     /// Creates a new [`UserActionConsumer`].
-    pub(crate) fn new() -> Self {
-        Self::default()
+    pub(crate) const fn new() -> Self {
+        Self {
+            is_sized: Cell::new(false),
+        }
     }
 }
 
@@ -61,7 +83,12 @@ impl Consumer for UserActionConsumer {
 
     #[throws(ConsumeError<Self::Failure>)]
     fn consume(&self) -> Self::Good {
-        if is_action_available().map_err(|error| ConsumeError::Failure(error.into()))? {
+        if !self.is_sized.replace(true) {
+            // The display only reports changes to its size so the initial size must be provided.
+            terminal::size()
+                .map(|(columns, rows)| UserAction::from(Event::Resize(columns, rows)))
+                .map_err(|error| ConsumeError::Failure(ReadFailure::from(error).into()))?
+        } else if is_action_available().map_err(|error| ConsumeError::Failure(error.into()))? {
             read_action().map_err(|error| ConsumeError::Failure(error.into()))?
         } else {
             throw!(ConsumeError::EmptyStock);
@@ -70,17 +97,33 @@ impl Consumer for UserActionConsumer {
 }
 
 /// Produces all [`DisplayCmd`]s via the stdout of the application.
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub(crate) struct Terminal {
     /// The presenter.
     presenter: Presenter,
+    /// The [`Theme`] used to style text.
+    theme: RefCell<Theme>,
+    /// The [`ColorDepth`] of the terminal.
+    color_depth: ColorDepth,
+    /// The contents of the page.
+    screen: RefCell<Screen>,
+    /// The header that is currently displayed.
+    ///
+    /// [`None`] indicates the header row needs to be written.
+    header: RefCell<Option<String>>,
 }
 
 impl Terminal {
-    /// Creates and initializes a new [`Terminal`].
+    /// Creates and initializes a new [`Terminal`] that styles text according to the default [`Theme`] until a theme file is set.
     #[throws(CreateTerminalError)]
     pub(crate) fn new() -> Self {
-        let terminal = Self::default();
+        let terminal = Self {
+            presenter: Presenter::default(),
+            theme: RefCell::default(),
+            color_depth: ColorDepth::detect(),
+            screen: RefCell::default(),
+            header: RefCell::default(),
+        };
 
         terminal.presenter.init()?;
         terminal
@@ -95,33 +138,85 @@ impl Drop for Terminal {
     }
 }
 
+impl Terminal {
+    /// Styles text according to the theme file at `theme_file`.
+    ///
+    /// Text is restyled when it is next displayed.
+    #[throws(LoadThemeError)]
+    pub(crate) fn set_theme(&self, theme_file: &Path) {
+        *self.theme.borrow_mut() = Theme::load(theme_file)?;
+    }
+
+    /// Sets the row that is `offset` rows below the page to `row`.
+    #[throws(WriteFailure)]
+    fn set_footer(&self, offset: u16, row: &Row) {
+        let mut screen = self.screen.borrow_mut();
+        let index = screen
+            .height()
+            .saturating_sub(RESERVED_ROWS.saturating_sub(1))
+            .saturating_add(offset);
+
+        screen.set_row(usize::from(index), row, &self.theme.borrow());
+        self.presenter.render(&mut screen, self.color_depth)?;
+    }
+
+    /// Matches the size of the page to the size of the terminal.
+    ///
+    /// If the size has changed, the terminal is cleared so that everything is written again.
+    #[throws(WriteFailure)]
+    fn fit(&self) {
+        let (columns, rows) = terminal::size()?;
+        // The screen includes everything below the header.
+        let height = rows.saturating_sub(1);
+        let mut screen = self.screen.borrow_mut();
+
+        if !screen.is_size(height, columns) {
+            self.presenter.clear()?;
+            screen.resize(height, columns);
+            *self.header.borrow_mut() = None;
+        }
+    }
+}
+
 impl Producer for Terminal {
     type Good = DisplayCmd;
     type Failure = DisplayCmdFailure;
 
     #[throws(ProduceError<Self::Failure>)]
     fn produce(&self, good: Self::Good) {
+        self.fit()
+            .map_err(|failure| ProduceError::Failure(failure.into()))?;
+
         match good {
             DisplayCmd::Rows { rows } => {
-                let mut row = RowId(0);
+                let mut screen = self.screen.borrow_mut();
 
-                for text in rows {
-                    self.presenter
-                        .single_line(
-                            row.try_into()
-                                .map_err(|error: ReachedEnd| ProduceError::Failure(error.into()))?,
-                            text.to_string(),
-                        )
-                        .map_err(|failure| ProduceError::Failure(failure.into()))?;
-                    row.step_forward()
-                        .map_err(|failure| ProduceError::Failure(failure.into()))?;
+                for (index, row) in rows.iter().enumerate() {
+                    screen.set_row(index, row, &self.theme.borrow());
                 }
-            }
-            DisplayCmd::Header { header } => {
+
                 self.presenter
-                    .single_line(Unit(0), header)
+                    .render(&mut screen, self.color_depth)
+                    .map_err(|failure| ProduceError::Failure(failure.into()))?;
+            }
+            DisplayCmd::Status { status } => {
+                self.set_footer(0, &status)
+                    .map_err(|failure| ProduceError::Failure(failure.into()))?;
+            }
+            DisplayCmd::Message { message } => {
+                self.set_footer(1, &message)
                     .map_err(|failure| ProduceError::Failure(failure.into()))?;
             }
+            DisplayCmd::Header { header } => {
+                let mut current_header = self.header.borrow_mut();
+
+                if current_header.as_ref() != Some(&header) {
+                    self.presenter
+                        .single_line(Unit(0), &header)
+                        .map_err(|failure| ProduceError::Failure(failure.into()))?;
+                    *current_header = Some(header);
+                }
+            }
         }
     }
 }
@@ -151,11 +246,32 @@ impl Presenter {
         execute!(self.out_mut(), LeaveAlternateScreen)?;
     }
 
-    /// Writes `text` at `row`.
+    /// Clears the entire display.
     #[throws(WriteFailure)]
-    fn single_line(&self, row: Unit, text: String) {
+    fn clear(&self) {
+        execute!(self.out_mut(), Clear(ClearType::All))?;
+    }
+
+    /// Writes `text` at `row`, clearing the remainder of the row.
+    #[throws(WriteFailure)]
+    fn single_line(&self, row: Unit, text: &str) {
         trace!("Writing to {}: `{}`", row, text);
-        execute!(self.out_mut(), MoveTo(0, *row), Print(text))?;
+        execute!(
+            self.out_mut(),
+            MoveTo(0, *row),
+            Print(text),
+            Clear(ClearType::UntilNewLine)
+        )?;
+    }
+
+    /// Writes the cells of `screen` that have changed, flushing them to the display at once.
+    #[throws(WriteFailure)]
+    fn render(&self, screen: &mut Screen, color_depth: ColorDepth) {
+        let mut out = self.out_mut();
+
+        // Account for header row.
+        screen.render(&mut *out, 1, color_depth)?;
+        out.flush().map_err(crossterm::ErrorKind::from)?;
     }
 }
 
@@ -192,8 +308,8 @@ impl From<Event> for UserAction {
         match value {
             Event::Resize(columns, rows) => Self::Resize {
                 dimensions: Dimensions {
-                    // Reserve the top row for the header. Since a display height of 0 has no available height for the page, saturating_sub() is okay.
-                    height: rows.saturating_sub(1).into(),
+                    // Reserve the rows that are not a part of the page. Since a display height of 0 has no available height for the page, saturating_sub() is okay.
+                    height: rows.saturating_sub(RESERVED_ROWS).into(),
                     width: columns.into(),
                 },
             },
@@ -220,7 +336,17 @@ pub(crate) enum DisplayCmd {
     /// Display rows of text.
     Rows {
         /// The rows to be displayed.
-        rows: Vec<String>,
+        rows: Vec<Row>,
+    },
+    /// Displays the status line.
+    Status {
+        /// The status.
+        status: Row,
+    },
+    /// Displays the message line.
+    Message {
+        /// The message.
+        message: Row,
     },
     /// Displays the header.
     Header {
@@ -260,35 +386,112 @@ impl From<u16> for Unit {
     }
 }
 
-impl TryFrom<RowId> for Unit {
-    type Error = ReachedEnd;
+/// Identifies the meaning of a section of text, such as `keyword` or `function.declaration`.
+///
+/// A scope is made up of segments separated by `.`, ordered from least to most specific.
+#[derive(Clone, Debug, Eq, Hash, ParseDisplay, PartialEq)]
+#[display("{0}")]
+pub struct Scope(String);
 
-    #[throws(Self::Error)]
-    #[inline]
-    fn try_from(value: RowId) -> Self {
-        // Account for header row.
-        value.0.checked_add(1).ok_or(ReachedEnd)?.into()
+impl Scope {
+    /// Creates a new [`Scope`].
+    pub(crate) fn new(name: String) -> Self {
+        Self(name)
+    }
+
+    /// Returns `self` followed by each of its ancestors, from most to least specific.
+    fn ancestry(&self) -> impl Iterator<Item = &str> {
+        iter::successors(Some(self.0.as_str()), |name| {
+            name.rfind('.').and_then(|index| name.get(..index))
+        })
     }
 }
 
-/// Represents a row index.
-#[derive(Clone, Copy, Debug, ParseDisplay)]
-#[display("{0}")]
-pub(crate) struct RowId(u16);
-
-impl RowId {
-    /// Increments `self` by 1.
-    // This could be a part of a newly created Traveler trait.
-    #[throws(ReachedEnd)]
-    fn step_forward(&mut self) {
-        self.0 = self.0.checked_add(1).ok_or(ReachedEnd)?;
+/// A section of text that shares a single [`Scope`].
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Span {
+    /// The text.
+    text: String,
+    /// The [`Scope`] of `text`.
+    ///
+    /// [`None`] indicates that `text` has no special meaning.
+    scope: Option<Scope>,
+}
+
+/// A row of text made up of [`Span`]s.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Row {
+    /// The [`Span`]s of the row.
+    spans: Vec<Span>,
+}
+
+impl Row {
+    /// Appends `text` with `scope` to the end of `self`.
+    ///
+    /// If the last [`Span`] of `self` has the same [`Scope`], `text` is merged into it.
+    pub(crate) fn push_str(&mut self, text: &str, scope: Option<&Scope>) {
+        if text.is_empty() {
+            return;
+        }
+
+        match self.spans.last_mut() {
+            Some(last) if last.scope.as_ref() == scope => last.text.push_str(text),
+            _ => self.spans.push(Span {
+                text: text.to_string(),
+                scope: scope.cloned(),
+            }),
+        }
+    }
+
+    /// Appends the [`Span`]s of `other` to the end of `self`.
+    pub(crate) fn append(&mut self, other: Self) {
+        for span in other.spans {
+            self.push_str(&span.text, span.scope.as_ref());
+        }
+    }
+
+    /// Returns `self` clipped or padded with spaces so that it occupies exactly `width` columns.
+    ///
+    /// A wide grapheme that does not fit is replaced by spaces.
+    pub(crate) fn fit(self, width: usize) -> Self {
+        let mut row = Self::default();
+        let mut column: usize = 0;
+
+        for span in self.spans {
+            for grapheme in span.text.graphemes(true) {
+                let end = column.saturating_add(grapheme.width());
+
+                if end > width {
+                    break;
+                }
+
+                row.push_str(grapheme, span.scope.as_ref());
+                column = end;
+            }
+        }
+
+        row.push_str(&" ".repeat(width.saturating_sub(column)), None);
+        row
     }
 }
 
-impl Deref for RowId {
-    type Target = u16;
+impl From<String> for Row {
+    #[inline]
+    fn from(value: String) -> Self {
+        let mut row = Self::default();
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
+        row.push_str(&value, None);
+        row
+    }
+}
+
+impl fmt::Display for Row {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for span in &self.spans {
+            write!(f, "{}", span.text)?;
+        }
+
+        Ok(())
     }
 }
@@ -26,8 +26,6 @@ pub enum UserActionFailure {
 pub enum DisplayCmdFailure {
     /// A failure writing text.
     Write(#[from] WriteFailure),
-    /// A failure incrementing a row.
-    End(#[from] ReachedEnd),
 }
 
 /// A failure writing to stdout.
@@ -74,8 +72,3 @@ pub(crate) struct DestroyError {
     #[from]
     error: ErrorKind,
 }
-
-/// When the [`RowId`] has reached its end.
-#[derive(Clone, Copy, Debug, ThisError)]
-#[error("")]
-pub struct ReachedEnd;
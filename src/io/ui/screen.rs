@@ -0,0 +1,261 @@
+//! Implements an in-memory representation of the page so that only what has changed is rendered.
+use {
+    super::{
+        theme::{ColorDepth, Style, Theme},
+        Row,
+    },
+    core::convert::TryFrom,
+    crossterm::{
+        cursor::MoveTo,
+        queue,
+        style::{
+            Attribute, Print, SetAttribute, SetAttributes, SetBackgroundColor, SetForegroundColor,
+        },
+        ErrorKind,
+    },
+    std::io::Write,
+    unicode_segmentation::UnicodeSegmentation,
+    unicode_width::UnicodeWidthStr,
+};
+
+/// A single column of the page and its [`Style`].
+#[derive(Clone, Debug, PartialEq)]
+struct Cell {
+    /// The grapheme cluster that begins at the cell.
+    ///
+    /// An empty grapheme indicates the cell is covered by the wide grapheme of a previous cell.
+    grapheme: String,
+    /// The [`Style`] of `grapheme`.
+    style: Style,
+}
+
+impl Cell {
+    /// Creates a [`Cell`] covered by the wide grapheme of a previous cell.
+    fn continuation(style: Style) -> Self {
+        Self {
+            grapheme: String::new(),
+            style,
+        }
+    }
+}
+
+impl Default for Cell {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            grapheme: " ".to_string(),
+            style: Style::default(),
+        }
+    }
+}
+
+/// A grid of [`Cell`]s, organized by rows.
+type Grid = Vec<Vec<Cell>>;
+
+/// Creates a blank [`Grid`] of `height` rows and `width` columns.
+fn blank_grid(height: u16, width: u16) -> Grid {
+    vec![vec![Cell::default(); usize::from(width)]; usize::from(height)]
+}
+
+/// The page as it is currently displayed and as it shall be displayed.
+#[derive(Debug, Default)]
+pub(crate) struct Screen {
+    /// The [`Cell`]s currently displayed by the terminal.
+    front: Grid,
+    /// The [`Cell`]s that shall be displayed by the terminal.
+    back: Grid,
+    /// The number of rows.
+    height: u16,
+    /// The number of columns.
+    width: u16,
+}
+
+impl Screen {
+    /// Returns the number of rows of `self`.
+    pub(crate) const fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Returns if `self` has `height` rows and `width` columns.
+    pub(crate) fn is_size(&self, height: u16, width: u16) -> bool {
+        self.height == height && self.width == width
+    }
+
+    /// Resizes `self` to `height` rows and `width` columns, retaining as much of the back buffer as fits.
+    ///
+    /// The front buffer is assumed to be blank, i.e. the terminal shall be cleared.
+    pub(crate) fn resize(&mut self, height: u16, width: u16) {
+        let mut back = blank_grid(height, width);
+
+        for (new_row, old_row) in back.iter_mut().zip(&self.back) {
+            for (new_cell, old_cell) in new_row.iter_mut().zip(old_row) {
+                new_cell.clone_from(old_cell);
+            }
+        }
+
+        self.front = blank_grid(height, width);
+        self.back = back;
+        self.height = height;
+        self.width = width;
+    }
+
+    /// Sets the row at `index` of the back buffer to `row`, styled according to `theme`.
+    ///
+    /// A grapheme occupies as many cells as its display width; graphemes with no width are discarded. Cells after the end of `row` are cleared and text past the last column is discarded.
+    pub(crate) fn set_row(&mut self, index: usize, row: &Row, theme: &Theme) {
+        if let Some(cells) = self.back.get_mut(index) {
+            let mut new_cells = Vec::with_capacity(cells.len());
+
+            for span in &row.spans {
+                let style = span
+                    .scope
+                    .as_ref()
+                    .map_or_else(Style::default, |scope| theme.style(scope));
+
+                for grapheme in span.text.graphemes(true) {
+                    let width = grapheme.width();
+
+                    if width == 0 {
+                        continue;
+                    }
+
+                    if new_cells.len().saturating_add(width) > cells.len() {
+                        break;
+                    }
+
+                    new_cells.push(Cell {
+                        grapheme: grapheme.to_string(),
+                        style,
+                    });
+                    new_cells.resize(
+                        new_cells.len().saturating_add(width).saturating_sub(1),
+                        Cell::continuation(style),
+                    );
+                }
+            }
+
+            new_cells.resize_with(cells.len(), Cell::default);
+            *cells = new_cells;
+        }
+    }
+
+    /// Queues to `out` the commands that change the display from the front buffer to the back buffer, where the page begins at row `top`.
+    ///
+    /// Only cells that have changed are written. Colors are converted to those that are able to be displayed at `color_depth`.
+    pub(crate) fn render<W: Write>(
+        &mut self,
+        out: &mut W,
+        top: u16,
+        color_depth: ColorDepth,
+    ) -> Result<(), ErrorKind> {
+        // The style of the terminal; None indicates it is unknown.
+        let mut current_style = None;
+
+        for (index, (front_row, back_row)) in self.front.iter().zip(&self.back).enumerate() {
+            let row = u16::try_from(index).unwrap_or(u16::MAX).saturating_add(top);
+            // The column of the terminal cursor if it is on this row.
+            let mut cursor = None;
+
+            for (column, (front_cell, back_cell)) in front_row.iter().zip(back_row).enumerate() {
+                if front_cell != back_cell {
+                    if back_cell.grapheme.is_empty() {
+                        // The cell was written along with the wide grapheme that covers it.
+                        continue;
+                    }
+
+                    if cursor != Some(column) {
+                        queue!(out, MoveTo(u16::try_from(column).unwrap_or(u16::MAX), row))?;
+                    }
+
+                    if current_style != Some(back_cell.style) {
+                        apply_style(out, back_cell.style, color_depth)?;
+                        current_style = Some(back_cell.style);
+                    }
+
+                    queue!(out, Print(&back_cell.grapheme))?;
+                    cursor = column.checked_add(back_cell.grapheme.width());
+                }
+            }
+        }
+
+        if current_style.is_some() {
+            queue!(out, SetAttribute(Attribute::Reset))?;
+        }
+
+        self.front.clone_from(&self.back);
+        Ok(())
+    }
+}
+
+/// Queues to `out` the commands that set the style of the terminal to `style`.
+fn apply_style<W: Write>(
+    out: &mut W,
+    style: Style,
+    color_depth: ColorDepth,
+) -> Result<(), ErrorKind> {
+    queue!(
+        out,
+        SetAttribute(Attribute::Reset),
+        SetAttributes(style.attributes)
+    )?;
+
+    if let Some(color) = style.foreground {
+        queue!(out, SetForegroundColor(color_depth.convert(color.0)))?;
+    }
+
+    if let Some(color) = style.background {
+        queue!(out, SetBackgroundColor(color_depth.convert(color.0)))?;
+    }
+
+    Ok(())
+}
+
+/// Testing of the screen module.
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Returns the output of rendering `screen`.
+    fn rendered(screen: &mut Screen) -> String {
+        let mut out = Vec::new();
+
+        screen.render(&mut out, 1, ColorDepth::TrueColor).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    /// Only cells that have changed shall be rendered and shorter rows shall clear remaining cells.
+    #[test]
+    fn damage() {
+        let theme = Theme::default();
+        let mut screen = Screen::default();
+
+        screen.resize(2, 5);
+        screen.set_row(0, &Row::from("abcde".to_string()), &theme);
+        screen.set_row(1, &Row::from("xy".to_string()), &theme);
+        assert_eq!(
+            rendered(&mut screen),
+            "\u{1b}[2;1H\u{1b}[0mabcde\u{1b}[3;1Hxy\u{1b}[0m"
+        );
+
+        screen.set_row(0, &Row::from("abXde".to_string()), &theme);
+        screen.set_row(1, &Row::from("xy".to_string()), &theme);
+        assert_eq!(rendered(&mut screen), "\u{1b}[2;3H\u{1b}[0mX\u{1b}[0m");
+
+        screen.set_row(0, &Row::from("ab".to_string()), &theme);
+        assert_eq!(rendered(&mut screen), "\u{1b}[2;3H\u{1b}[0m   \u{1b}[0m");
+    }
+
+    /// Wide graphemes shall occupy multiple cells and be discarded if they do not fit.
+    #[test]
+    fn wide() {
+        let theme = Theme::default();
+        let mut screen = Screen::default();
+
+        screen.resize(1, 4);
+        screen.set_row(0, &Row::from("a世界".to_string()), &theme);
+        assert_eq!(rendered(&mut screen), "\u{1b}[2;1H\u{1b}[0ma世\u{1b}[0m");
+
+        screen.set_row(0, &Row::from("ab界".to_string()), &theme);
+        assert_eq!(rendered(&mut screen), "\u{1b}[2;2H\u{1b}[0mb界\u{1b}[0m");
+    }
+}
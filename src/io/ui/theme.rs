@@ -0,0 +1,381 @@
+//! Implements the styling of text according to its [`Scope`].
+use {
+    super::Scope,
+    core::convert::TryFrom,
+    crossterm::style::{Attribute, Attributes, Color},
+    fehler::{throw, throws},
+    serde::Deserialize,
+    std::{collections::HashMap, env, fs, io, path::Path},
+    thiserror::Error,
+};
+
+/// An error loading a [`Theme`].
+#[derive(Debug, Error)]
+pub enum LoadThemeError {
+    /// An error reading the theme file.
+    #[error("unable to read theme file: {0}")]
+    Read(#[from] io::Error),
+    /// An error deserializing the theme file.
+    #[error("theme file invalid format: {0}")]
+    Deserialize(#[from] toml::de::Error),
+}
+
+/// The appearance of text.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Style {
+    /// The color of the text.
+    ///
+    /// [`None`] indicates the default color of the terminal.
+    pub(crate) foreground: Option<ThemeColor>,
+    /// The color behind the text.
+    ///
+    /// [`None`] indicates the default color of the terminal.
+    pub(crate) background: Option<ThemeColor>,
+    /// The attributes of the text.
+    #[serde(deserialize_with = "deserialize_attributes")]
+    pub(crate) attributes: Attributes,
+}
+
+impl Style {
+    /// Creates a [`Style`] with a foreground of `color`.
+    fn foreground(color: Color) -> Self {
+        Self {
+            foreground: Some(ThemeColor(color)),
+            ..Self::default()
+        }
+    }
+}
+
+/// Deserializes a list of attribute names into [`Attributes`].
+fn deserialize_attributes<'de, D>(deserializer: D) -> Result<Attributes, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Vec::<TextAttribute>::deserialize(deserializer).map(|attributes| {
+        attributes
+            .into_iter()
+            .fold(Attributes::default(), |all, attribute| {
+                all | Attribute::from(attribute)
+            })
+    })
+}
+
+/// An attribute of text that is configurable by a theme.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum TextAttribute {
+    /// Increased intensity.
+    Bold,
+    /// Decreased intensity.
+    Dim,
+    /// Italicized.
+    Italic,
+    /// Underlined.
+    Underlined,
+    /// Foreground and background colors are swapped.
+    Reverse,
+    /// Crossed out.
+    CrossedOut,
+}
+
+impl From<TextAttribute> for Attribute {
+    #[inline]
+    fn from(value: TextAttribute) -> Self {
+        match value {
+            TextAttribute::Bold => Self::Bold,
+            TextAttribute::Dim => Self::Dim,
+            TextAttribute::Italic => Self::Italic,
+            TextAttribute::Underlined => Self::Underlined,
+            TextAttribute::Reverse => Self::Reverse,
+            TextAttribute::CrossedOut => Self::CrossedOut,
+        }
+    }
+}
+
+/// A [`Color`] that is configurable by a theme.
+///
+/// A color may be given as a name such as `"dark_cyan"`, an ANSI value such as `208` or a hex code such as `"#ff8700"`.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(try_from = "RawColor")]
+pub(crate) struct ThemeColor(pub(crate) Color);
+
+/// The representation of a [`ThemeColor`] within a theme file.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawColor {
+    /// An ANSI value.
+    Ansi(u8),
+    /// A name or hex code.
+    Text(String),
+}
+
+impl TryFrom<RawColor> for ThemeColor {
+    type Error = String;
+
+    #[inline]
+    fn try_from(value: RawColor) -> Result<Self, Self::Error> {
+        match value {
+            RawColor::Ansi(value) => Ok(Self(Color::AnsiValue(value))),
+            RawColor::Text(text) => text
+                .strip_prefix('#')
+                .and_then(|hex| {
+                    if hex.len() == 6 {
+                        u32::from_str_radix(hex, 16).ok()
+                    } else {
+                        None
+                    }
+                })
+                .map(|rgb| {
+                    let [_, r, g, b] = rgb.to_be_bytes();
+                    Color::Rgb { r, g, b }
+                })
+                .or_else(|| Color::try_from(text.as_str()).ok())
+                .map(Self)
+                .ok_or_else(|| format!("unknown color `{}`", text)),
+        }
+    }
+}
+
+/// Maps [`Scope`]s to the [`Style`]s with which they are displayed.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Theme {
+    /// The [`Style`] of each scope name.
+    styles: HashMap<String, Style>,
+}
+
+impl Theme {
+    /// Loads the [`Theme`] defined by the file at `path`.
+    ///
+    /// Scopes not defined by the file are styled as defined by the default [`Theme`]. If the file does not exist, the default [`Theme`] is returned.
+    #[throws(LoadThemeError)]
+    pub(crate) fn load(path: &Path) -> Self {
+        let mut theme = Self::default();
+
+        match fs::read_to_string(path) {
+            Ok(text) => theme
+                .styles
+                .extend(toml::from_str::<HashMap<String, Style>>(&text)?),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => throw!(error),
+        }
+
+        theme
+    }
+
+    /// Returns the [`Style`] of the most specific ancestor of `scope` that `self` defines.
+    pub(crate) fn style(&self, scope: &Scope) -> Style {
+        scope
+            .ancestry()
+            .find_map(|name| self.styles.get(name))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            styles: vec![
+                ("comment", Color::DarkGrey),
+                ("keyword", Color::Magenta),
+                ("string", Color::Green),
+                ("number", Color::DarkYellow),
+                ("constant", Color::DarkYellow),
+                ("operator", Color::Grey),
+                ("namespace", Color::Cyan),
+                ("type", Color::Yellow),
+                ("struct", Color::Yellow),
+                ("enum", Color::Yellow),
+                ("interface", Color::Yellow),
+                ("typeParameter", Color::Yellow),
+                ("builtinType", Color::Yellow),
+                ("function", Color::Blue),
+                ("macro", Color::Cyan),
+                ("lifetime", Color::DarkMagenta),
+                ("inlayHint", Color::DarkGrey),
+                ("ui.wrap", Color::DarkGrey),
+                ("ui.overflow", Color::DarkGrey),
+                ("ui.border", Color::DarkGrey),
+                ("ui.lineNumber", Color::DarkGrey),
+                ("ui.lineNumber.current", Color::Yellow),
+                ("ui.message.error", Color::Red),
+                ("ui.message.warning", Color::Yellow),
+            ]
+            .into_iter()
+            .map(|(name, color)| (name.to_string(), Style::foreground(color)))
+            .chain(
+                vec![
+                    ("ui.cursor", Attributes::from(Attribute::Reverse)),
+                    ("ui.status", Attributes::from(Attribute::Reverse)),
+                    (
+                        "ui.status.mode",
+                        Attributes::from(&[Attribute::Reverse, Attribute::Bold][..]),
+                    ),
+                ]
+                .into_iter()
+                .map(|(name, attributes)| {
+                    (
+                        name.to_string(),
+                        Style {
+                            attributes,
+                            ..Style::default()
+                        },
+                    )
+                }),
+            )
+            .collect(),
+        }
+    }
+}
+
+/// The number of colors that a terminal is able to display.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum ColorDepth {
+    /// The 16 named ANSI colors.
+    Ansi16,
+    /// The 256 ANSI colors.
+    Ansi256,
+    /// All 24-bit RGB colors.
+    TrueColor,
+}
+
+/// The RGB values of the 16 named ANSI colors, in order of their ANSI values.
+static ANSI16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::DarkRed, (128, 0, 0)),
+    (Color::DarkGreen, (0, 128, 0)),
+    (Color::DarkYellow, (128, 128, 0)),
+    (Color::DarkBlue, (0, 0, 128)),
+    (Color::DarkMagenta, (128, 0, 128)),
+    (Color::DarkCyan, (0, 128, 128)),
+    (Color::Grey, (192, 192, 192)),
+    (Color::DarkGrey, (128, 128, 128)),
+    (Color::Red, (255, 0, 0)),
+    (Color::Green, (0, 255, 0)),
+    (Color::Yellow, (255, 255, 0)),
+    (Color::Blue, (0, 0, 255)),
+    (Color::Magenta, (255, 0, 255)),
+    (Color::Cyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// The intensities of each component of the 6x6x6 color cube within the 256 ANSI colors.
+static CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorDepth {
+    /// Determines the [`ColorDepth`] of the current terminal from its environment.
+    pub(crate) fn detect() -> Self {
+        if env::var("COLORTERM")
+            .map(|value| value == "truecolor" || value == "24bit")
+            .unwrap_or(false)
+        {
+            Self::TrueColor
+        } else if env::var("TERM")
+            .map(|value| value.contains("256color"))
+            .unwrap_or(false)
+        {
+            Self::Ansi256
+        } else {
+            Self::Ansi16
+        }
+    }
+
+    /// Returns the [`Color`] closest to `color` that is able to be displayed at `self`.
+    pub(crate) fn convert(self, color: Color) -> Color {
+        match (self, color) {
+            (Self::Ansi16, Color::AnsiValue(value)) => nearest_ansi16(ansi256_to_rgb(value)),
+            (Self::Ansi16, Color::Rgb { r, g, b }) => nearest_ansi16((r, g, b)),
+            (Self::Ansi256, Color::Rgb { r, g, b }) => nearest_ansi256((r, g, b)),
+            (Self::Ansi16, _) | (Self::Ansi256, _) | (Self::TrueColor, _) => color,
+        }
+    }
+}
+
+/// Returns the RGB values of the ANSI color `value`.
+fn ansi256_to_rgb(value: u8) -> (u8, u8, u8) {
+    if let Some((_, rgb)) = ANSI16.get(usize::from(value)) {
+        *rgb
+    } else if value >= 232 {
+        let level = value
+            .saturating_sub(232)
+            .saturating_mul(10)
+            .saturating_add(8);
+        (level, level, level)
+    } else {
+        let index = usize::from(value.saturating_sub(16));
+        let level = |component: usize| CUBE_LEVELS.get(component % 6).copied().unwrap_or(0);
+
+        (level(index / 36), level(index / 6), level(index))
+    }
+}
+
+/// Returns the square of the distance between `a` and `b`.
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let component = |x: u8, y: u8| u32::from(x.max(y) - x.min(y)).pow(2);
+
+    component(a.0, b.0) + component(a.1, b.1) + component(a.2, b.2)
+}
+
+/// Returns the named ANSI color nearest to `rgb`.
+fn nearest_ansi16(rgb: (u8, u8, u8)) -> Color {
+    ANSI16
+        .iter()
+        .min_by_key(|(_, ansi)| distance(rgb, *ansi))
+        .map_or(Color::Reset, |(color, _)| *color)
+}
+
+/// Returns the ANSI color nearest to `rgb`, excluding the named colors.
+fn nearest_ansi256(rgb: (u8, u8, u8)) -> Color {
+    (16..=255)
+        .min_by_key(|value| distance(rgb, ansi256_to_rgb(*value)))
+        .map_or(Color::Reset, Color::AnsiValue)
+}
+
+/// Testing of the theme module.
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A theme file shall define styles that override the default theme.
+    #[test]
+    fn parse() {
+        let styles: HashMap<String, Style> = toml::from_str(
+            "[keyword]\nforeground = \"#ff8700\"\nbackground = 17\nattributes = [\"bold\", \"italic\"]\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            styles.get("keyword"),
+            Some(&Style {
+                foreground: Some(ThemeColor(Color::Rgb {
+                    r: 255,
+                    g: 135,
+                    b: 0
+                })),
+                background: Some(ThemeColor(Color::AnsiValue(17))),
+                attributes: Attributes::from(&[Attribute::Bold, Attribute::Italic][..]),
+            })
+        );
+        assert!(
+            toml::from_str::<HashMap<String, Style>>("[keyword]\nforeground = \"mauve\"\n")
+                .is_err()
+        );
+    }
+
+    /// Colors shall fall back to the nearest color the terminal is able to display.
+    #[test]
+    fn fallback() {
+        let orange = Color::Rgb {
+            r: 255,
+            g: 135,
+            b: 0,
+        };
+
+        assert_eq!(ColorDepth::TrueColor.convert(orange), orange);
+        assert_eq!(ColorDepth::Ansi256.convert(orange), Color::AnsiValue(208));
+        assert_eq!(ColorDepth::Ansi16.convert(orange), Color::Yellow);
+        assert_eq!(ColorDepth::Ansi16.convert(Color::AnsiValue(9)), Color::Red);
+        assert_eq!(ColorDepth::Ansi16.convert(Color::Blue), Color::Blue);
+    }
+}
@@ -21,14 +21,32 @@ use {
     core::option::Option,
     fehler::{throw, throws},
     io::{
+        config::{self, WriteConfigError},
         ConsumeInputError, ConsumeInputIssue, CreateInterfaceError, Interface, ProduceOutputError,
     },
     log::{error, info},
     logging::{InitLoggerError, LogConfig},
     market::{Consumer, Producer},
+    std::path::PathBuf,
     thiserror::Error as ThisError,
 };
 
+/// Writes a config file with the default settings to the config file of `arguments` and returns its path.
+///
+/// # Errors
+///
+/// If the config file already exists or is unable to be written, a [`WriteConfigError`] shall be thrown.
+///
+/// [`WriteConfigError`]: io/config/enum.WriteConfigError.html
+#[inline]
+#[throws(WriteConfigError)]
+pub fn write_config(arguments: &Arguments<'_>) -> PathBuf {
+    let path = config::config_file(arguments.config).ok_or(WriteConfigError::ConfigDir)?;
+
+    config::write_default_config(&path)?;
+    path
+}
+
 /// Arguments for [`Paper`] initialization.
 ///
 /// [`Paper`]: ../struct.Paper.html
@@ -40,6 +58,12 @@ pub struct Arguments<'a> {
     ///
     /// [`None`]: https://doc.rust-lang.org/core/option/enum.Option.html#variant.None
     pub file: Option<&'a str>,
+    /// The path of the config file.
+    ///
+    /// [`None`] indicates that `paper.toml` within the config directory is used.
+    ///
+    /// [`None`]: https://doc.rust-lang.org/core/option/enum.Option.html#variant.None
+    pub config: Option<&'a str>,
     /// The configuration of the logger.
     pub log_config: LogConfig,
 }
@@ -49,6 +73,7 @@ impl<'a> From<&'a ArgMatches<'a>> for Arguments<'a> {
     fn from(value: &'a ArgMatches<'a>) -> Self {
         Self {
             file: value.value_of("file"),
+            config: value.value_of("config"),
             log_config: LogConfig::from(value),
         }
     }
@@ -59,6 +84,7 @@ impl Default for Arguments<'_> {
     fn default() -> Self {
         Self {
             file: None,
+            config: None,
             log_config: LogConfig::default(),
         }
     }
@@ -97,7 +123,7 @@ impl Paper {
         logging::init(arguments.log_config)?;
 
         Self {
-            io: Interface::new(arguments.file)?,
+            io: Interface::new(arguments.file, arguments.config)?,
             processor: Processor::new(),
         }
     }
@@ -155,6 +181,9 @@ pub enum Failure {
     /// An error running `paper`.
     #[error(transparent)]
     Run(#[from] RunError),
+    /// An error writing the default config file.
+    #[error("Failed to write config file: {0}")]
+    WriteConfig(#[from] WriteConfigError),
 }
 
 /// An error creating a [`Paper`].
@@ -65,7 +65,9 @@ impl Log for Logger {
                     writeln!(
                         file,
                         "{} [{}]: {}",
-                        OffsetDateTime::now_local().format("%F %T"),
+                        OffsetDateTime::try_now_local()
+                            .unwrap_or_else(|_| OffsetDateTime::now_utc())
+                            .format("%F %T"),
                         record.level(),
                         record.args()
                     );
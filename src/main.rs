@@ -4,7 +4,7 @@ use {
     // https://github.com/clap-rs/clap/issues/1478 states that fix has been added to be released in 3.0.0.
     clap::{app_from_crate, crate_authors, crate_description, crate_name, crate_version, Arg},
     fehler::throws,
-    paper::{Failure, Paper},
+    paper::{Arguments, Failure, Paper},
 };
 
 #[throws(Failure)]
@@ -12,28 +12,45 @@ fn main() {
     // Forces compiler to rebuild when Cargo.toml file is changed, needed for app_from_crate.
     let _ = include_str!("../Cargo.toml");
 
-    Paper::new(
-        &(&app_from_crate!()
-            .arg(
-                Arg::with_name("log")
-                    .long("log")
-                    .value_name("COMPONENT")
-                    .possible_values(&["starship"])
-                    .help("Enables logs for components"),
-            )
-            .arg(
-                Arg::with_name("file")
-                    .value_name("FILE")
-                    .help("The file to be viewed"),
-            )
-            .arg(
-                Arg::with_name("verbose")
-                    .short("v")
-                    .multiple(true)
-                    .help("Increases the logging verbosity - can be repeated upto 3 times"),
-            )
-            .get_matches())
-            .into(),
-    )?
-    .run()?;
+    let matches = app_from_crate!()
+        .arg(
+            Arg::with_name("log")
+                .long("log")
+                .value_name("COMPONENT")
+                .possible_values(&["starship"])
+                .help("Enables logs for components"),
+        )
+        .arg(
+            Arg::with_name("config")
+                .long("config")
+                .value_name("PATH")
+                .help("The config file to be used instead of `paper.toml` in the config directory"),
+        )
+        .arg(
+            Arg::with_name("write-config")
+                .long("write-config")
+                .help("Writes a config file with the default settings, then exits"),
+        )
+        .arg(
+            Arg::with_name("file")
+                .value_name("FILE")
+                .help("The file to be viewed"),
+        )
+        .arg(
+            Arg::with_name("verbose")
+                .short("v")
+                .multiple(true)
+                .help("Increases the logging verbosity - can be repeated upto 3 times"),
+        )
+        .get_matches();
+    let arguments = Arguments::from(&matches);
+
+    if matches.is_present("write-config") {
+        println!(
+            "Wrote default config to {}",
+            paper::write_config(&arguments)?.display()
+        );
+    } else {
+        Paper::new(&arguments)?.run()?;
+    }
 }